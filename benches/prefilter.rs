@@ -0,0 +1,142 @@
+/*
+    Benchmark of --prefilter auto (cargo bench --bench prefilter)
+        - sweeps the library size, the spread of its lengths and the
+          levenshtein --max-distance d, the ranges the cutoffs of the
+          candidates module choose between
+        - every point is compared with --prefilter auto and none, and the
+          strategy auto picked is printed with both times and the ratio
+        - the matches of both are checked to be the same, auto never
+          drops one
+    Synthetic sequences from a fixed seed; half the inputs are library
+    ASOs with a few edits, so every run has matches near d.
+*/
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use aso_scramble_check::{Cli, Library, Query};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+const LIBRARY_SIZES: [usize; 5] = [25, 250, 2_000, 8_000, 32_000];
+/// Bases of length variation around INPUT_LENGTH
+const LENGTH_SPREADS: [usize; 3] = [0, 3, 10];
+const MAX_DISTANCES: [usize; 4] = [1, 2, 4, 8];
+const INPUT_LENGTH: usize = 20;
+const INPUTS: usize = 64;
+/// Best of this many runs per point
+const REPEATS: usize = 3;
+
+/// The strategy line auto logs, kept for the table
+struct StrategyLog(Mutex<Option<String>>);
+
+impl Log for StrategyLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        let message = record.args().to_string();
+        if let Some(strategy) = message.strip_prefix("Prefilter auto: ") {
+            *self.0.lock().unwrap() = strategy.split(',').next().map(String::from);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static STRATEGY: StrategyLog = StrategyLog(Mutex::new(None));
+
+/// Linear congruential generator, the same sequences on every run
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, below: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % below as u64) as usize
+    }
+
+    fn sequence(&mut self, length: usize) -> String {
+        (0..length).map(|_| ['A', 'C', 'G', 'T'][self.next(4)]).collect()
+    }
+
+    /// `seq` with `edits` substitutions, insertions or deletions
+    fn edited(&mut self, seq: &str, edits: usize) -> String {
+        let mut bases: Vec<char> = seq.chars().collect();
+        for _ in 0..edits {
+            let position = self.next(bases.len());
+            match self.next(3) {
+                0 => bases[position] = ['A', 'C', 'G', 'T'][self.next(4)],
+                1 => bases.insert(position, ['A', 'C', 'G', 'T'][self.next(4)]),
+                _ if bases.len() > 1 => { bases.remove(position); }
+                _ => {}
+            }
+        }
+        bases.into_iter().collect()
+    }
+}
+
+fn cli(prefilter: &str, max_distance: usize) -> Cli {
+    Cli::from_args(["aso_scramble_check", "--list-by", "levenshtein", "--prefilter", prefilter,
+                    "--max-distance", &max_distance.to_string()])
+        .expect("valid arguments")
+}
+
+/// Best time of comparing the inputs with the library, and the matches
+fn time(library: &[(&str, &str)], inputs: &[(&str, &str)], cli: &Cli) -> (Duration, Vec<(String, String)>) {
+    let library = Library::from_sequences(library, cli).expect("library");
+    let mut best = Duration::MAX;
+    let mut matches = Vec::new();
+    for _ in 0..REPEATS {
+        let query = Query::from_sequences(inputs, cli).expect("inputs");
+        let start = Instant::now();
+        let compared = query.compare(&library, cli);
+        best = best.min(start.elapsed());
+        matches = compared.iter()
+            .flat_map(|input| input.matches().iter()
+                .map(|hit| (input.name().to_string(), hit.library_aso().name().to_string())))
+            .collect();
+    }
+    matches.sort();
+    (best, matches)
+}
+
+fn main() {
+    log::set_logger(&STRATEGY).expect("logger");
+    log::set_max_level(LevelFilter::Info);
+    println!("{:>7}\t{:>6}\t{:>2}\t{:<16}\t{:>10}\t{:>10}\t{:>5}", "Library", "Spread", "d", "Auto strategy",
+             "Auto ms", "None ms", "Ratio");
+    for size in LIBRARY_SIZES {
+        for spread in LENGTH_SPREADS {
+            let mut random = Random(0x5eed ^ (size * 31 + spread) as u64);
+            let library: Vec<(String, String)> = (0..size)
+                .map(|i| {
+                    let length = INPUT_LENGTH - spread + random.next(2 * spread + 1);
+                    (format!("L{}", i), random.sequence(length))
+                })
+                .collect();
+            for max_distance in MAX_DISTANCES {
+                let inputs: Vec<(String, String)> = (0..INPUTS)
+                    .map(|i| match i % 2 {
+                        0 => {
+                            let (_, seq) = &library[random.next(size)];
+                            let edits = random.next(max_distance + 1);
+                            (format!("I{}", i), random.edited(seq, edits))
+                        }
+                        _ => (format!("I{}", i), random.sequence(INPUT_LENGTH)),
+                    })
+                    .collect();
+                let library: Vec<(&str, &str)> = library.iter().map(|(name, seq)| (name.as_str(), seq.as_str()))
+                    .collect();
+                let inputs: Vec<(&str, &str)> = inputs.iter().map(|(name, seq)| (name.as_str(), seq.as_str()))
+                    .collect();
+                *STRATEGY.0.lock().unwrap() = None;
+                let (auto, auto_matches) = time(&library, &inputs, &cli("auto", max_distance));
+                let strategy = STRATEGY.0.lock().unwrap().take().unwrap_or_default();
+                let (none, none_matches) = time(&library, &inputs, &cli("none", max_distance));
+                assert_eq!(auto_matches, none_matches, "auto dropped matches at {} {} {}", size, spread,
+                           max_distance);
+                println!("{:>7}\t{:>6}\t{:>2}\t{:<16}\t{:>10.1}\t{:>10.1}\t{:>5.2}", size, spread, max_distance,
+                         strategy, auto.as_secs_f64() * 1e3, none.as_secs_f64() * 1e3,
+                         auto.as_secs_f64() / none.as_secs_f64());
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+/*
+    Input files of any bytes load or fail with an error, never panic,
+    with and without --strict
+        - cargo fuzz run inputs
+*/
+#![no_main]
+use aso_scramble_check::{Cli, Query};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&strict, data)) = data.split_first() else { return };
+    let args: &[&str] = match strict % 2 {
+        0 => &["aso_scramble_check", "-m"],
+        _ => &["aso_scramble_check", "-m", "--strict"],
+    };
+    let cli = Cli::from_args(args).unwrap();
+    let _ = Query::from_reader(data, "fuzz", &cli);
+});
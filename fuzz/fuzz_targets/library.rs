@@ -0,0 +1,13 @@
+/*
+    Library files of any bytes: csv, FASTA, FASTQ, compressed or not,
+    in any encoding, load or fail with an error, never panic
+        - cargo fuzz run library
+*/
+#![no_main]
+use aso_scramble_check::{Cli, Library};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let cli = Cli::from_args(["aso_scramble_check"]).unwrap();
+    let _ = Library::from_reader(data, "fuzz", &cli);
+});
@@ -0,0 +1,20 @@
+/*
+    Every metric on any two strings returns a distance or an error,
+    never panics or NaN
+        - cargo fuzz run metrics
+*/
+#![no_main]
+use aso_scramble_check::{Dist, MetricOptions};
+use libfuzzer_sys::fuzz_target;
+
+const METRICS: [Dist; 11] = [Dist::Hamming, Dist::Levenshtein, Dist::Sift3, Dist::Damerau, Dist::Needleman,
+                             Dist::SmithWaterman, Dist::Qgram, Dist::Cosine, Dist::D2, Dist::D2s, Dist::Lcs];
+
+fuzz_target!(|data: (&str, &str)| {
+    let (a, b) = data;
+    for metric in METRICS {
+        if let Ok(distance) = metric.distance(a, b, &MetricOptions::default()) {
+            assert!(!distance.is_nan(), "{:?} of {:?} and {:?} is NaN", metric, a, b);
+        }
+    }
+});
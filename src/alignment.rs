@@ -0,0 +1,170 @@
+/*
+    Alignment metrics with configurable scores
+        - needleman: Needleman-Wunsch global alignment, linear gap score
+        - smith-waterman: Smith-Waterman local alignment, linear gap score,
+          for short inputs against longer or partially overlapping oligos
+    An alignment score grows with similarity, so it is reported as a
+    distance: the score of a perfect alignment of the longer sequence
+    (the shorter one for local alignment) minus the score of the best
+    alignment of the two. Identical sequences are at 0, as with the edit
+    distances, and so is an input contained in a longer library oligo.
+*/
+use clap::Args;
+use serde::Serialize;
+
+// Scores of the alignment metrics; a plain comment, a doc comment would
+// become the about text of the commandline it is flattened into
+#[derive(Args, Debug, Clone, Copy, Serialize)]
+pub struct AlignmentScores {
+    /// Alignment score of two equal bases (needleman, smith-waterman)
+    #[arg(long="match-score", default_value_t = 1.0, allow_negative_numbers = true)]
+    pub match_score: f32,
+    /// Alignment score of two different bases (needleman, smith-waterman)
+    #[arg(long="mismatch-score", default_value_t = -1.0, allow_negative_numbers = true)]
+    pub mismatch_score: f32,
+    /// Alignment score of a base against a gap (needleman, smith-waterman)
+    #[arg(long="gap-score", default_value_t = -2.0, allow_negative_numbers = true)]
+    pub gap_score: f32,
+}
+
+impl Default for AlignmentScores {
+    fn default() -> Self {
+        AlignmentScores { match_score: 1.0, mismatch_score: -1.0, gap_score: -2.0 }
+    }
+}
+
+impl AlignmentScores {
+    fn pair(&self, x: char, y: char) -> f32 {
+        if x == y { self.match_score } else { self.mismatch_score }
+    }
+}
+
+/// Best global alignment score of two sequences
+fn global_score(a: &[char], b: &[char], scores: &AlignmentScores) -> f32 {
+    let mut previous: Vec<f32> = (0..=b.len()).map(|j| j as f32 * scores.gap_score).collect();
+    let mut current = vec![0.0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i as f32 * scores.gap_score;
+        for j in 1..=b.len() {
+            current[j] = (previous[j - 1] + scores.pair(a[i - 1], b[j - 1]))
+                .max(previous[j] + scores.gap_score)
+                .max(current[j - 1] + scores.gap_score);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Needleman-Wunsch distance: perfect score of the longer sequence minus
+/// the best global alignment score
+pub fn needleman(a: &str, b: &str, scores: &AlignmentScores) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    a.len().max(b.len()) as f32 * scores.match_score - global_score(&a, &b, scores)
+}
+
+/// Best local alignment of two sequences
+pub struct LocalAlignment {
+    pub score: f32,
+    /// 1-based inclusive positions of the aligned region in each
+    /// sequence, None when no bases align with a positive score
+    pub regions: Option<((usize, usize), (usize, usize))>,
+}
+
+/// Smith-Waterman local alignment, the first best scoring region on ties
+pub fn local_alignment(a: &str, b: &str, scores: &AlignmentScores) -> LocalAlignment {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // score of the best alignment ending at each cell, and where it starts
+    let mut previous: Vec<(f32, (usize, usize))> = (0..=b.len()).map(|j| (0.0, (0, j))).collect();
+    let mut current = previous.clone();
+    let mut best = LocalAlignment { score: 0.0, regions: None };
+    for i in 1..=a.len() {
+        current[0] = (0.0, (i, 0));
+        for j in 1..=b.len() {
+            let start_of = |(score, start): (f32, (usize, usize)), cell: (usize, usize)| {
+                if score > 0.0 { start } else { cell }
+            };
+            let candidates = [
+                (previous[j - 1].0 + scores.pair(a[i - 1], b[j - 1]), start_of(previous[j - 1], (i - 1, j - 1))),
+                (previous[j].0 + scores.gap_score, start_of(previous[j], (i - 1, j))),
+                (current[j - 1].0 + scores.gap_score, start_of(current[j - 1], (i, j - 1))),
+            ];
+            current[j] = candidates.into_iter()
+                .fold((0.0, (i, j)), |chosen, candidate| if candidate.0 > chosen.0 { candidate } else { chosen });
+            if current[j].0 > best.score {
+                let (start_i, start_j) = current[j].1;
+                best = LocalAlignment {
+                    score: current[j].0,
+                    regions: Some(((start_i + 1, i), (start_j + 1, j))),
+                };
+            }
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    best
+}
+
+/// Smith-Waterman distance: perfect score of the shorter sequence minus
+/// the best local alignment score
+pub fn smith_waterman(a: &str, b: &str, scores: &AlignmentScores) -> f32 {
+    let shorter = a.chars().count().min(b.chars().count());
+    shorter as f32 * scores.match_score - local_alignment(a, b, scores).score
+}
+
+/// Score and aligned region of the best local alignment, e.g.
+/// score 12, input 1-12, library 4-15
+pub fn describe_region(input: &str, library: &str, scores: &AlignmentScores) -> String {
+    let local = local_alignment(input, library, scores);
+    match local.regions {
+        Some(((input_start, input_end), (library_start, library_end))) =>
+            format!("score {}, input {}-{}, library {}-{}", local.score, input_start, input_end,
+                    library_start, library_end),
+        None => String::from("-"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needleman_is_the_score_short_of_a_perfect_alignment() {
+        let scores = AlignmentScores::default();
+        assert_eq!(needleman("ACGT", "ACGT", &scores), 0.0);
+        // one mismatch: 3 - 1 = 2 against a perfect 4
+        assert_eq!(needleman("ACGT", "ACCT", &scores), 2.0);
+        // one gap: 3 - 2 = 1 against a perfect 4
+        assert_eq!(needleman("ACGT", "ACG", &scores), 3.0);
+        assert_eq!(needleman("ACGT", "", &scores), 12.0);
+    }
+
+    #[test]
+    fn needleman_uses_the_given_scores() {
+        let scores = AlignmentScores { match_score: 2.0, mismatch_score: -1.0, gap_score: -1.0 };
+        // A-A, C against a gap, G-G, T-T: 6 - 1 = 5 against a perfect 8
+        assert_eq!(needleman("ACGT", "AGT", &scores), 3.0);
+        // a mismatch costs 3, two gaps cost 2 + 2: the mismatch is taken
+        assert_eq!(needleman("AC", "AG", &scores), 3.0);
+    }
+
+    #[test]
+    fn smith_waterman_finds_an_input_inside_a_longer_oligo() {
+        let scores = AlignmentScores::default();
+        assert_eq!(smith_waterman("ACGT", "TTACGTTT", &scores), 0.0);
+        assert_eq!(describe_region("ACGT", "TTACGTTT", &scores), "score 4, input 1-4, library 3-6");
+        // five matches and one mismatch beat the shorter runs of matches
+        assert_eq!(smith_waterman("ACGTAC", "ACTTAC", &scores), 2.0);
+        assert_eq!(describe_region("ACGTAC", "ACTTAC", &scores), "score 4, input 1-6, library 1-6");
+    }
+
+    #[test]
+    fn smith_waterman_without_shared_bases_has_no_region() {
+        let scores = AlignmentScores::default();
+        let local = local_alignment("AAAA", "CCCC", &scores);
+        assert_eq!(local.score, 0.0);
+        assert!(local.regions.is_none());
+        assert_eq!(smith_waterman("AAAA", "CCCC", &scores), 4.0);
+        assert_eq!(describe_region("AAAA", "CCCC", &scores), "-");
+    }
+}
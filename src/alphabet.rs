@@ -0,0 +1,102 @@
+/*
+    Sequence alphabets
+        - dna: A C G T
+        - rna: A C G U
+        - iupac: DNA plus the IUPAC ambiguity codes R Y S W K M B D H V N
+        - custom: symbols and their complements, e.g. custom:ACGTX/TGCAX
+    The alphabet drives validation (--strict), composition profiling
+    (prefilter, composition delta, PWM, logos) and reverse complementation.
+*/
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alphabet {
+    name: String,
+    /// Valid symbols, in composition order
+    symbols: Vec<char>,
+    /// Complement of each symbol, same order as `symbols`
+    complements: Vec<char>,
+}
+
+impl Alphabet {
+    fn new(name: &str, symbols: &str, complements: &str) -> Result<Self, String> {
+        let symbols: Vec<char> = symbols.chars().collect();
+        let complements: Vec<char> = complements.chars().collect();
+        if symbols.is_empty() || symbols.len() != complements.len() {
+            return Err(String::from("An alphabet needs as many complements as symbols"))
+        }
+        if complements.iter().any(|c| !symbols.contains(c)) {
+            return Err(String::from("Every complement must itself be a symbol of the alphabet"))
+        }
+        Ok(Alphabet { name: name.to_string(), symbols, complements })
+    }
+
+    pub fn dna() -> Self {
+        Alphabet::new("dna", "ATGC", "TACG").unwrap()
+    }
+
+    pub fn rna() -> Self {
+        Alphabet::new("rna", "AUGC", "UACG").unwrap()
+    }
+
+    pub fn iupac() -> Self {
+        Alphabet::new("iupac", "ATGCRYSWKMBDHVN", "TACGYRSWMKVHDBN").unwrap()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.symbols.contains(&c)
+    }
+
+    pub fn complement(&self, c: char) -> Option<char> {
+        self.symbols.iter()
+            .position(|&s| s == c)
+            .map(|i| self.complements[i])
+    }
+
+    pub fn is_complement(&self, a: char, b: char) -> bool {
+        self.complement(a) == Some(b)
+    }
+
+    /// Symbols outside the alphabet are kept as they are
+    pub fn reverse_complement(&self, seq: &str) -> String {
+        seq.chars()
+            .rev()
+            .map(|c| self.complement(c).unwrap_or(c))
+            .collect()
+    }
+
+    /// Count of each symbol, in the order of `symbols`
+    pub fn composition(&self, seq: &str) -> Vec<usize> {
+        let mut counts = vec![0; self.symbols.len()];
+        for c in seq.chars() {
+            if let Some(i) = self.symbols.iter().position(|&s| s == c) {
+                counts[i] += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// dna, rna, iupac or custom:SYMBOLS/COMPLEMENTS
+pub fn parse_alphabet(s: &str) -> Result<Alphabet, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "dna" => Ok(Alphabet::dna()),
+        "rna" => Ok(Alphabet::rna()),
+        "iupac" => Ok(Alphabet::iupac()),
+        _ => {
+            let spec = s.strip_prefix("custom:")
+                .ok_or(format!("Unknown alphabet {}, expected dna, rna, iupac or custom:SYMBOLS/COMPLEMENTS", s))?;
+            let (symbols, complements) = spec.split_once('/')
+                .ok_or(String::from("Custom alphabet must be custom:SYMBOLS/COMPLEMENTS"))?;
+            Alphabet::new("custom", &symbols.to_ascii_uppercase(), &complements.to_ascii_uppercase())
+        }
+    }
+}
@@ -0,0 +1,300 @@
+/*
+    Extra per-match columns, computed only for reported matches
+        - 3'-end complementarity: longest run of complementary bases
+          anchored at the 3' terminus of either sequence (primer-dimer
+          like interactions, relevant for qPCR assay components)
+        - composition delta: per-symbol count difference of the match
+          relative to the input, e.g. A:+1,G:-1
+        - group: target/project of the library ASO, from --group-by
+        - disagreement: the lowest and highest metric when they are at least
+          --flag-disagreement apart, e.g. low hamming but high sift3, which
+          usually points at an alignment-register shift worth a look
+        - null z/p: distance against composition-preserving shuffles of the
+          match, see the null module
+        - local region: score of the best smith-waterman local alignment
+          and where it lies in the input and in the library ASO
+        - LCS: longest common subsequence length and its ratio to the
+          longer length, with --list-by lcs
+        - library: the library file of the match, with several
+          --library-aso-file
+    Extra per-input values, computed from the reported matches
+        - identical: the other inputs of a group of identical ones, see
+          the dedup module
+        - PWM score: a position weight matrix is built from the matches of
+          the same length as the input and the input is scored against it,
+          giving how typical the candidate is of its match family
+        - groups: matches summarized per target/project, e.g.
+          "3 matches, all from HTT"
+        - parent distance: distance to the parent ASO by every metric of
+          --list-by, see --parent
+        - verdict: PASS, REVIEW or FAIL, see gate::verdict
+        - balance: composition balance of the least even window, see the
+          balance module
+        - GC skew and longest runs: positional liabilities, see the
+          liabilities module
+        - duplex: longest run of base pairs between the two strands of a
+          --duplex input, and the library ASOs matched by either strand
+*/
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use crate::alphabet::Alphabet;
+use crate::{alignment, balance, gate, lcs, liabilities, mask, null};
+use crate::{AsoMatch, AsoProfile, Cli, Dist, Library};
+
+/// Longest antiparallel complementary run starting at the 3' terminal base
+/// of `a`, over every position of `b` it could anneal to
+fn anchored_run(a: &[char], b: &[char], alphabet: &Alphabet) -> usize {
+    (0..b.len())
+        .map(|start| {
+            a.iter().rev()
+                .zip(&b[start..])
+                .take_while(|(&x, &y)| alphabet.is_complement(x, y))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Longest 3'-anchored complementary run between two sequences
+pub fn three_prime_run(a: &str, b: &str, alphabet: &Alphabet) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    anchored_run(&a, &b, alphabet).max(anchored_run(&b, &a, alphabet))
+}
+
+/// Longest antiparallel complementary run between two sequences, at any
+/// offset, e.g. the paired region of a duplex with overhangs
+pub fn paired_run(a: &str, b: &str, alphabet: &Alphabet) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().rev().collect();
+    let mut longest = 0;
+    for shift in 0..a.len() + b.len() {
+        let (a_start, b_start) = (shift.saturating_sub(b.len()), b.len().saturating_sub(shift));
+        let mut run = 0;
+        for (&x, &y) in a[a_start..].iter().zip(&b[b_start..]) {
+            run = if alphabet.is_complement(x, y) { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+    }
+    longest
+}
+
+/// Per-symbol count difference of `b` relative to `a`, e.g. A:+1,G:-1
+pub fn composition_delta(a: &AsoProfile, b: &AsoProfile, alphabet: &Alphabet) -> String {
+    let delta: Vec<String> = alphabet.symbols().iter()
+        .zip(a.composition.iter().zip(b.composition.iter()))
+        .filter(|(_, (x, y))| x != y)
+        .map(|(symbol, (&x, &y))| format!("{}:{:+}", symbol, y as isize - x as isize))
+        .collect();
+    if delta.is_empty() {
+        String::from("0")
+    } else {
+        delta.join(",")
+    }
+}
+
+/// Total number of bases by which two compositions differ
+pub fn composition_difference(a: &[usize], b: &[usize]) -> usize {
+    a.iter().zip(b.iter()).map(|(x, y)| x.abs_diff(*y)).sum()
+}
+
+/// Matches needed before a position weight matrix means anything
+const MIN_PWM_MATCHES: usize = 2;
+
+/// Log-odds score (bits, against a uniform background) of the input
+/// under the PWM of its same-length matches, and the best possible score.
+/// Counts get a pseudocount of one per base.
+pub fn pwm_score(input: &AsoProfile, alphabet: &Alphabet) -> Option<(f32, f32)> {
+    let input_seq: Vec<char> = input.seq.chars().collect();
+    let family: Vec<Vec<char>> = input.aso_names.iter()
+        .map(|hit| hit.aso.seq.chars().collect::<Vec<char>>())
+        .filter(|seq| seq.len() == input_seq.len())
+        .collect();
+    if family.len() < MIN_PWM_MATCHES {
+        return None
+    }
+    let symbols = alphabet.symbols();
+    let background = 1.0 / symbols.len() as f32;
+    let total = family.len() as f32 + symbols.len() as f32;
+    let mut score = 0.0;
+    let mut max_score = 0.0;
+    for (position, &base) in input_seq.iter().enumerate() {
+        let log_odds = |b: char| {
+            let count = family.iter().filter(|seq| seq[position] == b).count() as f32;
+            ((count + 1.0) / total / background).log2()
+        };
+        score += log_odds(base);
+        max_score += symbols.iter().map(|&b| log_odds(b)).fold(f32::MIN, f32::max);
+    }
+    Some((score, max_score))
+}
+
+/// Lowest and highest scoring metric, when they are at least `spread` apart
+pub fn disagreement(hit: &AsoMatch, spread: f32) -> Option<String> {
+    let lowest = hit.scores.iter().min_by(|a, b| a.1.total_cmp(&b.1))?;
+    let highest = hit.scores.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    (highest.1 - lowest.1 >= spread)
+        .then(|| format!("{:?} {} vs {:?} {}", lowest.0, lowest.1, highest.0, highest.1))
+}
+
+/// Matches per group, largest group first
+pub fn group_summary(input: &AsoProfile) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for hit in &input.aso_names {
+        *counts.entry(hit.aso.group.as_deref().unwrap_or("(none)")).or_default() += 1;
+    }
+    let total = input.aso_names.len();
+    let plural = if total == 1 { "match" } else { "matches" };
+    match counts.len() {
+        0 => String::from("no matches"),
+        1 => format!("{} {}, all from {}", total, plural, counts.keys().next().unwrap()),
+        _ => {
+            let mut groups: Vec<(&str, usize)> = counts.into_iter().collect();
+            groups.sort_by_key(|(_, count)| Reverse(*count));
+            let listed: Vec<String> = groups.iter()
+                .map(|(group, count)| format!("{} ({})", group, count))
+                .collect();
+            format!("{} {}: {}", total, plural, listed.join(", "))
+        }
+    }
+}
+
+/// Distance of an input to its parent, e.g. "2" or with several metrics
+/// "Hamming 2, Levenshtein 2"
+pub fn parent_distance(input: &AsoProfile, parent: &str, cli: &Cli) -> String {
+    let metrics = cli.list_by.metrics();
+    let distances: Vec<String> = metrics.iter()
+        .map(|&metric| {
+            let distance = metric.distance(&input.seq, parent, &cli.metric_options)
+                .map_or(String::from("n/a"), |distance| metric.format(distance, cli.precision));
+            match metrics.len() {
+                1 => distance,
+                _ => format!("{:?} {}", metric, distance),
+            }
+        })
+        .collect();
+    distances.join(", ")
+}
+
+/// Fill in the extra values of an input, once its matches are final
+pub fn annotate_input(input: &mut AsoProfile, cli: &Cli) {
+    if let Some((_, others)) = input.members.split_first() {
+        input.annotations.push(("Identical", others.join(", ")));
+    }
+    if let Some(parent) = &input.parent {
+        let value = parent_distance(input, parent, cli);
+        input.annotations.push(("Parent distance", value));
+    }
+    if cli.pwm {
+        let value = match pwm_score(input, &cli.alphabet) {
+            Some((score, max_score)) => format!("{:.2} bits of {:.2} ({:.0}%)", score, max_score,
+                                                100.0 * score.max(0.0) / max_score),
+            None => String::from("n/a"),
+        };
+        input.annotations.push(("PWM score", value));
+    }
+    if cli.group_by.is_some() {
+        input.annotations.push(("Groups", group_summary(input)));
+    }
+    if let Some(min_balance) = cli.min_balance {
+        let value = balance::describe(&input.seq, cli.balance_window, min_balance, &cli.alphabet);
+        input.annotations.push(("Balance", value));
+    }
+    if cli.liabilities {
+        input.annotations.push(("GC skew", liabilities::describe_skew(&input.seq)));
+        input.annotations.push(("Longest runs", liabilities::describe_runs(&input.seq)));
+    }
+    if cli.verdict {
+        input.annotations.push(("Verdict", gate::verdict(input, cli).to_string()));
+    }
+}
+
+/// Add the duplex summary to both strands of every --duplex input, once
+/// the matches of both are final
+pub fn annotate_duplexes(inputs: &mut [AsoProfile], alphabet: &Alphabet) {
+    let mut matched: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for input in inputs.iter() {
+        if let Some((duplex, _)) = &input.duplex {
+            matched.entry(duplex.clone()).or_default()
+                .extend(input.aso_names.iter().map(|hit| hit.aso.name.clone()));
+        }
+    }
+    for input in inputs.iter_mut() {
+        let Some((duplex, partner)) = &input.duplex else {
+            continue
+        };
+        let value = format!("{} bp paired, {} library ASOs matched across strands",
+                            paired_run(&input.seq, partner, alphabet), matched[duplex].len());
+        input.annotations.push(("Duplex", value));
+    }
+}
+
+/// Names of the extra columns, in output order
+pub fn columns(cli: &Cli, library: &Library) -> Vec<&'static str> {
+    let mut columns = Vec::new();
+    if cli.three_prime {
+        columns.push("3' run");
+    }
+    if cli.composition_delta {
+        columns.push("Composition delta");
+    }
+    if cli.group_by.is_some() {
+        columns.push("Group");
+    }
+    if cli.flag_disagreement.is_some() {
+        columns.push("Disagreement");
+    }
+    if cli.null_shuffles.is_some() {
+        columns.push("Null z (p)");
+    }
+    if cli.list_by.metrics().contains(&Dist::SmithWaterman) {
+        columns.push("Local region");
+    }
+    if cli.list_by.metrics().contains(&Dist::Lcs) {
+        columns.push("LCS");
+    }
+    // filled in once the matches of every library are in, see compute_distance
+    if several_libraries(library) {
+        columns.push("Library");
+    }
+    columns
+}
+
+/// Whether matches are tagged with their library, also for a snapshot
+/// of several library files
+pub fn several_libraries(library: &Library) -> bool {
+    library.sources.len() > 1
+}
+
+/// Fill in the extra columns of a reported match
+pub fn annotate(hit: &mut AsoMatch, input: &AsoProfile, cli: &Cli) {
+    if cli.three_prime {
+        hit.annotations.push(("3' run", three_prime_run(&input.seq, &hit.aso.seq, &cli.alphabet).to_string()));
+    }
+    if cli.composition_delta {
+        hit.annotations.push(("Composition delta", composition_delta(input, &hit.aso, &cli.alphabet)));
+    }
+    if cli.group_by.is_some() {
+        hit.annotations.push(("Group", hit.aso.group.clone().unwrap_or_default()));
+    }
+    if let Some(spread) = cli.flag_disagreement {
+        let value = disagreement(hit, spread).unwrap_or_else(|| String::from("-"));
+        hit.annotations.push(("Disagreement", value));
+    }
+    if let Some(shuffles) = cli.null_shuffles {
+        let value = match null::null_model(input, hit, cli.list_by.primary(), &cli.metric_options,
+                                           shuffles, cli.null_seed) {
+            Some((z, p)) => format!("{:.2} ({:.3})", z, p),
+            None => String::from("n/a"),
+        };
+        hit.annotations.push(("Null z (p)", value));
+    }
+    if cli.list_by.metrics().contains(&Dist::SmithWaterman) {
+        let library_seq = mask::masked(input, &hit.aso.seq);
+        let value = alignment::describe_region(&input.seq, &library_seq, &cli.metric_options.scores);
+        hit.annotations.push(("Local region", value));
+    }
+    if cli.list_by.metrics().contains(&Dist::Lcs) {
+        hit.annotations.push(("LCS", lcs::describe(&input.seq, &mask::masked(input, &hit.aso.seq))));
+    }
+}
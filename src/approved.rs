@@ -0,0 +1,113 @@
+/*
+    Order file of approved candidates (export-approved subcommand)
+        - reads the results of a run, --output-format json or the manifest
+        - the verdict of a candidate is the one recorded with --verdict,
+          or PASS without matches and REVIEW with some
+        - a match at or below --max-distance makes it FAIL
+        - the strands screened for a candidate (<name>_rc with
+          --orientation both, <name>_passenger with --duplex) are not
+          ordered, the worst of their verdicts is the candidate's
+        - candidates with an --accept verdict (PASS by default) are
+          written as a csv order: name, sequence, scale, chemistry; a
+          group of identical inputs is one row per member
+        - the others are logged with their verdict, and counted on stderr
+    Nothing is ordered: the file is for the vendor's upload form.
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use clap::ValueEnum;
+use csv::WriterBuilder;
+use log::{info, warn};
+use crate::compare::RunResults;
+use crate::error::CheckError;
+use crate::manifest::InputSummary;
+
+/// From best to worst
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, ValueEnum)]
+pub enum Verdict {
+    Pass,
+    Review,
+    Warn,
+    Fail,
+}
+
+impl Verdict {
+    fn parse(label: &str) -> Option<Verdict> {
+        Verdict::from_str(label.trim(), true).ok()
+    }
+}
+
+pub struct ApprovalOptions<'a> {
+    pub accept: &'a [Verdict],
+    pub max_distance: Option<f32>,
+    pub scale: &'a str,
+    pub chemistry: &'a str,
+}
+
+fn verdict(input: &InputSummary, options: &ApprovalOptions) -> Result<Verdict, CheckError> {
+    if options.max_distance.is_some_and(|max| input.matches.iter().any(|hit| hit.distance <= max)) {
+        return Ok(Verdict::Fail)
+    }
+    match input.annotations.get("Verdict") {
+        Some(label) => Verdict::parse(label)
+            .ok_or(CheckError::Data(format!("{}: unknown verdict {:?}", input.name, label))),
+        None if input.match_count == 0 && input.matches.is_empty() => Ok(Verdict::Pass),
+        None => Ok(Verdict::Review),
+    }
+}
+
+/// Write the approved candidates of the results at `path` to `output`,
+/// stdout without it
+pub fn export_approved(path: &Path, output: Option<&Path>, options: &ApprovalOptions) -> Result<(), Box<dyn Error>> {
+    info!("Reading results from {:?}", path);
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open results file {:?}: {}", path, e)))?;
+    let run: RunResults = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| CheckError::Data(format!("{:?}: not a JSON results file or manifest: {}", path, e)))?;
+    let destination: Box<dyn Write> = match output {
+        Some(output) => Box::new(File::create(output)
+            .map_err(|e| CheckError::Io(format!("Unable to create order file {:?}: {}", output, e)))?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = WriterBuilder::new().from_writer(destination);
+    writer.write_record(["Name", "Sequence", "Scale", "Chemistry"])?;
+    // worst verdict of the strands of every candidate
+    let mut strands: HashMap<&str, Verdict> = HashMap::new();
+    for input in &run.results {
+        if let Some(candidate) = &input.strand_of {
+            let verdict = verdict(input, options)?;
+            let worst = strands.entry(candidate).or_insert(verdict);
+            *worst = (*worst).max(verdict);
+        }
+    }
+    let (mut approved, mut candidates) = (0, 0);
+    for input in run.results.iter().filter(|input| input.strand_of.is_none()) {
+        let own = verdict(input, options)?;
+        let names = match input.members.is_empty() {
+            true => std::slice::from_ref(&input.name),
+            false => input.members.as_slice(),
+        };
+        for name in names {
+            candidates += 1;
+            let verdict = strands.get(name.as_str()).map_or(own, |&strand| own.max(strand));
+            if !options.accept.contains(&verdict) {
+                info!("Not approved: {} ({:?}, {} matches)", name, verdict, input.match_count);
+                continue
+            }
+            writer.write_record([name.as_str(), input.seq.as_str(), options.scale, options.chemistry])?;
+            approved += 1;
+        }
+    }
+    match writer.flush() {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        other => other?,
+    }
+    if approved == 0 {
+        warn!("No candidate of {:?} was approved", path);
+    }
+    eprintln!("{} of {} candidates approved", approved, candidates);
+    Ok(())
+}
@@ -0,0 +1,55 @@
+/*
+    Composition balance of a candidate (--min-balance)
+        - the sequence is read in windows of --balance-window bases
+        - the balance of a window is the Shannon entropy of its base
+          counts, relative to the most a window of that size can have
+        - the balance of the sequence is its least balanced window, so a
+          run like all purines in the first half stands out even when the
+          overall composition looks fine
+    Unbalanced candidates are flagged, or with --reject-unbalanced not
+    screened at all.
+*/
+use crate::alphabet::Alphabet;
+
+/// Balance (0-1) of the least balanced window, and that window's 0-based start
+pub fn balance(seq: &str, window: usize, alphabet: &Alphabet) -> (f32, usize) {
+    let bases: Vec<char> = seq.chars().collect();
+    let window = window.clamp(1, bases.len().max(1));
+    let symbols = alphabet.symbols();
+    // the most even split of `window` bases over the alphabet
+    let best = entropy(&even_counts(window, symbols.len()), window);
+    if bases.is_empty() || best == 0.0 {
+        return (1.0, 0)
+    }
+    (0..=bases.len() - window)
+        .map(|start| {
+            let counts: Vec<usize> = symbols.iter()
+                .map(|&symbol| bases[start..start + window].iter().filter(|&&b| b == symbol).count())
+                .collect();
+            (entropy(&counts, window) / best, start)
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap_or((1.0, 0))
+}
+
+fn even_counts(total: usize, symbols: usize) -> Vec<usize> {
+    (0..symbols).map(|i| total / symbols + usize::from(i < total % symbols)).collect()
+}
+
+fn entropy(counts: &[usize], total: usize) -> f32 {
+    counts.iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f32 / total as f32;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Balance value of an input, e.g. "0.50 at 1-8 (low)"
+pub fn describe(seq: &str, window: usize, min_balance: f32, alphabet: &Alphabet) -> String {
+    let (score, start) = balance(seq, window, alphabet);
+    let end = (start + window).min(seq.chars().count());
+    let low = if score < min_balance { " (low)" } else { "" };
+    format!("{:.2} at {}-{}{}", score, start + 1, end, low)
+}
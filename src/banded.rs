@@ -0,0 +1,53 @@
+/*
+    Banded Levenshtein distance with early termination (--max-distance)
+        - only cells within `max` of the diagonal can lead to a distance of
+          at most `max`, so each row of the table is computed in that band
+        - stops as soon as every cell of a row is beyond the cutoff, and
+          right away when the lengths alone differ by more
+    Dissimilar pairs, most of a library, cost a few rows instead of the
+    full table. Pairs the bit-parallel algorithm handles use it instead,
+    with the same kind of cutoff, see the myers module.
+*/
+use crate::iupac::{self, Ambiguity};
+use crate::myers;
+
+/// Levenshtein distance of two sequences, None when it is above `max`
+pub fn levenshtein_within(a: &str, b: &str, max: usize, ambiguity: Ambiguity) -> Option<usize> {
+    if myers::fits(a, b) {
+        return myers::levenshtein_within(a, b, max, ambiguity)
+    }
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None
+    }
+    // any value above the cutoff is as good as another
+    let beyond = max + 1;
+    let mut previous: Vec<usize> = (0..=b.len()).map(|j| j.min(beyond)).collect();
+    let mut current = vec![beyond; b.len() + 1];
+    for i in 1..=a.len() {
+        let first = i.saturating_sub(max).max(1);
+        let last = (i + max).min(b.len());
+        current[0] = i.min(beyond);
+        // the cell left of the band, outside it
+        if first > 1 {
+            current[first - 1] = beyond;
+        }
+        let mut row_min = if first == 1 { current[0] } else { beyond };
+        for j in first..=last {
+            let substitution = previous[j - 1] + !iupac::compatible(a[i - 1], b[j - 1], ambiguity) as usize;
+            let cell = substitution.min(previous[j] + 1).min(current[j - 1] + 1).min(beyond);
+            current[j] = cell;
+            row_min = row_min.min(cell);
+        }
+        // the next row reads one cell past this band
+        if last < b.len() {
+            current[last + 1] = beyond;
+        }
+        if row_min > max {
+            return None
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
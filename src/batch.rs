@@ -0,0 +1,188 @@
+/*
+    Batch job files
+        - TOML, one [[job]] table per check
+        - keys are the long commandline options (library-aso-file,
+          input-aso-file, list-by, ...), plus an optional name
+        - jobs sharing a library and its parsing options load it once
+        - groups of jobs run sequentially, or in parallel with --parallel
+        - a named pipe can back only one library group or one input
+    Example:
+        [[job]]
+        name = "gapmers"
+        library-aso-file = "library.csv"
+        multiple-aso-seq = true
+        input-aso-file = "gapmers.csv"
+        list-by = "all"
+*/
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use clap::CommandFactory;
+use log::info;
+use serde::Deserialize;
+use toml::{Table, Value};
+use crate::columns::ColumnSpec;
+use crate::conflicts::OnNameConflict;
+use crate::dialect::DialectArgs;
+use crate::fasta::FileFormat;
+use crate::locale::CsvLocale;
+use crate::output;
+use crate::softmask::Softmask;
+use crate::{check, cli_from_matches, read_once, CheckOutcome, Cli, Library, LibraryOrientation, OnEmpty};
+
+/// Exit code when at least one job failed to run
+const JOB_FAILED_EXIT_CODE: i32 = 1;
+
+#[derive(Deserialize)]
+struct JobFile {
+    job: Vec<Table>,
+}
+
+struct Job {
+    name: String,
+    cli: Cli,
+}
+
+/// Rendered results of a job, or why it failed
+struct JobOutcome {
+    output: Vec<u8>,
+    result: Result<CheckOutcome, String>,
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Turn a job table into commandline arguments
+fn job_args(table: &Table, index: usize) -> (String, Vec<String>) {
+    let mut name = format!("job{}", index + 1);
+    let mut args = vec![String::from(env!("CARGO_PKG_NAME"))];
+    for (key, value) in table {
+        if key == "name" {
+            name = value_text(value);
+            continue
+        }
+        let flag = format!("--{}", key);
+        match value {
+            Value::Boolean(true) => args.push(flag),
+            Value::Boolean(false) => {}
+            Value::Array(items) => {
+                args.push(flag);
+                args.push(items.iter().map(value_text).collect::<Vec<_>>().join(","));
+            }
+            other => {
+                args.push(flag);
+                args.push(value_text(other));
+            }
+        }
+    }
+    (name, args)
+}
+
+/// Jobs can share a library only if it would be parsed the same way
+type LibraryKey = ((Vec<PathBuf>, Option<PathBuf>), (bool, Option<usize>, FileFormat, Option<String>), (bool, OnNameConflict, Option<ColumnSpec>, Option<ColumnSpec>),
+                   OnEmpty, Option<String>, (CsvLocale, DialectArgs), LibraryOrientation, bool, Option<usize>, Option<Softmask>, bool);
+
+fn library_key(cli: &Cli) -> LibraryKey {
+    ((cli.library_aso_file.clone(), cli.library_snapshot.clone()), (cli.library_header_status, cli.library_expect_columns, cli.library_format,
+                                                     cli.sheet.clone()),
+     (cli.strict, cli.on_name_conflict, cli.columns.name_column.clone(), cli.columns.seq_column.clone()),
+     cli.on_empty, cli.group_by.clone(),
+     (cli.csv_locale, cli.dialect), cli.library_orientation,
+     cli.sanitize_names, cli.max_name_length, cli.softmask, cli.no_rna_normalization)
+}
+
+fn run_group(jobs: &[Job], group: &[usize]) -> Vec<(usize, JobOutcome)> {
+    let library = match Library::load(&jobs[group[0]].cli) {
+        Ok(library) => library,
+        Err(e) => {
+            return group.iter()
+                .map(|&i| (i, JobOutcome { output: vec![], result: Err(e.to_string()) }))
+                .collect()
+        }
+    };
+    group.iter()
+        .map(|&i| {
+            info!("Running job {}", jobs[i].name);
+            let mut output = Vec::new();
+            let result = check(&jobs[i].cli, &library, None, &mut output).map_err(|e| e.to_string());
+            (i, JobOutcome { output, result })
+        })
+        .collect()
+}
+
+/// Run every job in the file and return the process exit code
+pub fn run_jobs(path: &Path, parallel: bool) -> Result<i32, Box<dyn Error>> {
+    let job_file: JobFile = toml::from_str(&fs::read_to_string(path)?)?;
+    let mut jobs = Vec::new();
+    for (index, table) in job_file.job.iter().enumerate() {
+        let (name, args) = job_args(table, index);
+        let matches = Cli::command().try_get_matches_from(args)
+            .map_err(|e| format!("Job {}: {}", name, e))?;
+        let cli = cli_from_matches(&matches).map_err(|e| format!("Job {}: {}", name, e))?;
+        if cli.command.is_some() || cli.tui || cli.scan_file.is_some() || cli.pairs.is_some()
+            || cli.focus.is_some() {
+            return Err(format!("Job {}: subcommands, --tui, --scan-file, --pairs and --focus can't be used in a job file",
+                               name).into())
+        }
+        jobs.push(Job { name, cli });
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, job) in jobs.iter().enumerate() {
+        match groups.iter_mut().find(|g| library_key(&jobs[g[0]].cli) == library_key(&job.cli)) {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    let libraries = groups.iter().flat_map(|g| jobs[g[0]].cli.library_aso_file.iter().map(PathBuf::as_path)
+        .chain(jobs[g[0]].cli.library_snapshot.as_deref()));
+    let inputs = jobs.iter()
+        .filter(|job| job.cli.multiple_aso)
+        .filter_map(|job| job.cli.input_aso_file.as_deref());
+    read_once(libraries.chain(inputs))?;
+    info!("Running {} jobs using {} libraries", jobs.len(), groups.len());
+    let finished: Vec<(usize, JobOutcome)> = if parallel {
+        thread::scope(|scope| {
+            let handles: Vec<_> = groups.iter()
+                .map(|group| scope.spawn(|| run_group(&jobs, group)))
+                .collect();
+            handles.into_iter()
+                .flat_map(|handle| handle.join().expect("Batch worker panicked"))
+                .collect()
+        })
+    } else {
+        groups.iter().flat_map(|group| run_group(&jobs, group)).collect()
+    };
+    let mut outcomes: Vec<Option<JobOutcome>> = jobs.iter().map(|_| None).collect();
+    for (i, outcome) in finished {
+        outcomes[i] = Some(outcome);
+    }
+    let mut exit_code = 0;
+    let mut stdout = io::stdout().lock();
+    for (job, outcome) in jobs.iter().zip(outcomes) {
+        let outcome = outcome.expect("Job was not run");
+        if let Some(output_path) = &job.cli.output {
+            output::write_output(output_path, &outcome.output, job.cli.append)?;
+        } else {
+            if !job.cli.raw {
+                writeln!(stdout, "# job: {}", job.name)?;
+            }
+            stdout.write_all(&outcome.output)?;
+        }
+        match outcome.result {
+            Ok(outcome) if exit_code == 0 => exit_code = outcome.exit_code(),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Job {} failed: {}", job.name, e);
+                exit_code = JOB_FAILED_EXIT_CODE;
+            }
+        }
+    }
+    stdout.flush()?;
+    Ok(exit_code)
+}
@@ -0,0 +1,158 @@
+/*
+    Calibration against known cross-reactivity (calibrate subcommand)
+        - a training csv of sequence pairs with known outcomes: sequence
+          in column1, sequence in column2, outcome in column3 (1/0,
+          yes/no, true/false, positive/negative)
+        - every metric is fitted a max-distance: a pair is called
+          cross-reactive at or below it, and the cutoff kept is the one
+          with the best Youden J (sensitivity + specificity - 1), the more
+          sensitive one on a tie
+        - the best fit among the metrics that apply to every pair is
+          written to a TOML profile, with the fits of all metrics; --preset
+          calibrated --calibration profile.toml screens with it
+    Sequences are compared uppercased, with the default metric parameters
+    (qgram size, alignment scores), as written otherwise.
+*/
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use csv::ReaderBuilder;
+use log::info;
+use serde::{Deserialize, Serialize};
+use crate::clean;
+use crate::encoding::DecodingReader;
+use crate::error::CheckError;
+use crate::{Dist, MetricOptions};
+
+/// Fitted cutoff of one metric
+#[derive(Serialize, Deserialize)]
+struct Fit {
+    metric: Dist,
+    max_distance: f32,
+    /// Training pairs the metric applies to
+    pairs: usize,
+    sensitivity: f64,
+    specificity: f64,
+    youden: f64,
+}
+
+/// Calibrated profile, read by --preset calibrated
+#[derive(Serialize, Deserialize)]
+pub struct Profile {
+    pub metric: Dist,
+    pub max_distance: f32,
+    training_file: PathBuf,
+    fits: Vec<Fit>,
+}
+
+/// Read a profile written by `calibrate`
+pub fn load(path: &Path) -> Result<Profile, Box<dyn Error>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| CheckError::Io(format!("Unable to read calibration profile {:?}: {}", path, e)))?;
+    Ok(toml::from_str(&text)
+        .map_err(|e| CheckError::Data(format!("{:?}: not a calibration profile: {}", path, e)))?)
+}
+
+fn outcome(field: &str) -> Option<bool> {
+    match field.trim().to_ascii_lowercase().as_str() {
+        "1" | "yes" | "y" | "true" | "positive" => Some(true),
+        "0" | "no" | "n" | "false" | "negative" => Some(false),
+        _ => None,
+    }
+}
+
+fn sequence(field: &str) -> String {
+    let (field, _) = clean::clean_field(field);
+    field.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+/// Cutoff with the best Youden J for `scored` (distance, cross-reactive)
+/// pairs, None without both outcomes
+fn fit(metric: Dist, mut scored: Vec<(f32, bool)>) -> Option<Fit> {
+    let positives = scored.iter().filter(|(_, positive)| *positive).count();
+    let negatives = scored.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return None
+    }
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut best: Option<Fit> = None;
+    let (mut true_positives, mut false_positives) = (0, 0);
+    for (i, &(distance, positive)) in scored.iter().enumerate() {
+        match positive {
+            true => true_positives += 1,
+            false => false_positives += 1,
+        }
+        // a cutoff between two equal distances can't be applied
+        if scored.get(i + 1).is_some_and(|next| next.0 == distance) {
+            continue
+        }
+        let sensitivity = true_positives as f64 / positives as f64;
+        let specificity = (negatives - false_positives) as f64 / negatives as f64;
+        let youden = sensitivity + specificity - 1.0;
+        if best.as_ref().is_none_or(|best| youden >= best.youden) {
+            best = Some(Fit { metric, max_distance: distance, pairs: scored.len(), sensitivity, specificity, youden });
+        }
+    }
+    best
+}
+
+/// Fit every metric to the training pairs of `path`, print the fits and
+/// write the best to `profile_path`
+pub fn calibrate(path: &Path, header: bool, profile_path: &Path) -> Result<(), Box<dyn Error>> {
+    info!("Calibrating against the pairs of {:?}", path);
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open training file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut reader = ReaderBuilder::new()
+        .has_headers(header)
+        .flexible(true)
+        .from_reader(DecodingReader::new(file, &label)?);
+    let mut pairs = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |pos| pos.line());
+        let (Some(a), Some(b), Some(known)) = (record.get(0), record.get(1), record.get(2)) else {
+            return Err(CheckError::Data(format!("{}:{}: expected sequence, sequence and outcome columns", label,
+                                                line)).into())
+        };
+        let known = outcome(known)
+            .ok_or(CheckError::Data(format!("{}:{}: outcome {:?} is not 1/0, yes/no or true/false", label, line,
+                                            known)))?;
+        pairs.push((sequence(a), sequence(b), known));
+    }
+    let options = MetricOptions::default();
+    let fits: Vec<Fit> = Dist::All.metrics().into_iter()
+        .filter_map(|metric| {
+            let scored = pairs.iter()
+                .filter_map(|(a, b, known)| metric.distance(a, b, &options).ok().map(|distance| (distance, *known)))
+                .collect();
+            fit(metric, scored)
+        })
+        .collect();
+    let best = fits.iter()
+        .filter(|fit| fit.pairs == pairs.len())
+        .fold(None, |best: Option<&Fit>, fit| match best {
+            Some(best) if best.youden >= fit.youden => Some(best),
+            _ => Some(fit),
+        })
+        .ok_or(CheckError::Data(format!("{}: calibration needs cross-reactive and non-reactive pairs", label)))?;
+    let profile = Profile {
+        metric: best.metric,
+        max_distance: best.max_distance,
+        training_file: path.to_path_buf(),
+        fits: vec![],
+    };
+    let mut out = io::stdout().lock();
+    writeln!(out, "Metric\tMax distance\tPairs\tSensitivity\tSpecificity\tYouden J")?;
+    for fit in &fits {
+        writeln!(out, "{:?}\t{}\t{}\t{:.3}\t{:.3}\t{:.3}", fit.metric, fit.max_distance, fit.pairs, fit.sensitivity,
+                 fit.specificity, fit.youden)?;
+    }
+    writeln!(out, "{} pairs: calibrated {:?} <= {}, written to {:?}", pairs.len(), profile.metric,
+             profile.max_distance, profile_path)?;
+    let profile = Profile { fits, ..profile };
+    fs::write(profile_path, toml::to_string(&profile)?)
+        .map_err(|e| CheckError::Io(format!("Unable to write calibration profile {:?}: {}", profile_path, e)))?;
+    Ok(())
+}
@@ -0,0 +1,351 @@
+/*
+    Candidate generation for --prefilter auto
+        - auto reports what none would, every library ASO within the
+          cutoffs, but with a levenshtein --max-distance d only the library
+          ASOs that can be within d of an input are compared
+        - the strategy is picked once per run from the library size, its
+          length spread, the input lengths and d, and logged:
+            exhaustive: every library ASO (small libraries, large d)
+            composition gate: library ASOs within d bases of the input's
+              length whose symbol counts d edits can turn into the input's
+            k-mer index: library ASOs sharing enough k-mers with the input;
+              d edits leave at least len - k + 1 - k*d of its k-mers intact,
+              and within d bases of its length
+            BK-tree: a metric tree of the library, branches that can't hold
+              a sequence within d are never visited
+        - none of them drops a match: inputs the pruning can't be exact for
+          (masked, soft-masked, ambiguity-aware, no cutoff) are compared
+          with every library ASO
+*/
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use log::info;
+use rayon::prelude::*;
+use crate::iupac::Ambiguity;
+use crate::softmask::Softmask;
+use crate::{myers, AsoProfile, Cli, Dist};
+
+// The cutoffs below come from benches/prefilter.rs (cargo bench --bench
+// prefilter), 20-base inputs against libraries of 25 to 32,000 ASOs.
+
+/// Below this many library ASOs every run is exhaustive; the k-mer index
+/// was already twice as fast as none at 25, the smallest measured
+const MIN_INDEXED_LIBRARY: usize = 25;
+/// The composition gate is picked when lengths alone leave at most this
+/// fraction (20 times faster than none at 0.24)
+const COMPOSITION_GATE_FRACTION: f64 = 0.25;
+/// or when d is at most this fraction of the length: up to twice as fast
+/// as none at 0.2, no faster at 0.4
+const COMPOSITION_GATE_DISTANCE: f64 = 0.25;
+/// Shortest and longest k of the k-mer index; short k-mers are too common
+const MIN_INDEX_K: usize = 4;
+const MAX_INDEX_K: usize = 12;
+/// The BK-tree is picked when d is at most this fraction of the length:
+/// 3 times faster than none at 0.1, slower at 0.2
+const BK_TREE_FRACTION: f64 = 0.1;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Strategy {
+    Exhaustive,
+    CompositionGate,
+    KmerIndex(usize),
+    BkTree,
+}
+
+/// Library ASOs each input is compared with, in library order; None for
+/// every library ASO
+pub struct Candidates {
+    per_input: Vec<Option<Vec<usize>>>,
+}
+
+impl Candidates {
+    pub fn new(library: &[Arc<AsoProfile>], inputs: &[AsoProfile], cli: &Cli) -> Candidates {
+        let cutoffs: Vec<Option<usize>> = inputs.iter().map(|input| cutoff(input, cli)).collect();
+        let strategy = choose(library, inputs, &cutoffs);
+        let per_input = match strategy {
+            Strategy::Exhaustive => vec![None; inputs.len()],
+            Strategy::CompositionGate => {
+                let by_length = length_index(library);
+                inputs.par_iter().zip(&cutoffs)
+                    .map(|(input, cutoff)| cutoff.map(|max| by_length
+                        .range(input.aso_len.saturating_sub(max)..=input.aso_len + max)
+                        .flat_map(|(_, entries)| entries.iter().copied())
+                        .filter(|&entry| composition_edits(&input.composition, &library[entry].composition) <= max)
+                        .collect::<Vec<usize>>()))
+                    .map(|candidates| candidates.map(sorted))
+                    .collect()
+            }
+            Strategy::KmerIndex(k) => {
+                let index = KmerIndex::new(library, k);
+                inputs.par_iter().zip(&cutoffs)
+                    .map(|(input, cutoff)| cutoff.and_then(|max| index.candidates(library, &input.seq, max)))
+                    .collect()
+            }
+            Strategy::BkTree => {
+                let tree = BkTree::new(library);
+                inputs.par_iter().zip(&cutoffs)
+                    .map(|(input, cutoff)| cutoff.map(|max| sorted(tree.within(&input.seq, max))))
+                    .collect()
+            }
+        };
+        let pruned = per_input.iter().flatten().count();
+        let compared: usize = per_input.iter()
+            .map(|candidates| candidates.as_ref().map_or(library.len(), Vec::len))
+            .sum();
+        info!("Prefilter auto: {}, {} of {} inputs pruned, {} of {} pairs compared", describe(strategy),
+              pruned, inputs.len(), compared, inputs.len() * library.len());
+        Candidates { per_input }
+    }
+
+    /// Library indices in `range` the input is compared with
+    pub fn within(&self, input: usize, range: std::ops::Range<usize>) -> Box<dyn Iterator<Item = usize> + '_> {
+        match &self.per_input[input] {
+            None => Box::new(range),
+            Some(candidates) => {
+                let start = candidates.partition_point(|&i| i < range.start);
+                let end = candidates.partition_point(|&i| i < range.end);
+                Box::new(candidates[start..end].iter().copied())
+            }
+        }
+    }
+}
+
+fn describe(strategy: Strategy) -> String {
+    match strategy {
+        Strategy::Exhaustive => String::from("exhaustive"),
+        Strategy::CompositionGate => String::from("composition gate"),
+        Strategy::KmerIndex(k) => format!("{}-mer index", k),
+        Strategy::BkTree => String::from("BK-tree"),
+    }
+}
+
+fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+    indices.sort_unstable();
+    indices
+}
+
+/// The levenshtein cutoff of an input, when pruning by it is exact
+fn cutoff(input: &AsoProfile, cli: &Cli) -> Option<usize> {
+    let exact = cli.list_by.primary() == Dist::Levenshtein
+        && input.mask.is_empty()
+        && cli.softmask.is_none_or(|softmask| softmask == Softmask::None)
+        && cli.metric_options.ambiguity == Ambiguity::Strict
+        && input.seq.is_ascii();
+    input.max_distance.filter(|&max| exact && max >= 0.0).map(|max| max as usize)
+}
+
+/// The strategy predicted fastest for the run, see the cutoffs above: the
+/// k-mer index when the inputs are long enough for k-mers of MIN_INDEX_K
+/// bases to survive d edits, then the composition gate when lengths alone
+/// rule out most of the library or d is small for the length, then the
+/// BK-tree (also for sequences that aren't ASCII) for a smaller d
+fn choose(library: &[Arc<AsoProfile>], inputs: &[AsoProfile], cutoffs: &[Option<usize>]) -> Strategy {
+    let mut maxima: Vec<usize> = cutoffs.iter().flatten().copied().collect();
+    if library.len() < MIN_INDEXED_LIBRARY || maxima.is_empty() {
+        return Strategy::Exhaustive
+    }
+    maxima.sort_unstable();
+    let max = maxima[maxima.len() / 2];
+    // k-mers short enough for the shortest input to keep one through d edits
+    let shortest = inputs.iter().zip(cutoffs)
+        .filter(|(_, cutoff)| cutoff.is_some())
+        .map(|(input, _)| input.aso_len)
+        .min()
+        .unwrap_or(0);
+    let k = (shortest / (max + 1)).min(MAX_INDEX_K);
+    // k-mers and lengths are taken over bytes
+    let ascii = library.iter().all(|aso| aso.seq.is_ascii());
+    if ascii && k >= MIN_INDEX_K {
+        return Strategy::KmerIndex(k)
+    }
+    let mut lengths: Vec<usize> = library.iter().map(|aso| aso.aso_len).collect();
+    lengths.sort_unstable();
+    let median = lengths[lengths.len() / 2];
+    // share of the library within reach of a median-length input
+    let reachable = lengths.iter().filter(|&&length| length.abs_diff(median) <= max).count();
+    if ascii && ((reachable as f64) <= COMPOSITION_GATE_FRACTION * library.len() as f64
+                 || (max as f64) <= COMPOSITION_GATE_DISTANCE * median as f64) {
+        return Strategy::CompositionGate
+    }
+    if (max as f64) <= BK_TREE_FRACTION * median as f64 {
+        return Strategy::BkTree
+    }
+    Strategy::Exhaustive
+}
+
+/// Fewest edits between two sequences of these symbol counts: each edit
+/// adds at most one symbol and removes at most one
+fn composition_edits(a: &[usize], b: &[usize]) -> usize {
+    let (mut surplus, mut deficit) = (0, 0);
+    for (&a, &b) in a.iter().zip(b) {
+        surplus += a.saturating_sub(b);
+        deficit += b.saturating_sub(a);
+    }
+    surplus.max(deficit)
+}
+
+fn length_index(library: &[Arc<AsoProfile>]) -> BTreeMap<usize, Vec<usize>> {
+    let mut by_length: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, aso) in library.iter().enumerate() {
+        by_length.entry(aso.aso_len).or_default().push(i)
+    }
+    by_length
+}
+
+/// Library ASOs containing each k-mer, each listed once
+struct KmerIndex<'a> {
+    k: usize,
+    postings: HashMap<&'a [u8], Vec<usize>>,
+}
+
+impl<'a> KmerIndex<'a> {
+    fn new(library: &'a [Arc<AsoProfile>], k: usize) -> Self {
+        let mut postings: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        for (i, aso) in library.iter().enumerate() {
+            for kmer in aso.seq.as_bytes().windows(k) {
+                let entries = postings.entry(kmer).or_default();
+                if entries.last() != Some(&i) {
+                    entries.push(i)
+                }
+            }
+        }
+        KmerIndex { k, postings }
+    }
+
+    /// Library ASOs that can be within `max` edits of `seq`, by shared
+    /// k-mers and length, None when the k-mers can't rule any out
+    fn candidates(&self, library: &[Arc<AsoProfile>], seq: &str, max: usize) -> Option<Vec<usize>> {
+        let positions = seq.len().checked_sub(self.k - 1)?;
+        let needed = positions.checked_sub(self.k * max).filter(|&needed| needed > 0)?;
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for kmer in seq.as_bytes().windows(self.k) {
+            for &entry in self.postings.get(kmer).map_or(&[][..], Vec::as_slice) {
+                *shared.entry(entry).or_default() += 1;
+            }
+        }
+        Some(sorted(shared.into_iter()
+            .filter(|&(entry, count)| count >= needed && library[entry].aso_len.abs_diff(seq.len()) <= max)
+            .map(|(entry, _)| entry)
+            .collect()))
+    }
+}
+
+/// Burkhard-Keller tree of the library by levenshtein distance
+struct BkTree<'a> {
+    library: &'a [Arc<AsoProfile>],
+    /// Library index and children by distance, per node; node 0 is the root
+    nodes: Vec<(usize, BTreeMap<usize, usize>)>,
+}
+
+impl<'a> BkTree<'a> {
+    fn new(library: &'a [Arc<AsoProfile>]) -> Self {
+        let mut tree = BkTree { library, nodes: Vec::with_capacity(library.len()) };
+        for entry in 0..library.len() {
+            tree.insert(entry)
+        }
+        tree
+    }
+
+    fn distance(&self, seq: &str, entry: usize) -> usize {
+        myers::levenshtein(seq, &self.library[entry].seq, Ambiguity::Strict)
+    }
+
+    fn insert(&mut self, entry: usize) {
+        if self.nodes.is_empty() {
+            self.nodes.push((entry, BTreeMap::new()));
+            return
+        }
+        let mut node = 0;
+        loop {
+            let distance = self.distance(&self.library[entry].seq, self.nodes[node].0);
+            match self.nodes[node].1.get(&distance) {
+                Some(&child) => node = child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push((entry, BTreeMap::new()));
+                    self.nodes[node].1.insert(distance, child);
+                    return
+                }
+            }
+        }
+    }
+
+    /// Library ASOs within `max` of `seq`
+    fn within(&self, seq: &str, max: usize) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut pending = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(node) = pending.pop() {
+            let (entry, children) = &self.nodes[node];
+            let distance = self.distance(seq, *entry);
+            if distance <= max {
+                found.push(*entry)
+            }
+            // the triangle inequality rules out every other branch
+            pending.extend(children.range(distance.saturating_sub(max)..=distance + max).map(|(_, &child)| child));
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::Alphabet;
+
+    fn library(seqs: &[&str]) -> Vec<Arc<AsoProfile>> {
+        seqs.iter()
+            .enumerate()
+            .map(|(i, seq)| Arc::new(AsoProfile::new(format!("L{}", i + 1), seq.to_string(), &Alphabet::dna())))
+            .collect()
+    }
+
+    const LIBRARY: [&str; 4] = ["ACGTTGCAAC", "ACGTTGCATC", "GGGCCCAAAT", "TTGACCGTAG"];
+
+    #[test]
+    fn composition_edits_is_the_larger_of_surplus_and_deficit() {
+        let dna = Alphabet::dna();
+        let edits = |a: &str, b: &str| composition_edits(&dna.composition(a), &dna.composition(b));
+        // C for an A: one substitution
+        assert_eq!(edits("ACGT", "AAGT"), 1);
+        // a T less: one deletion
+        assert_eq!(edits("ACGT", "ACG"), 1);
+        assert_eq!(edits("AAAA", "CCGT"), 4);
+        assert_eq!(edits("ACGT", "TGCA"), 0);
+    }
+
+    #[test]
+    fn kmer_index_keeps_library_asos_sharing_enough_kmers() {
+        let library = library(&LIBRARY);
+        let index = KmerIndex::new(&library, 3);
+        // 8 3-mers, one edit leaves 8 - 3 = 5: L2 shares 6, L4 only TTG and CGT
+        assert_eq!(index.candidates(&library, "ACGTTGCAAC", 1), Some(vec![0, 1]));
+        // three edits can leave none of them
+        assert_eq!(index.candidates(&library, "ACGTTGCAAC", 3), None);
+    }
+
+    #[test]
+    fn bk_tree_finds_every_library_aso_within_d() {
+        let library = library(&LIBRARY);
+        let tree = BkTree::new(&library);
+        assert_eq!(sorted(tree.within("ACGTTGCAAC", 0)), vec![0]);
+        assert_eq!(sorted(tree.within("ACGTTGCAAC", 1)), vec![0, 1]);
+        assert_eq!(sorted(tree.within("GGGCCCAAAA", 1)), vec![2]);
+        assert_eq!(sorted(tree.within("ACGTTGCAAC", 10)), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn choose_follows_the_cutoffs() {
+        let inputs = [AsoProfile::new(String::from("I1"), String::from("ACGTACGTACGTACGTACGT"), &Alphabet::dna())];
+        let choose_for = |size: usize, cutoff: Option<usize>| {
+            choose(&library(&vec!["TGCATGCATGCATGCATGCA"; size]), &inputs, &[cutoff])
+        };
+        assert_eq!(choose_for(MIN_INDEXED_LIBRARY - 1, Some(1)), Strategy::Exhaustive);
+        assert_eq!(choose_for(MIN_INDEXED_LIBRARY, None), Strategy::Exhaustive);
+        // 20 bases keep a k-mer of 20 / (d + 1) bases through d edits
+        assert_eq!(choose_for(MIN_INDEXED_LIBRARY, Some(1)), Strategy::KmerIndex(10));
+        assert_eq!(choose_for(MIN_INDEXED_LIBRARY, Some(4)), Strategy::KmerIndex(4));
+        // 3-mers are too short, 5 is a quarter of the length
+        assert_eq!(choose_for(MIN_INDEXED_LIBRARY, Some(5)), Strategy::CompositionGate);
+        assert_eq!(choose_for(MIN_INDEXED_LIBRARY, Some(8)), Strategy::Exhaustive);
+    }
+}
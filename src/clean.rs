@@ -0,0 +1,29 @@
+/*
+    Cleanup of text pasted from Word/Excel
+        - Unicode compatibility normalization (NFKC), e.g. fullwidth letters
+        - zero-width spaces, non-breaking spaces, byte order marks
+        - smart quotes
+    These corrupt composition counts and lengths without being visible.
+*/
+use unicode_normalization::UnicodeNormalization;
+
+fn is_invisible_or_typographic(c: char) -> bool {
+    matches!(c,
+        '\u{00A0}' | '\u{202F}' | '\u{2007}' // non-breaking spaces
+        | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' // zero-width
+        | '\u{00AD}' // soft hyphen
+        | '\u{2018}' | '\u{2019}' | '\u{201C}' | '\u{201D}' // smart quotes
+    )
+}
+
+/// Normalize a field and strip invisible characters.
+/// Returns the cleaned text and whether anything changed.
+pub fn clean_field(field: &str) -> (String, bool) {
+    // strip first: NFKC would turn non-breaking spaces into plain spaces
+    let cleaned: String = field.chars()
+        .filter(|&c| !is_invisible_or_typographic(c))
+        .nfkc()
+        .collect();
+    let changed = cleaned != field;
+    (cleaned, changed)
+}
@@ -0,0 +1,106 @@
+/*
+    Name and sequence columns (--name-column, --seq-column)
+        - column1 is the name and column2 the sequence unless set
+          otherwise, in library and input files alike
+        - a column is a 1-based index, or the name of a header (trimmed,
+          any case), which needs a header row
+        - the passenger strand of a --duplex input is in --passenger-column,
+          by default the first column that is neither the name nor the
+          sequence
+        - csv and xlsx files, and the validate subcommand; sqlite tables
+          name theirs with ?name=&seq=, and FASTA and FASTQ records and
+          --aso-seq have no columns
+    Spares reshaping exports that put the sequence first or after IDs.
+*/
+use clap::Args;
+use csv::StringRecord;
+use serde::Serialize;
+use crate::error::CheckError;
+
+/// A column given on the commandline
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
+pub enum ColumnSpec {
+    /// 0-based
+    Index(usize),
+    Header(String),
+}
+
+pub fn parse_column(s: &str) -> Result<ColumnSpec, String> {
+    let s = s.trim();
+    match s.parse::<usize>() {
+        Ok(0) => Err(String::from("columns are numbered from 1")),
+        Ok(index) => Ok(ColumnSpec::Index(index - 1)),
+        Err(_) if s.is_empty() => Err(String::from("expected a column number or header name")),
+        Err(_) => Ok(ColumnSpec::Header(s.to_string())),
+    }
+}
+
+// The name and sequence columns of the commandline
+#[derive(Args, Debug, Default, Clone, Serialize)]
+pub struct ColumnArgs {
+    /// Column of the name in the input and library files: a number
+    /// (from 1) or a header name. Default: column1
+    #[arg(long="name-column", value_parser = parse_column)]
+    pub name_column: Option<ColumnSpec>,
+    /// Column of the sequence in the input and library files: a number
+    /// (from 1) or a header name. Default: column2
+    #[arg(long="seq-column", value_parser = parse_column)]
+    pub seq_column: Option<ColumnSpec>,
+}
+
+/// 0-based positions of the name, sequence and --duplex passenger strand
+/// in the records of a file
+#[derive(Debug, Copy, Clone)]
+pub struct RecordColumns {
+    pub name: usize,
+    pub seq: usize,
+    pub passenger: usize,
+}
+
+impl Default for RecordColumns {
+    fn default() -> Self {
+        RecordColumns { name: 0, seq: 1, passenger: 2 }
+    }
+}
+
+/// 0-based position of the column `spec` in the `headers` of a file (None
+/// when it has no header row), `default` without it
+pub fn position(spec: Option<&ColumnSpec>, default: usize, flag: &str, headers: Option<&StringRecord>, label: &str)
+    -> Result<usize, CheckError> {
+    match (spec, headers) {
+        (None, _) => Ok(default),
+        (Some(ColumnSpec::Index(index)), _) => Ok(*index),
+        (Some(ColumnSpec::Header(name)), Some(headers)) => headers.iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+            .ok_or(CheckError::Data(format!("{}: no column named {:?} for {}", label, name, flag))),
+        (Some(ColumnSpec::Header(name)), None) =>
+            Err(CheckError::Usage(format!("{}: {} {:?} needs a header row, give the column number instead", label,
+                                          flag, name))),
+    }
+}
+
+impl RecordColumns {
+    /// The columns set on the commandline, found in the `headers` of a
+    /// file (None when it has no header row)
+    pub fn resolve(args: &ColumnArgs, headers: Option<&StringRecord>, label: &str) -> Result<Self, CheckError> {
+        let name = position(args.name_column.as_ref(), 0, "--name-column", headers, label)?;
+        let seq = position(args.seq_column.as_ref(), 1, "--seq-column", headers, label)?;
+        if name == seq {
+            return Err(CheckError::Usage(format!("{}: --name-column and --seq-column are the same column {}", label,
+                                                 name + 1)))
+        }
+        let passenger = (0..).find(|column| *column != name && *column != seq).unwrap_or(2);
+        Ok(RecordColumns { name, seq, passenger })
+    }
+
+    /// With the --duplex passenger strand in the column `spec`, when set
+    pub fn with_passenger(self, spec: Option<&ColumnSpec>, headers: Option<&StringRecord>, label: &str)
+        -> Result<Self, CheckError> {
+        let passenger = position(spec, self.passenger, "--passenger-column", headers, label)?;
+        if passenger == self.name || passenger == self.seq {
+            return Err(CheckError::Usage(format!("{}: --passenger-column {} is also the name or sequence column",
+                                                 label, passenger + 1)))
+        }
+        Ok(RecordColumns { passenger, ..self })
+    }
+}
@@ -0,0 +1,94 @@
+/*
+    Compare the results of two runs
+        - matches that appeared
+        - matches that disappeared
+        - matches whose distance changed
+        - matches are those of every input by its own name, also of each
+          member of a group of identical inputs
+        - a file whose matches were cut short by --max-hits is refused,
+          the ones left out would show as disappeared (the manifest keeps
+          them all)
+    Used when the library is updated, to check whether previously
+    cleared candidates are still clean.
+*/
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use log::info;
+use serde::Deserialize;
+use crate::error::CheckError;
+use crate::manifest::InputSummary;
+
+/// Any JSON file carrying a `results` list (run manifests, JSON output)
+#[derive(Deserialize)]
+pub struct RunResults {
+    pub results: Vec<InputSummary>,
+}
+
+enum Change {
+    Appeared(f32),
+    Disappeared(f32),
+    Changed(f32, f32),
+}
+
+fn load_results(path: &Path) -> Result<BTreeMap<(String, String), f32>, Box<dyn Error>> {
+    info!("Reading results from {:?}", path);
+    let run: RunResults = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let mut matches = BTreeMap::new();
+    for input in run.results {
+        if input.match_count > input.matches.len() {
+            return Err(CheckError::Data(format!("{:?}: {} has {} of its {} matches (--max-hits), compare the \
+                                                 manifests or results written without it", path, input.name,
+                                                input.matches.len(), input.match_count)).into())
+        }
+        let names = match input.members.is_empty() {
+            true => vec![input.name],
+            false => input.members,
+        };
+        for lib_aso in input.matches {
+            for name in &names {
+                matches.insert((name.clone(), lib_aso.name.clone()), lib_aso.distance);
+            }
+        }
+    }
+    Ok(matches)
+}
+
+pub fn compare_runs(old: &Path, new: &Path) -> Result<(), Box<dyn Error>> {
+    let old_matches = load_results(old)?;
+    let new_matches = load_results(new)?;
+    let mut changes: BTreeMap<&(String, String), Change> = BTreeMap::new();
+    for (key, &old_dist) in &old_matches {
+        match new_matches.get(key) {
+            None => { changes.insert(key, Change::Disappeared(old_dist)); }
+            Some(&new_dist) if new_dist != old_dist => {
+                changes.insert(key, Change::Changed(old_dist, new_dist));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, &new_dist) in &new_matches {
+        if !old_matches.contains_key(key) {
+            changes.insert(key, Change::Appeared(new_dist));
+        }
+    }
+    println!("{:<10}\t{:<10}\t{:<12}\t{:<10}\tNew distance", "Input ASO", "Matching ASO", "Change",
+             "Old distance");
+    for ((input, lib_aso), change) in &changes {
+        let (label, old_dist, new_dist) = match change {
+            Change::Appeared(d) => ("appeared", String::from("-"), d.to_string()),
+            Change::Disappeared(d) => ("disappeared", d.to_string(), String::from("-")),
+            Change::Changed(o, n) => ("changed", o.to_string(), n.to_string()),
+        };
+        println!("{:<10}\t{:<10}\t{:<12}\t{:<10}\t{}", input, lib_aso, label, old_dist, new_dist)
+    }
+    let count = |f: fn(&Change) -> bool| changes.values().filter(|c| f(c)).count();
+    println!("# {} appeared, {} disappeared, {} changed, {} unchanged",
+             count(|c| matches!(c, Change::Appeared(_))),
+             count(|c| matches!(c, Change::Disappeared(_))),
+             count(|c| matches!(c, Change::Changed(..))),
+             old_matches.len() - count(|c| !matches!(c, Change::Appeared(_))));
+    Ok(())
+}
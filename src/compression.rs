@@ -0,0 +1,60 @@
+/*
+    Compressed input and library files
+        - gzip (.gz) and zstd (.zst) files are decompressed as they are
+          read, told apart by their magic bytes rather than the extension,
+          so compressed pipes work too
+        - concatenated gzip members and zstd frames are read through
+        - anything else is read as is
+    Sits below the text decoding, so encodings, csv locales and formats
+    are detected on the decompressed text. The underlying reader still
+    sees the stored bytes, so file hashes are those of the files on disk.
+*/
+use std::io::{self, BufRead, BufReader, Read};
+use flate2::bufread::MultiGzDecoder;
+use log::info;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Reader of a file as stored or decompressed
+pub enum Decompressed<R: Read> {
+    Plain(BufReader<R>),
+    /// Boxed, the gzip state is several times the size of the others
+    Gzip(Box<MultiGzDecoder<BufReader<R>>>),
+    Zstd(zstd::Decoder<'static, BufReader<R>>),
+}
+
+impl<R: Read> Decompressed<R> {
+    pub fn new(inner: R, label: &str) -> io::Result<Self> {
+        let mut inner = BufReader::new(inner);
+        let sample = inner.fill_buf()?;
+        Ok(if sample.starts_with(GZIP_MAGIC) {
+            info!("{}: decompressing gzip", label);
+            Decompressed::Gzip(Box::new(MultiGzDecoder::new(inner)))
+        } else if sample.starts_with(ZSTD_MAGIC) {
+            info!("{}: decompressing zstd", label);
+            Decompressed::Zstd(zstd::Decoder::with_buffer(inner)?)
+        } else {
+            Decompressed::Plain(inner)
+        })
+    }
+
+    /// The original reader
+    pub fn into_inner(self) -> R {
+        match self {
+            Decompressed::Plain(inner) => inner.into_inner(),
+            Decompressed::Gzip(decoder) => decoder.into_inner().into_inner(),
+            Decompressed::Zstd(decoder) => decoder.finish().into_inner(),
+        }
+    }
+}
+
+impl<R: Read> Read for Decompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Decompressed::Plain(inner) => inner.read(buf),
+            Decompressed::Gzip(decoder) => decoder.read(buf),
+            Decompressed::Zstd(decoder) => decoder.read(buf),
+        }
+    }
+}
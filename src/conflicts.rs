@@ -0,0 +1,93 @@
+/*
+    Library names shared by different sequences (--on-name-conflict)
+        - rows with the same name and the same sequence are left as they
+          are; a name with more than one sequence, within a file or across
+          the library files, is a conflict and makes reports ambiguous
+        - keep-all (default): every sequence is kept, the later ones under
+          the name with a suffix, _2, _3, ..., and rows repeating one of
+          them get the same name
+        - keep-first: only the rows with the first sequence of the name
+          are kept
+        - fail: the library is rejected
+        - every conflict is logged with both file:line positions and
+          counted in the warning summary; renamed rows are in --name-map
+*/
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use clap::ValueEnum;
+use log::warn;
+use serde::Serialize;
+use crate::error::CheckError;
+use crate::names::Rename;
+use crate::warnings::WarningKind;
+use crate::Library;
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone, ValueEnum, Serialize)]
+pub enum OnNameConflict {
+    Fail,
+    KeepFirst,
+    #[default]
+    KeepAll,
+}
+
+/// The sequences of a name, in order, each with the name its rows are
+/// kept under (None when dropped)
+struct Variants {
+    first_at: String,
+    sequences: Vec<(String, Option<String>)>,
+}
+
+/// Resolve the name conflicts of a loaded library as `on_conflict` says
+pub fn resolve(library: &mut Library, on_conflict: OnNameConflict) -> Result<(), CheckError> {
+    let mut taken: HashSet<String> = library.asos.iter().map(|aso| aso.name.clone()).collect();
+    let mut names: HashMap<String, Variants> = HashMap::new();
+    let mut first_conflict = None;
+    let mut conflicts = 0;
+    let mut kept = Vec::with_capacity(library.asos.len());
+    for mut aso in std::mem::take(&mut library.asos) {
+        let at = format!("{}:{}", library.sources[aso.source], aso.line);
+        let variants = names.entry(aso.name.clone())
+            .or_insert_with(|| Variants { first_at: at.clone(), sequences: vec![] });
+        let known = variants.sequences.iter().find(|(seq, _)| *seq == aso.seq).map(|(_, name)| name.clone());
+        let new_sequence = known.is_none();
+        let name = match known {
+            Some(name) => name,
+            None if variants.sequences.is_empty() => Some(aso.name.clone()),
+            None => {
+                conflicts += 1;
+                warn!("{}: {} has a different sequence than at {}", at, aso.name, variants.first_at);
+                library.warnings.add(WarningKind::NameConflict, format!("{} {}", at, aso.name));
+                first_conflict.get_or_insert_with(|| format!("{} ({} at {})", at, aso.name, variants.first_at));
+                match on_conflict {
+                    OnNameConflict::Fail | OnNameConflict::KeepFirst => None,
+                    OnNameConflict::KeepAll => {
+                        let mut copy = variants.sequences.len() + 1;
+                        while taken.contains(&format!("{}_{}", aso.name, copy)) {
+                            copy += 1;
+                        }
+                        let name = format!("{}_{}", aso.name, copy);
+                        taken.insert(name.clone());
+                        Some(name)
+                    }
+                }
+            }
+        };
+        if new_sequence {
+            variants.sequences.push((aso.seq.clone(), name.clone()));
+        }
+        let Some(name) = name else { continue };
+        if name != aso.name {
+            library.renamed.push(Rename::new(library.sources[aso.source].clone(), aso.line, aso.name.clone(),
+                                             name.clone()));
+            Arc::make_mut(&mut aso).name = name;
+        }
+        kept.push(aso);
+    }
+    library.asos = kept;
+    match (on_conflict, first_conflict) {
+        (OnNameConflict::Fail, Some(first)) =>
+            Err(CheckError::Data(format!("{} library rows reuse a name with another sequence, first at {} (see --on-name-conflict)",
+                                         conflicts, first))),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,88 @@
+/*
+    Library coverage of target sequences (--coverage-file)
+        - built from the scan sites: run --scan-file with the library as
+          input file to see what the library already targets
+        - BED: covered and untargeted regions, depth as the score
+        - wig: depth at every position
+        - a covered/untargeted summary per target on stderr
+*/
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use log::info;
+
+enum CoverageFormat {
+    Bed,
+    Wig,
+}
+
+impl CoverageFormat {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase()) {
+            Some(ext) if ext == "bed" => Ok(CoverageFormat::Bed),
+            Some(ext) if ext == "wig" => Ok(CoverageFormat::Wig),
+            _ => Err(format!("Coverage file {:?} must end in .bed or .wig", path)),
+        }
+    }
+}
+
+/// A target and the sites mapped onto it, 1-based inclusive
+pub struct TargetSites<'a> {
+    pub name: &'a str,
+    pub length: usize,
+    pub sites: Vec<(usize, usize)>,
+}
+
+/// Number of sites covering each position
+fn depth(target: &TargetSites) -> Vec<usize> {
+    let mut depth = vec![0; target.length];
+    for &(start, end) in &target.sites {
+        for position in depth.iter_mut().take(end).skip(start - 1) {
+            *position += 1;
+        }
+    }
+    depth
+}
+
+/// Runs of equal depth as (0-based start, end, depth)
+fn runs(depth: &[usize]) -> Vec<(usize, usize, usize)> {
+    let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+    for (i, &d) in depth.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if run.2 == d => run.1 = i + 1,
+            _ => runs.push((i, i + 1, d)),
+        }
+    }
+    runs
+}
+
+pub fn write_coverage(path: &Path, targets: &[TargetSites]) -> Result<(), Box<dyn Error>> {
+    let format = CoverageFormat::from_path(path)?;
+    info!("Writing library coverage of {} targets to {:?}", targets.len(), path);
+    let mut writer = BufWriter::new(File::create(path)?);
+    for target in targets {
+        let depth = depth(target);
+        let runs = runs(&depth);
+        match format {
+            CoverageFormat::Bed => {
+                for &(start, end, d) in &runs {
+                    let label = if d == 0 { "untargeted" } else { "covered" };
+                    writeln!(writer, "{}\t{}\t{}\t{}\t{}", target.name, start, end, label, d)?;
+                }
+            }
+            CoverageFormat::Wig => {
+                writeln!(writer, "fixedStep chrom={} start=1 step=1", target.name)?;
+                for d in &depth {
+                    writeln!(writer, "{}", d)?;
+                }
+            }
+        }
+        let covered = depth.iter().filter(|&&d| d > 0).count();
+        let gaps = runs.iter().filter(|run| run.2 == 0).count();
+        eprintln!("{}: {} of {} positions covered ({:.0}%), {} untargeted regions", target.name,
+                  covered, target.length, 100.0 * covered as f32 / target.length.max(1) as f32, gaps);
+    }
+    writer.flush()?;
+    Ok(())
+}
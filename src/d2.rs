@@ -0,0 +1,149 @@
+/*
+    Alignment-free D2 statistics (--list-by d2, d2s)
+        - D2: the number of k-mer matches between two sequences, the dot
+          product of their k-mer count vectors of --d2-k bases
+        - D2S: the same over counts centered by what the base composition
+          of each sequence alone predicts, each k-mer weighted so that
+          neither sequence dominates; it picks up shared words rather than
+          shared composition. Summed over every k-mer of the bases of
+          either sequence: an absent k-mer still has a centered count,
+          minus its expected one. Only the counts of the k-mers present
+          are stored, the others are enumerated
+        - both reported as the dissimilarities d2 and d2S of Reinert et al.,
+          1/2 (1 - normalized statistic): 0 for the same k-mer profile, d2
+          at most 0.5 as counts are never negative, d2S at most 1
+    Suited where an edit distance means little, e.g. an ASO against a
+    much longer sequence. A sequence its composition fully explains, such
+    as a homopolymer, has no centered counts; d2 is used for it.
+*/
+use std::collections::{BTreeSet, HashMap};
+
+/// Longest --d2-k: D2S sums over every k-mer of the bases present
+const MAX_D2_K: usize = 8;
+
+pub fn parse_d2_k(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(k) if (1..=MAX_D2_K).contains(&k) => Ok(k),
+        _ => Err(format!("Expected a k-mer size from 1 to {}, got {}", MAX_D2_K, s)),
+    }
+}
+
+fn kmer_counts(seq: &[char], k: usize) -> HashMap<&[char], f64> {
+    let mut counts: HashMap<&[char], f64> = HashMap::new();
+    for kmer in seq.windows(k) {
+        *counts.entry(kmer).or_default() += 1.0;
+    }
+    counts
+}
+
+/// 1/2 (1 - D2 / (|X| |Y|)) of the raw k-mer counts
+pub fn d2(a: &str, b: &str, k: usize) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (a, b) = (kmer_counts(&a, k), kmer_counts(&b, k));
+    let squared = |counts: &HashMap<&[char], f64>| counts.values().map(|count| count * count).sum::<f64>();
+    match (squared(&a), squared(&b)) {
+        (0.0, 0.0) => 0.0,
+        (0.0, _) | (_, 0.0) => 0.5,
+        (squared_a, squared_b) => {
+            let dot: f64 = a.iter().map(|(kmer, count)| count * b.get(kmer).unwrap_or(&0.0)).sum();
+            // one square root of the product, so equal vectors come out at exactly 0
+            (0.5 * (1.0 - dot / (squared_a * squared_b).sqrt())).max(0.0) as f32
+        }
+    }
+}
+
+/// Counts of a sequence for D2S, its k-mers as numbers in base
+/// `bases.len()`
+struct KmerProfile {
+    counts: HashMap<usize, f64>,
+    /// Frequency of every base of `bases` in the sequence
+    frequency: Vec<f64>,
+    words: f64,
+}
+
+impl KmerProfile {
+    fn new(seq: &[char], bases: &[char], k: usize) -> Self {
+        let digits: Vec<usize> = seq.iter()
+            .map(|c| bases.binary_search(c).expect("bases cover the sequence"))
+            .collect();
+        let mut counts: HashMap<usize, f64> = HashMap::new();
+        for kmer in digits.windows(k) {
+            *counts.entry(kmer.iter().fold(0, |index, &digit| index * bases.len() + digit)).or_default() += 1.0;
+        }
+        let mut frequency = vec![0.0; bases.len()];
+        for &digit in &digits {
+            frequency[digit] += 1.0 / digits.len() as f64;
+        }
+        KmerProfile { counts, frequency, words: digits.len().saturating_sub(k - 1) as f64 }
+    }
+
+    /// Count of the k-mer `index` minus its expected count from the base
+    /// frequencies of the sequence
+    fn centered(&self, index: usize, k: usize) -> f64 {
+        let base_count = self.frequency.len();
+        let probability: f64 = (0..k)
+            .map(|position| self.frequency[index / base_count.pow(position as u32) % base_count])
+            .product();
+        self.counts.get(&index).unwrap_or(&0.0) - self.words * probability
+    }
+}
+
+/// 1/2 (1 - D2S / sqrt(sum X²/w * sum Y²/w)), w = sqrt(X² + Y²) per k-mer,
+/// over centered counts
+pub fn d2s(a: &str, b: &str, k: usize) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let bases: Vec<char> = a.iter().chain(&b).copied().collect::<BTreeSet<char>>().into_iter().collect();
+    if bases.is_empty() {
+        return 0.0
+    }
+    let (profile_a, profile_b) = (KmerProfile::new(&a, &bases, k), KmerProfile::new(&b, &bases, k));
+    let (mut statistic, mut norm_x, mut norm_y) = (0.0, 0.0, 0.0);
+    for index in 0..bases.len().pow(k as u32) {
+        let (x, y) = (profile_a.centered(index, k), profile_b.centered(index, k));
+        let weight = (x * x + y * y).sqrt();
+        if weight > 0.0 {
+            statistic += x * y / weight;
+            norm_x += x * x / weight;
+            norm_y += y * y / weight;
+        }
+    }
+    if norm_x == 0.0 || norm_y == 0.0 {
+        let (a, b): (String, String) = (a.into_iter().collect(), b.into_iter().collect());
+        return d2(&a, &b, k)
+    }
+    (0.5 * (1.0 - statistic / (norm_x * norm_y).sqrt())).clamp(0.0, 1.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f32, expected: f64) -> bool {
+        (actual as f64 - expected).abs() < 1e-6
+    }
+
+    #[test]
+    fn d2_counts_shared_kmers() {
+        // AC, CG, GT against AC, CG, GA: two of three 2-mers shared
+        assert!(close(d2("ACGT", "ACGA", 2), 0.5 * (1.0 - 2.0 / 3.0)));
+        assert_eq!(d2("ACGT", "ACGT", 2), 0.0);
+        assert_eq!(d2("AAAA", "CCCC", 2), 0.5);
+    }
+
+    #[test]
+    fn d2s_sums_over_absent_kmers_too() {
+        // values of the dense sum over every k-mer of the bases present
+        assert!(close(d2s("ACGTACGTACGTACGTACGT", "TGCATGCATGCATGCATGCA", 3), 0.341494588669));
+        assert!(close(d2s("ACGTTGCAAC", "ACGTTGCATC", 2), 0.174553800567));
+        assert!(close(d2s("AACCGGTTAG", "CTAACCGGTT", 3), 0.157086529212));
+    }
+
+    #[test]
+    fn d2s_of_a_homopolymer_is_d2() {
+        assert_eq!(d2s("AAAA", "AAAA", 2), 0.0);
+        assert_eq!(d2s("AAAA", "CCCC", 2), 0.5);
+        assert_eq!(d2s("", "", 2), 0.0);
+    }
+}
@@ -0,0 +1,55 @@
+/*
+    Damerau-Levenshtein distance (--list-by damerau)
+        - insertions, deletions, substitutions and transpositions of two
+          adjacent bases each count as one edit
+        - optimal string alignment: a transposed pair isn't edited again,
+          which is what swapped bases in a scramble design look like
+    The distance crate has no transpositions, so this is computed here.
+*/
+
+/// Edits turning `a` into `b`, a swap of adjacent bases being one edit
+pub fn damerau(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // three rows of the dynamic programming table: two back, previous, current
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swapped_neighbours_are_one_edit() {
+        assert_eq!(damerau("ACGT", "ACGT"), 0);
+        // CG to GC: one transposition, two substitutions for levenshtein
+        assert_eq!(damerau("ACGT", "AGCT"), 1);
+        assert_eq!(damerau("ACGTTGCAAC", "CAGTTGCAAC"), 1);
+        assert_eq!(damerau("ACGTT", "ACGT"), 1);
+        assert_eq!(damerau("ACGT", ""), 4);
+    }
+
+    #[test]
+    fn transposed_bases_are_not_edited_again() {
+        // CA to AGC: a true Damerau distance of 2 (swap, then insert G
+        // between), 3 as an optimal string alignment
+        assert_eq!(damerau("CA", "AGC"), 3);
+    }
+}
@@ -0,0 +1,54 @@
+/*
+    Identical inputs (on by default, --no-group-identical to keep them apart)
+        - inputs with the same sequence, compared the same way (max
+          distance, mask, soft-masked positions, parent, duplex), are
+          checked once
+        - the group is reported under the name of its first input, with
+          the others as an Identical value (e.g. DESIGN-37 for DESIGN-12);
+          the manifest and json results list every member, so that
+          export-approved and compare-runs see each of them by name
+    Saves the work and the repeated rows of design sets that list one
+    sequence under several names.
+*/
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use log::info;
+use crate::AsoProfile;
+
+/// What makes two inputs compare the same way, and the input a derived
+/// strand belongs to (the strands of two inputs are kept apart)
+type Key = (String, Option<u32>, BTreeSet<usize>, BTreeSet<usize>, Option<String>, Option<(String, String)>,
+            Option<String>);
+
+fn key(aso: &AsoProfile) -> Key {
+    (aso.seq.clone(), aso.max_distance.map(f32::to_bits), aso.mask.clone(), aso.softmasked.clone(),
+     aso.parent.clone(), aso.duplex.clone(), aso.strand_of.clone())
+}
+
+/// Keep the first of every group of identical inputs, with the names of
+/// the group as its members
+pub fn group_identical(inputs: &mut Vec<AsoProfile>) {
+    let mut first_of: HashMap<Key, usize> = HashMap::new();
+    let mut names: Vec<Vec<String>> = Vec::new();
+    let mut kept = Vec::with_capacity(inputs.len());
+    for aso in inputs.drain(..) {
+        match first_of.entry(key(&aso)) {
+            Entry::Occupied(first) => names[*first.get()].push(aso.name),
+            Entry::Vacant(slot) => {
+                slot.insert(kept.len());
+                names.push(vec![aso.name.clone()]);
+                kept.push(aso);
+            }
+        }
+    }
+    let folded: usize = names.iter().map(|group| group.len() - 1).sum();
+    for (aso, group) in kept.iter_mut().zip(names) {
+        if group.len() > 1 {
+            aso.members = group;
+        }
+    }
+    if folded > 0 {
+        info!("Checking {} inputs once, they are identical to an earlier one", folded);
+    }
+    *inputs = kept;
+}
@@ -0,0 +1,80 @@
+/*
+    Dialect of csv files (--delimiter, --quote-char, --comment-prefix)
+        - the delimiter is the one of --csv-locale unless set, a character
+          or tab
+        - fields are quoted with --quote-char, " by default
+        - lines starting with --comment-prefix, # by default, are comments
+          and not read (notes above or between the rows); none reads them
+    Applies to the input, library, mask, pairs and scan files, and to the
+    file of the validate subcommand.
+*/
+use clap::Args;
+use csv::ReaderBuilder;
+use serde::Serialize;
+use crate::locale::CsvLocale;
+use crate::Cli;
+
+/// Start of a comment line, None when every line is read
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize)]
+pub struct CommentPrefix(pub Option<u8>);
+
+fn single_byte(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        [byte] if byte.is_ascii() => Ok(*byte),
+        _ => Err(format!("expected a single ASCII character, got {:?}", s)),
+    }
+}
+
+pub fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ => single_byte(s),
+    }
+}
+
+pub fn parse_quote(s: &str) -> Result<u8, String> {
+    single_byte(s)
+}
+
+pub fn parse_comment(s: &str) -> Result<CommentPrefix, String> {
+    match s {
+        "none" => Ok(CommentPrefix(None)),
+        _ => single_byte(s).map(|prefix| CommentPrefix(Some(prefix))),
+    }
+}
+
+// The csv dialect of the commandline
+#[derive(Args, Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize)]
+pub struct DialectArgs {
+    /// Field delimiter of the csv files, a character or tab. Overrides
+    /// the one of csv-locale
+    #[arg(long="delimiter", value_parser = parse_delimiter)]
+    pub delimiter: Option<u8>,
+    /// Quote character of the csv files
+    #[arg(long="quote-char", value_parser = parse_quote, default_value = "\"")]
+    pub quote_char: u8,
+    /// Lines of the csv files starting with this character are comments
+    /// and not read; none reads every line
+    #[arg(long="comment-prefix", value_parser = parse_comment, default_value = "#")]
+    pub comment_prefix: CommentPrefix,
+}
+
+impl DialectArgs {
+    /// Reader of a csv file in this dialect, the delimiter of `locale`
+    /// unless set, `sample` being the start of the file
+    pub fn reader_builder(&self, locale: CsvLocale, sample: &[u8]) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter.unwrap_or_else(|| locale.delimiter(sample)))
+            .quote(self.quote_char)
+            .comment(self.comment_prefix.0)
+            .flexible(true);
+        builder
+    }
+}
+
+/// Reader of a csv file in the dialect of the settings, `sample` being
+/// the start of the file
+pub fn reader_builder(cli: &Cli, sample: &[u8]) -> ReaderBuilder {
+    cli.dialect.reader_builder(cli.csv_locale, sample)
+}
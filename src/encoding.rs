@@ -0,0 +1,103 @@
+/*
+    Text encoding of input and library files
+        - UTF-8, with or without a byte order mark
+        - UTF-16 LE/BE with a byte order mark (Excel "Unicode text")
+        - anything else that isn't valid UTF-8 is read as Windows-1252,
+          the default of Excel csv exports on Windows
+    Everything is handed to the csv reader as UTF-8, after decompressing
+    gzip and zstd files (see the compression module). The underlying
+    reader still sees the original bytes, so file hashes are unaffected.
+*/
+use std::io::{self, BufRead, BufReader, Read};
+use encoding_rs::{Decoder, Encoding, UTF_8, WINDOWS_1252};
+use log::{info, warn};
+use crate::compression::Decompressed;
+
+/// Bytes looked at to tell UTF-8 from Windows-1252
+const SNIFF_LEN: usize = 64 * 1024;
+
+/// Reader producing UTF-8 from a file in any of the supported encodings
+pub struct DecodingReader<R: Read> {
+    inner: BufReader<Decompressed<R>>,
+    /// None when the file already is UTF-8
+    decoder: Option<Decoder>,
+    decoded: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+/// Valid UTF-8, allowing a character cut off at the end of the sample
+fn is_utf8_prefix(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+impl<R: Read> DecodingReader<R> {
+    pub fn new(inner: R, label: &str) -> io::Result<Self> {
+        let mut inner = BufReader::with_capacity(SNIFF_LEN, Decompressed::new(inner, label)?);
+        let sample = inner.fill_buf()?;
+        let decoder = match Encoding::for_bom(sample) {
+            Some((encoding, bom_length)) if encoding == UTF_8 => {
+                info!("{}: skipping UTF-8 byte order mark", label);
+                inner.consume(bom_length);
+                None
+            }
+            Some((encoding, _)) => {
+                info!("{}: reading as {}", label, encoding.name());
+                Some(encoding.new_decoder_with_bom_removal())
+            }
+            None if is_utf8_prefix(sample) => None,
+            None => {
+                warn!("{}: not valid UTF-8, reading as {}", label, WINDOWS_1252.name());
+                Some(WINDOWS_1252.new_decoder())
+            }
+        };
+        Ok(DecodingReader {
+            inner,
+            decoder,
+            decoded: Vec::new(),
+            position: 0,
+            finished: false,
+        })
+    }
+
+    /// Start of the file as stored (but decompressed), e.g. to guess the
+    /// csv delimiter. Only meaningful before reading
+    pub fn sample(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    /// The original reader
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(decoder) = self.decoder.as_mut() else {
+            return self.inner.read(buf)
+        };
+        while self.position == self.decoded.len() {
+            if self.finished {
+                return Ok(0)
+            }
+            let input = self.inner.fill_buf()?;
+            let last = input.is_empty();
+            let capacity = decoder.max_utf8_buffer_length(input.len())
+                .unwrap_or(input.len() * 3 + 16);
+            self.decoded.resize(capacity, 0);
+            let (_, read, written, _) = decoder.decode_to_utf8(input, &mut self.decoded, last);
+            self.inner.consume(read);
+            self.decoded.truncate(written);
+            self.position = 0;
+            self.finished = last;
+        }
+        let n = buf.len().min(self.decoded.len() - self.position);
+        buf[..n].copy_from_slice(&self.decoded[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,91 @@
+/*
+    Errors and exit codes
+        - a failed run prints one "error: ..." line to stderr, with the
+          file, line and field where the input is at fault, and exits with
+          the code of its class; it never panics on bad input
+        - CheckError classifies the errors this tool raises itself; errors
+          of the csv reader and of file access are classified by kind
+    See EXIT_CODES, also shown by --help.
+*/
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The documented exit codes, for --help
+pub const EXIT_CODES: &str = "Exit codes:
+  0  success
+  1  other failure, a failed self-test case or batch job
+  2  invalid arguments
+  3  close-match gate tripped (--fail-on-close-match, --fail-below)
+  4  stopped early on a resource limit (--max-runtime, --max-memory)
+  5  nothing was checked, no usable library or input records
+  6  invalid input data: malformed rows, sequences or values
+  7  a file could not be opened, read or written";
+
+/// Exit code of failures that fit no other class
+pub const OTHER_EXIT_CODE: i32 = 1;
+/// Exit code of invalid arguments, as the argument parser uses
+pub const USAGE_EXIT_CODE: i32 = 2;
+/// Exit code of malformed or invalid input data
+pub const DATA_EXIT_CODE: i32 = 6;
+/// Exit code of files that can't be opened, read or written
+pub const IO_EXIT_CODE: i32 = 7;
+
+#[derive(Debug)]
+pub enum CheckError {
+    /// Options that don't work together, or a missing one
+    Usage(String),
+    /// A malformed row or invalid value, with file:line context
+    Data(String),
+    /// A file that can't be opened, read or written
+    Io(String),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Usage(message) | CheckError::Data(message) | CheckError::Io(message) =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for CheckError {}
+
+/// Exit code for an error, by its class
+pub fn exit_code(error: &(dyn Error + 'static)) -> i32 {
+    if let Some(error) = error.downcast_ref::<CheckError>() {
+        return match error {
+            CheckError::Usage(_) => USAGE_EXIT_CODE,
+            CheckError::Data(_) => DATA_EXIT_CODE,
+            CheckError::Io(_) => IO_EXIT_CODE,
+        }
+    }
+    if let Some(error) = error.downcast_ref::<csv::Error>() {
+        return match error.kind() {
+            csv::ErrorKind::Io(error) => io_exit_code(error),
+            _ => DATA_EXIT_CODE,
+        }
+    }
+    if let Some(error) = error.downcast_ref::<io::Error>() {
+        return io_exit_code(error)
+    }
+    if error.is::<serde_json::Error>() {
+        return DATA_EXIT_CODE
+    }
+    OTHER_EXIT_CODE
+}
+
+fn io_exit_code(error: &io::Error) -> i32 {
+    match error.kind() {
+        // undecodable text
+        io::ErrorKind::InvalidData => DATA_EXIT_CODE,
+        _ => IO_EXIT_CODE,
+    }
+}
+
+/// Print a failed step and exit with the code of its error
+pub fn exit_with(context: &str, error: &(dyn Error + 'static)) -> ! {
+    eprintln!("error: {}: {}", context, error);
+    std::process::exit(exit_code(error))
+}
@@ -0,0 +1,86 @@
+/*
+    Why a match was reported (--explain)
+        - prefilter: what the input and the match had in common to be
+          compared at all
+        - shared k-mers between the two sequences
+        - every computed metric against its cutoff
+        - mismatch positions, and how they fall into the 5' third, the
+          middle and the 3' third of the input
+    Printed under each match of the default output.
+*/
+use std::collections::BTreeSet;
+use crate::annotate::composition_delta;
+use crate::{mask, AsoMatch, AsoProfile, Cli, Prefilter};
+
+/// k-mer length used for the shared k-mer count
+const KMER: usize = 4;
+
+fn kmers(seq: &[char]) -> BTreeSet<&[char]> {
+    seq.windows(KMER).collect()
+}
+
+fn prefilter_reason(input: &AsoProfile, hit: &AsoMatch, cli: &Cli) -> String {
+    match cli.prefilter {
+        Prefilter::Composition => format!("prefilter composition: same length ({}) and same count of each symbol",
+                                          input.aso_len),
+        Prefilter::Length => format!("prefilter length: same length ({}), composition delta {}",
+                                     input.aso_len, composition_delta(input, &hit.aso, &cli.alphabet)),
+        Prefilter::None => format!("prefilter none: lengths {} and {}", input.aso_len, hit.aso.aso_len),
+        Prefilter::Auto => format!("prefilter auto: lengths {} and {}, candidate within the cutoff",
+                                   input.aso_len, hit.aso.aso_len),
+    }
+}
+
+fn cutoffs(hit: &AsoMatch, cli: &Cli, max_distance: Option<f32>) -> String {
+    let primary = cli.list_by.primary();
+    hit.scores.iter()
+        .map(|&(metric, score)| {
+            let score_text = metric.format(score, cli.precision);
+            let threshold = cli.thresholds.iter().find(|(m, _)| *m == metric).map(|(_, t)| *t);
+            let limit = match (metric == primary, max_distance, threshold) {
+                (_, _, Some(t)) => format!(" (threshold {})", t),
+                (true, Some(max), None) => format!(" (max distance {})", max),
+                _ => String::new(),
+            };
+            format!("{:?} {}{}", metric, score_text, limit)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn mismatch_regions(a: &[char], b: &[char]) -> String {
+    let positions: Vec<usize> = a.iter().zip(b)
+        .enumerate()
+        .filter(|(_, (x, y))| x != y)
+        .map(|(i, _)| i)
+        .collect();
+    if positions.is_empty() {
+        return String::from("no mismatched positions (differences are shifts)")
+    }
+    let length = a.len().max(1);
+    let mut regions = [0; 3];
+    for &position in &positions {
+        regions[(position * 3 / length).min(2)] += 1;
+    }
+    let listed: Vec<String> = positions.iter().map(|p| (p + 1).to_string()).collect();
+    format!("mismatches at {}: 5' third {}, middle {}, 3' third {}", listed.join(","),
+            regions[0], regions[1], regions[2])
+}
+
+/// Lines explaining one reported match
+pub fn explain(input: &AsoProfile, hit: &AsoMatch, cli: &Cli) -> Vec<String> {
+    let a: Vec<char> = input.seq.chars().collect();
+    let b: Vec<char> = mask::masked(input, &hit.aso.seq).chars().collect();
+    let input_kmers = kmers(&a);
+    let shared = input_kmers.intersection(&kmers(&b)).count();
+    let mut lines = vec![
+        prefilter_reason(input, hit, cli),
+        format!("shared {}-mers: {} of {}", KMER, shared, input_kmers.len()),
+        format!("scores: {}", cutoffs(hit, cli, input.max_distance)),
+        mismatch_regions(&a, &b),
+    ];
+    if !input.mask.is_empty() {
+        lines.push(format!("masked positions {}, not counted", mask::describe(&input.mask)));
+    }
+    lines
+}
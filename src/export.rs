@@ -0,0 +1,150 @@
+/*
+    Structured results files
+        - CSV (long format: one row per match and metric) or JSON lines,
+          chosen by the file extension
+        - every row carries the run identifier
+        - in CSV, annotations are rows of their own with the annotation
+          and value columns set, metric and distance empty; per-input
+          values (verdict, PWM score, ...) are such rows without a match.
+          In JSON lines they are annotations and input_annotations
+        - --append accumulates successive runs into one file; a CSV file
+          with other columns is refused
+    Inputs without any match get one row with empty match fields, so a
+    checked-and-clean candidate is still on record.
+*/
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::{redact, AsoMatch, AsoProfile};
+
+enum ResultsFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ResultsFormat {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase()) {
+            Some(ext) if ext == "csv" => Ok(ResultsFormat::Csv),
+            Some(ext) if ext == "jsonl" || ext == "ndjson" => Ok(ResultsFormat::Jsonl),
+            _ => Err(format!("Results file {:?} must end in .csv or .jsonl", path)),
+        }
+    }
+}
+
+/// Unique enough to tell runs apart within one results file
+pub fn generate_run_id() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format!("{}-{}", now, std::process::id())
+}
+
+const CSV_HEADER: [&str; 9] = ["run_id", "input", "input_seq", "match", "match_seq", "metric", "distance",
+                               "annotation", "value"];
+
+#[derive(Serialize)]
+struct JsonRow<'a> {
+    run_id: &'a str,
+    input: &'a str,
+    input_seq: &'a str,
+    #[serde(rename = "match")]
+    matched: Option<&'a str>,
+    match_seq: Option<Cow<'a, str>>,
+    distance: Option<f32>,
+    metrics: BTreeMap<String, f32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<&'static str, &'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    input_annotations: BTreeMap<&'static str, &'a str>,
+}
+
+fn json_row<'a>(run_id: &'a str, aso: &'a AsoProfile, hit: Option<&'a AsoMatch>, redact: bool) -> JsonRow<'a> {
+    JsonRow {
+        run_id,
+        input: &aso.name,
+        input_seq: &aso.seq,
+        matched: hit.map(|hit| hit.aso.name.as_str()),
+        match_seq: hit.map(|hit| redact::shown(&hit.aso.seq, redact)),
+        distance: hit.map(|hit| hit.distance),
+        metrics: hit.map_or(BTreeMap::new(), |hit| hit.scores.iter()
+            .map(|(metric, score)| (format!("{:?}", metric), *score))
+            .collect()),
+        annotations: hit.map_or(BTreeMap::new(), |hit| hit.annotations.iter()
+            .map(|(column, value)| (*column, value.as_str()))
+            .collect()),
+        input_annotations: aso.annotations.iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect(),
+    }
+}
+
+pub fn write_results(path: &Path, append: bool, run_id: &str, input_seq_props: &[AsoProfile],
+                     precision: Option<usize>, redact: bool) -> Result<(), Box<dyn Error>> {
+    let format = ResultsFormat::from_path(path)?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    let is_new = file.metadata()?.len() == 0;
+    let mut writer = BufWriter::new(file);
+    match format {
+        ResultsFormat::Csv => {
+            if !is_new {
+                let mut header = String::new();
+                BufReader::new(File::open(path)?).read_line(&mut header)?;
+                if header.trim_end() != CSV_HEADER.join(",") {
+                    return Err(format!("Results file {:?} has other columns ({}), expected {}", path,
+                                       header.trim_end(), CSV_HEADER.join(",")).into())
+                }
+            }
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            if is_new {
+                csv_writer.write_record(CSV_HEADER)?;
+            }
+            for aso in input_seq_props {
+                if aso.aso_names.is_empty() {
+                    csv_writer.write_record([run_id, &aso.name, &aso.seq, "", "", "", "", "", ""])?;
+                }
+                for (name, value) in &aso.annotations {
+                    csv_writer.write_record([run_id, &aso.name, &aso.seq, "", "", "", "", name, value])?;
+                }
+                for hit in &aso.aso_names {
+                    let match_seq = redact::shown(&hit.aso.seq, redact);
+                    for (metric, score) in &hit.scores {
+                        csv_writer.write_record([run_id, &aso.name, &aso.seq, &hit.aso.name,
+                                                 &match_seq, &format!("{:?}", metric),
+                                                 &metric.format(*score, precision), "", ""])?;
+                    }
+                    for (column, value) in &hit.annotations {
+                        csv_writer.write_record([run_id, &aso.name, &aso.seq, &hit.aso.name,
+                                                 &match_seq, "", "", column, value])?;
+                    }
+                }
+            }
+            csv_writer.flush()?;
+        }
+        ResultsFormat::Jsonl => {
+            for aso in input_seq_props {
+                if aso.aso_names.is_empty() {
+                    serde_json::to_writer(&mut writer, &json_row(run_id, aso, None, redact))?;
+                    writeln!(writer)?;
+                }
+                for hit in &aso.aso_names {
+                    serde_json::to_writer(&mut writer, &json_row(run_id, aso, Some(hit), redact))?;
+                    writeln!(writer)?;
+                }
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+/*
+    FASTA and FASTQ files (--library-format, --input-format)
+        - every FASTA record is an entry: the first word of its header
+          line (after the >) is the name, the sequence lines up to the
+          next header, however wrapped, are joined into the sequence
+        - FASTQ records are four lines, @name, sequence, + and qualities;
+          the qualities are read past, not used
+        - blank lines and ; comment lines (FASTA) are skipped
+        - each entry keeps the line of its header, for file:line context
+        - auto (the default) reads a file starting with > as FASTA, one
+          starting with @ as FASTQ, any other as csv
+    Records are handed to the library and input readers as name,sequence
+    rows, so entries get the same validation and options as csv rows.
+*/
+use std::error::Error;
+use std::io::BufRead;
+use clap::ValueEnum;
+use csv::{Position, StringRecord};
+use serde::Serialize;
+use crate::error::CheckError;
+
+#[derive(Debug, Default, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum FileFormat {
+    #[default]
+    Auto,
+    Csv,
+    Fasta,
+    Fastq,
+    /// Excel workbook, libraries only, see the xlsx module
+    Xlsx,
+}
+
+impl FileFormat {
+    /// The format a file is read as, `sample` being the start of it; never Auto
+    pub fn resolve(self, sample: &[u8]) -> FileFormat {
+        match self {
+            FileFormat::Auto => match sample.iter().find(|b| !b.is_ascii_whitespace()) {
+                Some(b'>') => FileFormat::Fasta,
+                Some(b'@') => FileFormat::Fastq,
+                _ => FileFormat::Csv,
+            },
+            format => format,
+        }
+    }
+}
+
+fn record(name: &str, seq: &str, line: u64) -> StringRecord {
+    let mut record = StringRecord::from(vec![name, seq]);
+    let mut position = Position::new();
+    position.set_line(line);
+    record.set_position(Some(position));
+    record
+}
+
+/// The records of a FASTA or FASTQ file as (name, sequence) rows
+/// positioned at their header line
+pub fn read_records<R: BufRead>(reader: R, format: FileFormat, label: &str) -> Result<Vec<StringRecord>, Box<dyn Error>> {
+    match format {
+        FileFormat::Fastq => read_fastq(reader, label),
+        _ => read_fasta(reader, label),
+    }
+}
+
+fn read_fasta<R: BufRead>(reader: R, label: &str) -> Result<Vec<StringRecord>, Box<dyn Error>> {
+    let mut records = Vec::new();
+    // name, header line and sequence of the record being read
+    let mut current: Option<(String, u64, String)> = None;
+    for (i, line) in reader.lines().enumerate() {
+        let line_number = i as u64 + 1;
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue
+        }
+        match line.strip_prefix('>') {
+            Some(header) => {
+                if let Some((name, header_line, seq)) = current.take() {
+                    records.push(record(&name, &seq, header_line));
+                }
+                let name = header.split_whitespace().next().unwrap_or("");
+                current = Some((name.to_string(), line_number, String::new()));
+            }
+            None => match &mut current {
+                Some((_, _, seq)) => seq.push_str(line),
+                None => return Err(CheckError::Data(format!("{}:{}: sequence before the first > header line",
+                                                            label, line_number)).into()),
+            },
+        }
+    }
+    if let Some((name, header_line, seq)) = current {
+        records.push(record(&name, &seq, header_line));
+    }
+    Ok(records)
+}
+
+fn read_fastq<R: BufRead>(reader: R, label: &str) -> Result<Vec<StringRecord>, Box<dyn Error>> {
+    let mut records = Vec::new();
+    let mut lines = reader.lines().enumerate()
+        .map(|(i, line)| line.map(|line| (i as u64 + 1, line.trim().to_string())));
+    while let Some(header) = lines.next() {
+        let (line_number, header) = header?;
+        if header.is_empty() {
+            continue
+        }
+        let Some(header) = header.strip_prefix('@') else {
+            return Err(CheckError::Data(format!("{}:{}: expected a FASTQ @ header line, found {:?}",
+                                                label, line_number, header)).into())
+        };
+        let mut field = |expected: &str| -> Result<String, Box<dyn Error>> {
+            match lines.next() {
+                Some(line) => Ok(line?.1),
+                None => Err(CheckError::Data(format!("{}:{}: FASTQ record ends before its {} line", label,
+                                                     line_number, expected)).into()),
+            }
+        };
+        let seq = field("sequence")?;
+        if !field("+")?.starts_with('+') {
+            return Err(CheckError::Data(format!("{}:{}: FASTQ record without its + separator line", label,
+                                                line_number)).into())
+        }
+        let qualities = field("quality")?;
+        if qualities.len() != seq.len() {
+            return Err(CheckError::Data(format!("{}:{}: FASTQ record has {} bases but {} qualities", label,
+                                                line_number, seq.len(), qualities.len())).into())
+        }
+        records.push(record(header.split_whitespace().next().unwrap_or(""), &seq, line_number));
+    }
+    Ok(records)
+}
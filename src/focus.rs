@@ -0,0 +1,75 @@
+/*
+    Reverse lookup (--focus LIBRARY_NAME)
+        - one library ASO, e.g. a lead compound, against every input
+        - inputs ranked by similarity to it, closest first
+        - no prefilter: every input is scored, max-distance still applies
+    Answers "which of these candidates come close to ours?", the way IP
+    and interference questions are framed.
+*/
+use std::error::Error;
+use std::io::{self, Write};
+use log::info;
+use crate::error::CheckError;
+use crate::{load_inputs, redact, score_of, AsoProfile, Cli, Dist, Library, RunStats};
+
+struct Ranked<'a> {
+    input: &'a AsoProfile,
+    scores: Vec<(Dist, f32)>,
+    distance: Option<f32>,
+}
+
+fn print_ranking(out: &mut dyn Write, focus: &AsoProfile, ranked: &[Ranked],
+                 cli: &Cli) -> io::Result<()> {
+    let metrics = cli.list_by.metrics();
+    if !cli.raw {
+        let header: Vec<String> = metrics.iter().map(|metric| format!("{:?}", metric)).collect();
+        writeln!(out, "{:<10}\t{:<20}\tRank\t{:<10}\t{:<20}\t{}", "Focus ASO", "Seq", "Input ASO",
+                 "Seq", header.join("\t"))?;
+    }
+    for (rank, entry) in ranked.iter().enumerate() {
+        let scores: Vec<String> = metrics.iter()
+            .map(|&metric| score_of(&entry.scores, metric)
+                .map_or(String::from("n/a"), |score| metric.format(score, cli.precision)))
+            .collect();
+        writeln!(out, "{:<10}\t{:<20}\t{}\t{:<10}\t{:<20}\t{}", focus.name,
+                 redact::shown(&focus.seq, cli.redact_sequences), rank + 1,
+                 entry.input.name, entry.input.seq, scores.join("\t"))?;
+    }
+    Ok(())
+}
+
+pub fn focus(cli: &Cli, name: &str, library: &Library, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let focus = library.asos.iter()
+        .find(|aso| aso.name == name)
+        .ok_or(CheckError::Usage(format!("No library ASO named {:?}", name)))?;
+    let (inputs, _) = load_inputs(cli, &mut RunStats::default())?;
+    info!("Ranking {} inputs by similarity to {}", inputs.len(), focus.name);
+    let primary = cli.list_by.primary();
+    let mut ranked: Vec<Ranked> = inputs.iter()
+        .map(|input| {
+            let scores: Vec<(Dist, f32)> = cli.list_by.metrics().into_iter()
+                .filter_map(|metric| metric.distance(&focus.seq, &input.seq, &cli.metric_options).ok().map(|d| (metric, d)))
+                .collect();
+            let distance = score_of(&scores, primary);
+            Ranked { input, scores, distance }
+        })
+        .filter(|entry| match (input_max(entry.input, cli), entry.distance) {
+            (Some(max), Some(distance)) => distance <= max,
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .collect();
+    // inputs the primary metric doesn't apply to go last
+    ranked.sort_by(|a, b| a.distance.unwrap_or(f32::INFINITY).total_cmp(&b.distance.unwrap_or(f32::INFINITY)));
+    // a closed pipe (e.g. `| head`) is not an error
+    if let Err(e) = print_ranking(out, focus, &ranked, cli).and_then(|_| out.flush()) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e.into())
+        }
+    }
+    Ok(())
+}
+
+fn input_max(input: &AsoProfile, cli: &Cli) -> Option<f32> {
+    input.max_distance.or(cli.max_distance)
+}
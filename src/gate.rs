@@ -0,0 +1,162 @@
+/*
+    Close-match gate for automated pipelines
+        - fail when any input has a match at or below a distance
+        - or above a positional identity
+    Each violation is reported on stderr as one JSON line.
+    The same gates decide the per-input verdict (--verdict).
+    Tiers (--fail-below, --warn-below)
+        - matches nearer than fail-below fail the run like a gate
+        - matches between fail-below and warn-below only warn
+        - each tier gets its own section after the report
+*/
+use std::fmt;
+use std::io::{self, Write};
+use serde::Serialize;
+use crate::{mask, redact, AsoMatch, AsoProfile, Cli};
+
+/// Exit code when the close-match gate rejects the run
+pub const CLOSE_MATCH_EXIT_CODE: i32 = 3;
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize)]
+pub enum CloseMatchGate {
+    Distance(f32),
+    Identity(f32),
+    /// --fail-below, strictly nearer than the distance
+    Below(f32),
+}
+
+/// Gates of the commandline, --fail-on-close-match and --fail-below
+pub fn gates(cli: &Cli) -> Vec<CloseMatchGate> {
+    let mut gates = cli.fail_on_close_match.clone();
+    gates.extend(cli.fail_below.map(CloseMatchGate::Below));
+    gates
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Tier {
+    Fail,
+    Warn,
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tier::Fail => write!(f, "FAIL"),
+            Tier::Warn => write!(f, "WARN"),
+        }
+    }
+}
+
+/// Tier of a match distance, None when it is outside both
+pub fn tier(distance: f32, cli: &Cli) -> Option<Tier> {
+    if cli.fail_below.is_some_and(|fail| distance < fail) {
+        Some(Tier::Fail)
+    } else if cli.warn_below.is_some_and(|warn| distance < warn) {
+        Some(Tier::Warn)
+    } else {
+        None
+    }
+}
+
+/// One section per tier, listing its matches
+pub fn print_tiers(out: &mut dyn Write, input_seq_props: &[AsoProfile], cli: &Cli) -> io::Result<()> {
+    let tiers = [(Tier::Fail, cli.fail_below), (Tier::Warn, cli.warn_below)];
+    for (section, below) in tiers.iter().filter_map(|(tier, below)| below.map(|below| (tier, below))) {
+        let matches: Vec<(&AsoProfile, &AsoMatch)> = input_seq_props.iter()
+            .flat_map(|aso| aso.aso_names.iter().map(move |hit| (aso, hit)))
+            .filter(|(_, hit)| tier(hit.distance, cli) == Some(*section))
+            .collect();
+        writeln!(out, "\n{} (distance below {}): {} matches", section, below, matches.len())?;
+        for (aso, hit) in matches {
+            writeln!(out, "{:<10}\t{:<20}\t{:<10}\t{:<20}\t{}", aso.name, aso.seq, hit.aso.name,
+                     redact::shown(&hit.aso.seq, cli.redact_sequences),
+                     cli.list_by.primary().format(hit.distance, cli.precision))?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse distance=N or identity=F (identity as a fraction, 0-1)
+pub fn parse_gate(s: &str) -> Result<CloseMatchGate, String> {
+    let (kind, value) = s.split_once('=')
+        .ok_or(format!("Expected distance=N or identity=F, got {}", s))?;
+    let value = value.trim().parse::<f32>().map_err(|e| e.to_string())?;
+    match kind.trim().to_ascii_lowercase().as_str() {
+        "distance" => Ok(CloseMatchGate::Distance(value)),
+        "identity" if (0.0..=1.0).contains(&value) => Ok(CloseMatchGate::Identity(value)),
+        "identity" => Err(String::from("Identity must be between 0 and 1")),
+        other => Err(format!("Unknown gate {}, expected distance or identity", other)),
+    }
+}
+
+/// Fraction of positions with identical bases, over the longer sequence
+pub fn identity(a: &str, b: &str) -> f32 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 0.0
+    }
+    let same = a.chars().zip(b.chars()).filter(|(x, y)| x == y).count();
+    same as f32 / longest as f32
+}
+
+#[derive(Serialize)]
+pub struct Violation<'a> {
+    status: &'static str,
+    reason: &'static str,
+    input: &'a str,
+    #[serde(rename = "match")]
+    matched: &'a str,
+    distance: f32,
+    identity: f32,
+    gate: CloseMatchGate,
+}
+
+/// The first gate a match violates, if any
+fn tripped_gate(aso: &AsoProfile, hit: &AsoMatch, gates: &[CloseMatchGate]) -> Option<CloseMatchGate> {
+    let hit_identity = identity(&aso.seq, &mask::masked(aso, &hit.aso.seq));
+    gates.iter()
+        .find(|gate| match gate {
+            CloseMatchGate::Distance(max) => hit.distance <= *max,
+            CloseMatchGate::Identity(min) => hit_identity > *min,
+            CloseMatchGate::Below(min) => hit.distance < *min,
+        })
+        .copied()
+}
+
+/// FAIL when a match violates a gate, WARN when one is in the
+/// --warn-below tier, REVIEW when there are reported matches, PASS when
+/// there are none
+pub fn verdict(aso: &AsoProfile, cli: &Cli) -> &'static str {
+    let gates = gates(cli);
+    if aso.aso_names.iter().any(|hit| tripped_gate(aso, hit, &gates).is_some()) {
+        "FAIL"
+    } else if aso.aso_names.iter().any(|hit| tier(hit.distance, cli) == Some(Tier::Warn)) {
+        "WARN"
+    } else if !aso.aso_names.is_empty() {
+        "REVIEW"
+    } else {
+        "PASS"
+    }
+}
+
+pub fn close_matches<'a>(input_seq_props: &'a [AsoProfile],
+                         gates: &[CloseMatchGate]) -> Vec<Violation<'a>> {
+    let mut violations = Vec::new();
+    for aso in input_seq_props {
+        for hit in &aso.aso_names {
+            if let Some(gate) = tripped_gate(aso, hit, gates) {
+                let hit_identity = identity(&aso.seq, &mask::masked(aso, &hit.aso.seq));
+                violations.push(Violation {
+                    status: "fail",
+                    reason: "close_match",
+                    input: &aso.name,
+                    matched: &hit.aso.name,
+                    distance: hit.distance,
+                    identity: hit_identity,
+                    gate,
+                })
+            }
+        }
+    }
+    violations
+}
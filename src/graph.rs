@@ -0,0 +1,92 @@
+/*
+    Similarity graph export (--graph-file)
+        - nodes: inputs and the library ASOs they matched, with their kind
+          and sequence
+        - edges: reported matches, weighted by distance, so the cutoffs of
+          the run (max-distance, thresholds) decide what is connected
+        - GraphML for Cytoscape/Gephi, or DOT for Graphviz, by extension
+*/
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use log::info;
+use crate::{redact, AsoProfile};
+
+enum GraphFormat {
+    GraphMl,
+    Dot,
+}
+
+impl GraphFormat {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase()) {
+            Some(ext) if ext == "graphml" => Ok(GraphFormat::GraphMl),
+            Some(ext) if ext == "dot" || ext == "gv" => Ok(GraphFormat::Dot),
+            _ => Err(format!("Graph file {:?} must end in .graphml or .dot", path)),
+        }
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn write_graph(path: &Path, input_seq_props: &[AsoProfile], redact: bool) -> Result<(), Box<dyn Error>> {
+    let format = GraphFormat::from_path(path)?;
+    // node id -> (kind, label, sequence); library ASOs and inputs may share names
+    let mut nodes: BTreeMap<String, (&str, &str, Cow<str>)> = BTreeMap::new();
+    let mut edges = Vec::new();
+    for aso in input_seq_props {
+        let input_id = format!("input:{}", aso.name);
+        nodes.insert(input_id.clone(), ("input", &aso.name, Cow::Borrowed(&aso.seq)));
+        for hit in &aso.aso_names {
+            let library_id = format!("library:{}", hit.aso.name);
+            nodes.insert(library_id.clone(), ("library", &hit.aso.name, redact::shown(&hit.aso.seq, redact)));
+            edges.push((input_id.clone(), library_id, hit.distance));
+        }
+    }
+    info!("Writing similarity graph of {} nodes and {} edges to {:?}", nodes.len(), edges.len(), path);
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        GraphFormat::GraphMl => {
+            writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+            writeln!(writer, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+            writeln!(writer, r#"  <key id="kind" for="node" attr.name="kind" attr.type="string"/>"#)?;
+            writeln!(writer, r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#)?;
+            writeln!(writer, r#"  <key id="seq" for="node" attr.name="seq" attr.type="string"/>"#)?;
+            writeln!(writer, r#"  <key id="distance" for="edge" attr.name="distance" attr.type="double"/>"#)?;
+            writeln!(writer, r#"  <graph id="similarity" edgedefault="undirected">"#)?;
+            for (id, (kind, label, seq)) in &nodes {
+                writeln!(writer, r#"    <node id="{}"><data key="kind">{}</data><data key="label">{}</data><data key="seq">{}</data></node>"#,
+                         xml_escape(id), kind, xml_escape(label), xml_escape(seq))?;
+            }
+            for (source, target, distance) in &edges {
+                writeln!(writer, r#"    <edge source="{}" target="{}"><data key="distance">{}</data></edge>"#,
+                         xml_escape(source), xml_escape(target), distance)?;
+            }
+            writeln!(writer, "  </graph>\n</graphml>")?;
+        }
+        GraphFormat::Dot => {
+            writeln!(writer, "graph similarity {{")?;
+            for (id, (kind, label, seq)) in &nodes {
+                let shape = if *kind == "input" { "box" } else { "ellipse" };
+                writeln!(writer, "  \"{}\" [label=\"{}\", kind=\"{}\", seq=\"{}\", shape={}];",
+                         dot_escape(id), dot_escape(label), kind, dot_escape(seq), shape)?;
+            }
+            for (source, target, distance) in &edges {
+                writeln!(writer, "  \"{}\" -- \"{}\" [weight={}, label=\"{}\"];", dot_escape(source),
+                         dot_escape(target), distance, distance)?;
+            }
+            writeln!(writer, "}}")?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
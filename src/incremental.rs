@@ -0,0 +1,114 @@
+/*
+    Incremental checking against an updated library (--previous-manifest)
+        - the manifest of the earlier run lists a fingerprint of every
+          library entry and of every input, and the matches it reported
+        - library entries whose name and sequence are unchanged are not
+          compared again for inputs that were already screened the same
+          way (sequence, per-row overrides, masked and soft-masked
+          positions, duplex), their earlier matches are carried over
+        - new or changed entries are compared as usual; matches of removed
+          or changed entries are dropped
+    The matching parameters must be the same as in the earlier run, and
+    that run must have been complete: one stopped by --max-runtime or
+    --max-memory did not compare every entry it lists.
+*/
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use crate::error::CheckError;
+use crate::manifest::MatchSummary;
+use crate::{AsoProfile, Cli};
+
+/// Parameters that change which matches are reported
+/// (the mask is part of the input fingerprints, whichever file it came from)
+const MATCHING_PARAMETERS: [&str; 15] = ["list_by", "prefilter", "max_distance", "thresholds",
+                                         "threshold_mode", "orientation", "alphabet", "csv_locale",
+                                         "delimiter", "quote_char", "comment_prefix",
+                                         "library_orientation", "metric_options",
+                                         "softmask", "no_rna_normalization"];
+
+#[derive(Deserialize)]
+struct PreviousCounters {
+    #[serde(default)]
+    stopped_early: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PreviousManifest {
+    parameters: Value,
+    counters: PreviousCounters,
+    #[serde(default)]
+    library_entries: Vec<String>,
+    #[serde(default)]
+    input_entries: Vec<String>,
+    results: Vec<crate::manifest::InputSummary>,
+}
+
+pub struct PreviousRun {
+    entries: HashSet<String>,
+    /// Matches by input fingerprint
+    results: HashMap<String, Vec<MatchSummary>>,
+}
+
+/// Short fingerprint of a library entry
+pub fn entry_digest(aso: &AsoProfile) -> String {
+    let digest = Sha256::digest(format!("{}\t{}", aso.name, aso.seq).as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+/// Short fingerprint of an input and of everything that is compared for
+/// it alone: the per-row max distance and parent, the masked and
+/// soft-masked positions and the other strand of a duplex
+pub fn input_digest(aso: &AsoProfile) -> String {
+    let digest = Sha256::digest(format!("{}\t{}\t{:?}\t{:?}\t{:?}\t{:?}\t{:?}", aso.name, aso.seq,
+                                        aso.max_distance.map(f32::to_bits), aso.parent, aso.mask,
+                                        aso.softmasked, aso.duplex).as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+impl PreviousRun {
+    pub fn load(path: &Path, cli: &Cli) -> Result<Self, Box<dyn Error>> {
+        info!("Reading previous run from {:?}", path);
+        let previous: PreviousManifest = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if previous.library_entries.is_empty() || previous.input_entries.len() != previous.results.len() {
+            return Err(CheckError::Data(format!("{:?} has no library or input fingerprints, it was written by an older version",
+                                                path)).into())
+        }
+        if let Some(reason) = &previous.counters.stopped_early {
+            return Err(CheckError::Data(format!("{:?} is of a run that stopped early ({}), run a full check",
+                                                path, reason)).into())
+        }
+        let current = serde_json::to_value(cli)?;
+        let changed: Vec<&str> = MATCHING_PARAMETERS.iter()
+            .filter(|&&key| previous.parameters.get(key) != current.get(key))
+            .copied()
+            .collect();
+        if !changed.is_empty() {
+            return Err(CheckError::Usage(format!("Matching parameters differ from the previous run ({}), run a full check",
+                                                 changed.join(", "))).into())
+        }
+        Ok(PreviousRun {
+            entries: previous.library_entries.into_iter().collect(),
+            results: previous.input_entries.into_iter()
+                .zip(previous.results)
+                .map(|(digest, input)| (digest, input.matches))
+                .collect(),
+        })
+    }
+
+    /// The library entry was in the previous run, unchanged
+    pub fn knows(&self, library_aso: &AsoProfile) -> bool {
+        self.entries.contains(&entry_digest(library_aso))
+    }
+
+    /// Matches of an input screened the same way in the previous run
+    pub fn matches_of(&self, input: &AsoProfile) -> Option<&[MatchSummary]> {
+        self.results.get(&input_digest(input)).map(Vec::as_slice)
+    }
+}
@@ -0,0 +1,63 @@
+/*
+    IUPAC ambiguity-aware matching (--ambiguity aware)
+        - a code stands for the bases it allows: R is A or G, Y is C or T,
+          N is any base, and so on (U counts as T)
+        - two symbols match when the bases they allow overlap, so R vs A
+          is no mismatch but R vs C is, and N matches everything
+        - used by hamming and levenshtein; the other metrics and the
+          composition prefilter still compare plain characters
+        - --strict accepts the codes with it, whatever the alphabet
+    strict (the default) compares every symbol as a plain character, an N
+    differs from every base.
+*/
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum Ambiguity {
+    Strict,
+    Aware,
+}
+
+/// Bases a symbol allows, one bit each for A, C, G and T
+fn bases(symbol: char) -> Option<u8> {
+    let (a, c, g, t) = (1, 2, 4, 8);
+    Some(match symbol.to_ascii_uppercase() {
+        'A' => a,
+        'C' => c,
+        'G' => g,
+        'T' | 'U' => t,
+        'R' => a | g,
+        'Y' => c | t,
+        'S' => c | g,
+        'W' => a | t,
+        'K' => g | t,
+        'M' => a | c,
+        'B' => c | g | t,
+        'D' => a | g | t,
+        'H' => a | c | t,
+        'V' => a | c | g,
+        'N' => a | c | g | t,
+        _ => return None,
+    })
+}
+
+/// An IUPAC nucleotide code, plain bases included
+pub fn is_code(symbol: char) -> bool {
+    bases(symbol).is_some()
+}
+
+/// The two symbols can stand for the same base
+pub fn compatible(a: char, b: char, ambiguity: Ambiguity) -> bool {
+    a == b || ambiguity == Ambiguity::Aware
+        && matches!((bases(a), bases(b)), (Some(a), Some(b)) if a & b != 0)
+}
+
+/// Positions at which two sequences of the same length differ, None
+/// when the lengths differ
+pub fn hamming(a: &str, b: &str, ambiguity: Ambiguity) -> Option<usize> {
+    if a.chars().count() != b.chars().count() {
+        return None
+    }
+    Some(a.chars().zip(b.chars()).filter(|&(a, b)| !compatible(a, b, ambiguity)).count())
+}
@@ -0,0 +1,69 @@
+/*
+    Longest common subsequence metric (--list-by lcs)
+        - the most bases two sequences share in the same order, gaps
+          allowed in between
+        - reported as a distance: the length of the longer sequence minus
+          the LCS length, 0 for identical sequences
+        - an extra column gives the LCS length and its ratio to the length
+          of the longer sequence, how designers judge shared sequence
+*/
+
+/// Length of the longest common subsequence
+pub fn lcs_length(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous = vec![0; b.len() + 1];
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            current[j] = match a[i - 1] == b[j - 1] {
+                true => previous[j - 1] + 1,
+                false => previous[j].max(current[j - 1]),
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn longer(a: &str, b: &str) -> usize {
+    a.chars().count().max(b.chars().count())
+}
+
+/// LCS distance: length of the longer sequence minus the LCS length
+pub fn lcs(a: &str, b: &str) -> usize {
+    longer(a, b) - lcs_length(a, b)
+}
+
+/// LCS value of a match, e.g. "9 of 10 (0.90)"
+pub fn describe(input: &str, library: &str) -> String {
+    let length = lcs_length(input, library);
+    let longer = longer(input, library);
+    let ratio = if longer == 0 { 1.0 } else { length as f32 / longer as f32 };
+    format!("{} of {} ({:.2})", length, longer, ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lcs_is_the_bases_left_out_of_the_common_subsequence() {
+        assert_eq!(lcs("ACGT", "ACGT"), 0);
+        // all but the substituted base
+        assert_eq!(lcs_length("ACGTTGCAAC", "ACGTTGCATC"), 9);
+        assert_eq!(lcs("ACGTTGCAAC", "ACGTTGCATC"), 1);
+        // only one base in the same order
+        assert_eq!(lcs_length("ACGT", "TGCA"), 1);
+        // GCTAG, with gaps in both
+        assert_eq!(lcs_length("AGCTATG", "GGTCTAG"), 5);
+        assert_eq!(lcs("ACGT", ""), 4);
+    }
+
+    #[test]
+    fn describe_gives_the_length_and_ratio() {
+        assert_eq!(describe("ACGTTGCAAC", "ACGTTGCATC"), "9 of 10 (0.90)");
+        assert_eq!(describe("ACGT", "ACGTAC"), "4 of 6 (0.67)");
+        assert_eq!(describe("", ""), "0 of 0 (1.00)");
+    }
+}
@@ -0,0 +1,65 @@
+/*
+    Positional sequence liabilities of a candidate (--liabilities)
+        - GC skew, (G - C) / (G + C), of the 5' third, middle and 3' third,
+          n/a for a part without G or C
+        - longest run of purines (A, G, R) and of pyrimidines (C, T, U, Y),
+          with its 1-based position
+    Both correlate with synthesis failures and aggregation. Shown with the
+    other per-input values.
+*/
+
+pub fn is_purine(base: char) -> bool {
+    matches!(base, 'A' | 'G' | 'R')
+}
+
+pub fn is_pyrimidine(base: char) -> bool {
+    matches!(base, 'C' | 'T' | 'U' | 'Y')
+}
+
+fn skew(bases: &[char]) -> Option<f32> {
+    let g = bases.iter().filter(|&&b| b == 'G').count() as f32;
+    let c = bases.iter().filter(|&&b| b == 'C').count() as f32;
+    (g + c > 0.0).then(|| (g - c) / (g + c))
+}
+
+/// GC skew of the 5' third, the middle and the 3' third
+pub fn gc_skew(seq: &str) -> [Option<f32>; 3] {
+    let bases: Vec<char> = seq.chars().collect();
+    let third = bases.len() / 3;
+    let (five_prime, rest) = bases.split_at(third);
+    let (middle, three_prime) = rest.split_at(rest.len() - third);
+    [skew(five_prime), skew(middle), skew(three_prime)]
+}
+
+/// Length and 0-based start of the longest run of bases of a class
+pub fn longest_run(seq: &str, class: fn(char) -> bool) -> (usize, usize) {
+    let mut longest = (0, 0);
+    let mut run = 0;
+    for (position, base) in seq.chars().enumerate() {
+        run = if class(base) { run + 1 } else { 0 };
+        if run > longest.0 {
+            longest = (run, position + 1 - run);
+        }
+    }
+    longest
+}
+
+fn describe_run(name: &str, (length, start): (usize, usize)) -> String {
+    match length {
+        0 => format!("no {}", name),
+        _ => format!("{} {} at {}-{}", length, name, start + 1, start + length),
+    }
+}
+
+/// GC skew value of an input, e.g. "5' +0.33, middle -1.00, 3' n/a"
+pub fn describe_skew(seq: &str) -> String {
+    let [five_prime, middle, three_prime] = gc_skew(seq)
+        .map(|skew| skew.map_or(String::from("n/a"), |skew| format!("{:+.2}", skew)));
+    format!("5' {}, middle {}, 3' {}", five_prime, middle, three_prime)
+}
+
+/// Longest runs value of an input, e.g. "6 purines at 3-8, 2 pyrimidines at 1-2"
+pub fn describe_runs(seq: &str) -> String {
+    format!("{}, {}", describe_run("purines", longest_run(seq, is_purine)),
+            describe_run("pyrimidines", longest_run(seq, is_pyrimidine)))
+}
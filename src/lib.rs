@@ -0,0 +1,2179 @@
+/*
+    Input ASO sequence in 5' -> 3' orientation
+        - import input sequences
+    Check against a csv (comma, semicolon or tab separated) file of ASOs see
+        - [X] Similar ATGC content
+        - [X] Levenshtein distance
+        - [X] Hamming distance
+        - [X] sift3
+    Used as a library, the same checks run without the binary:
+        - Cli::from_args builds the settings from commandline-style options
+        - Library::load reads and profiles the library once,
+          Library::from_sequences takes (name, sequence) pairs in memory
+        - Query::load or Query::from_sequences gives the inputs,
+          Query::compare returns them with their matches
+*/
+mod alignment;
+mod alphabet;
+mod annotate;
+mod approved;
+mod balance;
+mod banded;
+mod batch;
+mod calibrate;
+mod candidates;
+mod clean;
+mod columns;
+mod compare;
+mod compression;
+mod conflicts;
+mod coverage;
+mod d2;
+mod damerau;
+mod dedup;
+mod dialect;
+mod encoding;
+mod error;
+mod explain;
+mod export;
+mod fasta;
+mod focus;
+mod gate;
+mod graph;
+mod incremental;
+mod iupac;
+mod lcs;
+mod liabilities;
+mod limits;
+mod lint;
+mod locale;
+mod logo;
+mod manifest;
+mod myers;
+mod names;
+mod output;
+mod mask;
+mod null;
+mod overrides;
+mod pairs;
+mod peptide;
+mod preset;
+mod qgram;
+mod redact;
+mod revcomp;
+mod rna;
+mod scan;
+mod selftest;
+mod snapshot;
+mod softmask;
+mod sources;
+mod sqlite;
+mod tui;
+mod validate;
+mod warnings;
+mod windows;
+mod xlsx;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use clap::builder::Resettable;
+use clap::{ArgAction, Args, ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use csv::{ReaderBuilder, StringRecord, Trim};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+use distance::{hamming, sift3};
+use encoding::DecodingReader;
+use error::CheckError;
+use fasta::FileFormat;
+use names::{NameSanitizer, Rename};
+use softmask::Softmask;
+use sources::SourceStats;
+use manifest::{FileRecord, HashingReader, Manifest, Timings};
+use overrides::OverrideColumns;
+use alignment::AlignmentScores;
+use alphabet::{parse_alphabet, Alphabet};
+use columns::{parse_column, ColumnArgs, ColumnSpec, RecordColumns};
+use conflicts::OnNameConflict;
+use dialect::DialectArgs;
+use gate::{parse_gate, CloseMatchGate};
+use candidates::Candidates;
+use incremental::PreviousRun;
+use iupac::Ambiguity;
+use limits::{parse_duration, parse_size, Limits};
+use locale::CsvLocale;
+use preset::Preset;
+use warnings::{WarningKind, Warnings};
+
+#[derive(Parser, Debug, Clone, Serialize)]
+#[command(author, version, about, long_about = None,
+args_conflicts_with_subcommands = true, subcommand_negates_reqs = true, after_help = error::EXIT_CODES,
+group(ArgGroup::new("destination").args(["results_file", "output"]).multiple(true)))]
+pub struct Cli {
+    #[command(subcommand)]
+    #[serde(skip)]
+    command: Option<Command>,
+    /// Input ASO sequence. One sequence, in 5' -> 3' orientation
+    #[arg(short='a', long="aso-seq")]
+    aso_seq: Option<String>,
+    /// Process multiple ASO sequences. Conflicts with aso-seq option.
+    /// Requires input-aso-file argument
+    #[arg(short='m', long= "multiple-aso-seq", conflicts_with = "aso_seq")]
+    multiple_aso: bool,
+    /// path to input ASO sequences
+    /// in csv format, ASO name in column1,
+    /// ASO sequences in 5' -> 3' orientation in column2
+    /// Any additional information can be entered in lines
+    /// starting with #. They won't be read.
+    /// Optional max_distance, orientation and parent columns override
+    /// the matching options for that row.
+    /// - reads stdin, e.g. grep ... | aso_scramble_check -m --input-aso-file -
+    #[arg(long="input-aso-file", requires = "multiple_aso", conflicts_with = "aso_seq")]
+    input_aso_file: Option<PathBuf>,
+    /// no headers in the input file
+    #[arg(long="input-no-header", requires = "input_aso_file",
+    action=ArgAction::SetFalse, group = "multi-aso", conflicts_with = "aso_seq")]
+    input_header_status: bool,
+    /// Number of columns every input file row must have.
+    /// Rows with more or fewer are reported as errors.
+    #[arg(long="input-expect-columns", requires = "input_aso_file")]
+    input_expect_columns: Option<usize>,
+    /// Format of the input file, like --library-format: csv, fasta,
+    /// fastq or auto (xlsx is read for libraries only). FASTA and FASTQ
+    /// inputs have no override columns
+    #[arg(long="input-format", value_enum, ignore_case = true, default_value_t = FileFormat::Auto,
+    requires = "input_aso_file")]
+    input_format: FileFormat,
+    /// path to library of existing ASOs
+    /// in csv format, ASO name in column1
+    /// ASO sequence in 5' -> 3' orientation in column2,
+    /// or in FASTA format (see --library-format)
+    /// Any additional information can be entered in lines
+    /// starting with #. They won't be read.
+    /// Named pipes and process substitution, e.g. <(zcat lib.csv.gz),
+    /// work for both files: each is read exactly once. - reads stdin.
+    /// gzip and zstd compressed files are decompressed as they are read.
+    /// Repeat, or give a comma-separated list, to check against several
+    /// libraries at once: each match is tagged with its library in a
+    /// Library column and each library is summarized as a source at the
+    /// end of the run.
+    /// A table of an SQLite database is read with
+    /// sqlite://registry.db?table=asos (columns name and seq, or
+    /// &name=...&seq=...), only the rows that can pass --prefilter.
+    #[arg(short='l', long="library-aso-file", alias="library", name="libfile", value_delimiter = ',',
+    required_unless_present_any = ["scan_file", "library_snapshot"])]
+    library_aso_file: Vec<PathBuf>,
+    /// no headers in the library file
+    #[arg(long="library-no-header", name="lib_header", requires = "libfile",
+    action=ArgAction::SetFalse)]
+    library_header_status: bool,
+    /// Number of columns every library row must have.
+    /// Rows with more or fewer are reported as errors.
+    #[arg(long="library-expect-columns")]
+    library_expect_columns: Option<usize>,
+    /// Format of the library files.
+    /// csv: name in column1, sequence in column2;
+    /// fasta: >name header lines, each followed by its sequence, which
+    /// may be wrapped over several lines;
+    /// fastq: @name, sequence, + and quality lines;
+    /// xlsx: Excel workbook, columns as in csv, see --sheet;
+    /// auto: xlsx for a .xlsx or .xlsm file, fasta for a file starting
+    /// with >, fastq for one starting with @, csv otherwise
+    #[arg(long="library-format", value_enum, ignore_case = true, default_value_t = FileFormat::Auto)]
+    library_format: FileFormat,
+    /// Worksheet of an xlsx library to read. Default: the first
+    #[arg(long="sheet")]
+    sheet: Option<String>,
+    /// Load the library from a snapshot written by
+    /// --export-library-snapshot instead of reading library files
+    #[arg(long="library-snapshot", conflicts_with = "libfile")]
+    library_snapshot: Option<PathBuf>,
+    /// Write the library, as parsed and validated, to a snapshot file
+    /// that --library-snapshot loads elsewhere without parsing it again.
+    /// Without inputs to check, the run ends after writing it
+    #[arg(long="export-library-snapshot")]
+    export_library_snapshot: Option<PathBuf>,
+    /// Display only which distance? Default: Levenshtein
+    /// Distance: higher the number, greater the mismatch between sequences
+    /// damerau: Levenshtein counting a swap of adjacent bases as one edit
+    /// needleman: global alignment, see match-score, mismatch-score, gap-score
+    /// smith-waterman: local alignment with the same scores, the aligned
+    /// regions are listed in an extra column
+    /// qgram: difference of the counts of every substring of qgram-size
+    /// bases, alignment-free
+    /// cosine: 1 - cosine similarity of k-mer frequencies (cosine-k), 0 to 1
+    /// d2, d2s: alignment-free D2 and D2S statistics of k-mer counts
+    /// (d2-k) as dissimilarities, 0 for the same k-mer profile
+    /// lcs: bases not in the longest common subsequence, with the LCS
+    /// length and ratio in an extra column
+    /// all: compute and display every distance, sorted by Levenshtein
+    #[arg(long="list-by", name="List",
+    value_enum, ignore_case = true, default_value_t= Dist::Levenshtein)]
+    list_by: Dist,
+    /// Strand of the library sequences.
+    /// antisense: ASO sequences; sense: target sites, reverse complemented
+    /// before comparison; mixed: per row, from an orientation column
+    /// (sense or antisense, empty is antisense)
+    #[arg(long="library-orientation", value_enum, ignore_case = true,
+    default_value_t = LibraryOrientation::Antisense)]
+    library_orientation: LibraryOrientation,
+    #[command(flatten)]
+    metric_options: MetricOptions,
+    /// Which library ASOs are compared at all.
+    /// composition: same length and same count of each alphabet symbol
+    /// length: same length only
+    /// none: every library ASO, whatever its length, e.g. for
+    /// smith-waterman against longer oligos
+    /// auto: like none, but with a levenshtein max-distance only the
+    /// library ASOs that can be within it are compared, found by the
+    /// strategy predicted fastest (logged)
+    #[arg(long="prefilter", value_enum, ignore_case = true,
+    default_value_t = Prefilter::Composition)]
+    prefilter: Prefilter,
+    /// Report only matches at or below this distance
+    #[arg(long="max-distance")]
+    max_distance: Option<f32>,
+    /// Per-metric thresholds, e.g. hamming=2,levenshtein=3.
+    /// A match is reported when its thresholds are violated
+    /// (distance at or below the threshold), see threshold-mode
+    #[arg(long="threshold", value_delimiter = ',', value_parser = parse_threshold)]
+    thresholds: Vec<(Dist, f32)>,
+    /// Report a match if any or all of the thresholds are violated
+    #[arg(long="threshold-mode", value_enum, ignore_case = true,
+    default_value_t = ThresholdMode::Any, requires = "thresholds")]
+    threshold_mode: ThresholdMode,
+    /// Also check the reverse complement of each input ASO
+    /// (reported as <name>_rc)
+    #[arg(long="orientation", value_enum, ignore_case = true,
+    default_value_t = Orientation::Forward)]
+    orientation: Orientation,
+    /// Screening preset bundling prefilter, metric, threshold and
+    /// orientation. Individual flags override the preset.
+    #[arg(long="preset", value_enum, ignore_case = true)]
+    preset: Option<Preset>,
+    /// Calibration profile written by the calibrate subcommand, read
+    /// by --preset calibrated
+    #[arg(long="calibration")]
+    calibration: Option<PathBuf>,
+    /// Browse the results interactively instead of printing them
+    #[arg(long="tui", conflicts_with_all = ["output", "output_format"])]
+    tui: bool,
+    /// File the e key of --tui exports the current view to (tsv)
+    #[arg(long="tui-export", requires = "tui")]
+    tui_export: Option<PathBuf>,
+    /// Report at most this many matches per input, closest first; the
+    /// table notes how many more there are, json keeps the full count
+    #[arg(long="max-hits")]
+    max_hits: Option<usize>,
+    /// Print unpadded tab-separated rows, one per match, with no
+    /// header: input name, input seq, match name, match seq, distance(s)
+    /// and match columns, then the input's values such as "Verdict: PASS"
+    /// (an input with values but no match gets a row with empty match
+    /// columns)
+    #[arg(long="raw", conflicts_with = "tui")]
+    raw: bool,
+    /// Format of the results on stdout.
+    /// table: aligned columns for reading;
+    /// json: one document with the metric and every input with its
+    /// matches, the same layout as the manifest results
+    #[arg(long="output-format", value_enum, ignore_case = true,
+    default_value_t = OutputFormat::Table, conflicts_with_all = ["raw", "explain", "by_length"])]
+    output_format: OutputFormat,
+    /// Locale of the csv files.
+    /// standard: comma separated, decimal point;
+    /// european: semicolon separated, decimal comma (Excel in most of Europe);
+    /// auto: separator guessed from the first line, either decimal mark
+    #[arg(long="csv-locale", value_enum, ignore_case = true, default_value_t = CsvLocale::Auto)]
+    csv_locale: CsvLocale,
+    #[command(flatten)]
+    #[serde(flatten)]
+    dialect: DialectArgs,
+    /// Sequence alphabet: dna, rna, iupac, or custom:SYMBOLS/COMPLEMENTS
+    /// (e.g. custom:ACGTX/TGCAX). Drives validation, composition and
+    /// reverse complementation.
+    #[arg(long="alphabet", value_parser = parse_alphabet, default_value = "dna")]
+    alphabet: Alphabet,
+    /// Input rows hold a duplex (siRNA-style): the second strand in
+    /// passenger-column, reported as <name>_passenger. Both strands are screened
+    /// and each gets a summary of the duplex: base pairs between the
+    /// strands and library matches across both
+    #[arg(long="duplex", requires = "input_aso_file")]
+    duplex: bool,
+    /// Column of the --duplex passenger strand: a number (from 1) or a
+    /// header name. Default: the first column that is neither the name
+    /// nor the sequence, column3
+    #[arg(long="passenger-column", value_parser = parse_column, requires = "duplex")]
+    passenger_column: Option<ColumnSpec>,
+    /// csv of name,start,end rows: 1-based position ranges of an input
+    /// excluded from comparison (e.g. known tolerant positions)
+    #[arg(long="mask-file")]
+    mask_file: Option<PathBuf>,
+    /// no headers in the mask file
+    #[arg(long="mask-no-header", requires = "mask_file", action=ArgAction::SetFalse)]
+    mask_header_status: bool,
+    /// Clean up ASO names on load: whitespace stripped or replaced by _,
+    /// names repeated in a file made unique with a suffix (_2, _3, ...)
+    #[arg(long="sanitize-names")]
+    sanitize_names: bool,
+    /// Longest ASO name kept by sanitize-names, longer ones are cut
+    #[arg(long="max-name-length", requires = "sanitize_names")]
+    max_name_length: Option<usize>,
+    /// Write a csv table of every name changed by sanitize-names or
+    /// on-name-conflict: file, line, original and new name
+    #[arg(long="name-map")]
+    name_map: Option<PathBuf>,
+    /// Bases trimmed from the 5' end of every input sequence before it is
+    /// profiled (e.g. cloning adapters or overhangs). The trimmed sequence
+    /// is used for composition, metrics and output
+    #[arg(long="trim5", default_value_t = 0)]
+    trim5: usize,
+    /// Bases trimmed from the 3' end of every input sequence, see trim5
+    #[arg(long="trim3", default_value_t = 0)]
+    trim3: usize,
+    /// Fail on any sequence that is empty or contains anything outside
+    /// the alphabet (lowercase, whitespace, foreign characters),
+    /// reporting file, line and column
+    #[arg(long="strict")]
+    strict: bool,
+    /// Exit with a non-zero code if any input has a match at or below
+    /// a distance or above a positional identity, e.g. distance=2 or
+    /// identity=0.9. Each violation is printed to stderr as JSON.
+    #[arg(long="fail-on-close-match", value_delimiter = ',', value_parser = parse_gate)]
+    fail_on_close_match: Vec<CloseMatchGate>,
+    /// Matches strictly below this distance fail: they are listed in a
+    /// FAIL section and the run exits with code 3, like fail-on-close-match
+    #[arg(long="fail-below")]
+    fail_below: Option<f32>,
+    /// Matches below this distance, but not below fail-below, only warn:
+    /// they are listed in a WARN section and don't change the exit code
+    #[arg(long="warn-below")]
+    warn_below: Option<f32>,
+    /// What to do with rows whose sequence cell is empty or missing.
+    /// skip: drop silently, warn: drop with a warning, fail: stop.
+    /// --strict always fails. A library or input left with no usable
+    /// rows still gets its (empty) results, and the run exits with code 5.
+    #[arg(long="on-empty", value_enum, ignore_case = true, default_value_t = OnEmpty::Warn)]
+    on_empty: OnEmpty,
+    /// What to do with library rows whose name another row has with a
+    /// different sequence. keep-all: keep them, renamed name_2, name_3, ...;
+    /// keep-first: keep only the first sequence of the name; fail: stop.
+    /// Every conflict is reported
+    #[arg(long="on-name-conflict", value_enum, ignore_case = true, default_value_t = OnNameConflict::KeepAll)]
+    on_name_conflict: OnNameConflict,
+    #[command(flatten)]
+    #[serde(flatten)]
+    columns: ColumnArgs,
+    /// Report the longest complementary run anchored at the 3' end of
+    /// the input or the match (primer-dimer like interactions)
+    #[arg(long="three-prime")]
+    three_prime: bool,
+    /// Report the per-base composition of each match relative to the
+    /// input, e.g. A:+1,G:-1. Useful with --prefilter length.
+    #[arg(long="composition-delta")]
+    composition_delta: bool,
+    /// Order of matches per input.
+    /// distance: by distance;
+    /// composition: most similar composition first, then by distance
+    #[arg(long="sort-by", value_enum, ignore_case = true, default_value_t = SortBy::Distance)]
+    sort_by: SortBy,
+    /// Group the report by input length, each length class headed by
+    /// a summary of its inputs and matches
+    #[arg(long="by-length", conflicts_with = "raw")]
+    by_length: bool,
+    /// Invert the report: rank every input by similarity to this library
+    /// ASO (e.g. a lead compound), closest first
+    #[arg(long="focus", requires = "libfile", conflicts_with = "pairs")]
+    focus: Option<String>,
+    /// Instead of screening each input whole, screen every window of
+    /// this many bases of it against the library and for liabilities, and
+    /// report the best and worst windows (long inputs, e.g. target regions)
+    #[arg(long="window", requires = "libfile", conflicts_with_all = ["focus", "pairs"])]
+    window: Option<usize>,
+    /// Bases between the starts of consecutive windows
+    #[arg(long="window-step", requires = "window", default_value_t = 1)]
+    window_step: usize,
+    /// Best and worst windows reported per input
+    #[arg(long="window-top", requires = "window", default_value_t = 3)]
+    window_top: usize,
+    /// Instead of screening, compute every metric for just the pairs in
+    /// this csv file (name1,name2, names of library or input ASOs)
+    #[arg(long="pairs", requires = "libfile")]
+    pairs: Option<PathBuf>,
+    /// no headers in the pairs file
+    #[arg(long="pairs-no-header", requires = "pairs", action=ArgAction::SetFalse)]
+    pairs_header_status: bool,
+    /// Instead of comparing against a library, report every position
+    /// where an input or its reverse complement matches a sequence of
+    /// this csv file (any length, name in column1, sequence in column2)
+    /// with at most scan-mismatches mismatches
+    #[arg(long="scan-file", conflicts_with = "libfile")]
+    scan_file: Option<PathBuf>,
+    /// Mismatches allowed by scan-file
+    #[arg(long="scan-mismatches", requires = "scan_file", default_value_t = 2)]
+    scan_mismatches: usize,
+    /// no headers in the scan file
+    #[arg(long="scan-no-header", requires = "scan_file", action=ArgAction::SetFalse)]
+    scan_header_status: bool,
+    /// Also write the scan-file sites as a BED or GFF3 track,
+    /// chosen by the extension (.bed, .gff, .gff3)
+    #[arg(long="sites-file", requires = "scan_file")]
+    sites_file: Option<PathBuf>,
+    /// Write which regions of the scan-file sequences the inputs cover
+    /// and which are untargeted, as BED or wig (by extension). Pass the
+    /// library as input file to see what it already covers
+    #[arg(long="coverage-file", requires = "scan_file")]
+    coverage_file: Option<PathBuf>,
+    /// Library column (by header name) holding the target or project of
+    /// each ASO. Matches are labelled with it and summarized per input,
+    /// e.g. "3 matches, all from HTT"
+    #[arg(long="group-by", requires = "libfile", conflicts_with = "lib_header")]
+    group_by: Option<String>,
+    /// Flag matches whose metrics differ by at least this much
+    /// (e.g. hamming 6 but levenshtein 2), a sign of shifted alignment.
+    /// Needs more than one metric: list-by all, or thresholds
+    #[arg(long="flag-disagreement")]
+    flag_disagreement: Option<f32>,
+    /// Threads comparing library ASOs in parallel. Default: one per core.
+    /// The output is the same, byte for byte, for any number of threads
+    #[arg(long="threads", default_value_t = 0, hide_default_value = true)]
+    threads: usize,
+    /// Stop comparing after this long (e.g. 90s, 15m, 2h), write the
+    /// matches found so far and exit with code 4
+    #[arg(long="max-runtime", value_parser = parse_duration)]
+    max_runtime: Option<Duration>,
+    /// Stop comparing once the process uses this much memory
+    /// (e.g. 512M, 4G), write the matches found so far and exit with
+    /// code 4. Linux only
+    #[arg(long="max-memory", value_parser = parse_size)]
+    max_memory: Option<u64>,
+    /// Explain each reported match under its row: why it passed the
+    /// prefilter, shared k-mers, every metric against its cutoff and
+    /// where the mismatches are
+    #[arg(long="explain", conflicts_with = "raw")]
+    explain: bool,
+    /// Compare each match against this many composition-preserving
+    /// shuffles of it and report the z-score and empirical p-value of
+    /// the observed distance
+    #[arg(long="null-shuffles")]
+    null_shuffles: Option<usize>,
+    /// Seed of the null-shuffles, for reproducible p-values
+    #[arg(long="null-seed", requires = "null_shuffles", default_value_t = 0)]
+    null_seed: u64,
+    /// Treatment of lowercase (soft-masked) bases, in inputs and library.
+    /// none: read as uppercase;
+    /// ignore: left out of the composition, never a difference;
+    /// downweight: a difference there counts half.
+    /// Without it lowercase bases are uppercased, like none.
+    #[arg(long="softmask", value_enum, ignore_case = true)]
+    softmask: Option<Softmask>,
+    /// Keep U and T as written; by default U is read as T (T as U with
+    /// the rna alphabet), so RNA and DNA notations compare equal
+    #[arg(long="no-rna-normalization")]
+    no_rna_normalization: bool,
+    /// Report every input on its own; by default inputs with the same
+    /// sequence are checked once, under the first name, the others listed
+    /// as Identical
+    #[arg(long="no-group-identical")]
+    no_group_identical: bool,
+    /// Show library sequences only as SHA-256 digests, in the results
+    /// and in every file written, for reports shared outside; names,
+    /// distances and verdicts are kept
+    #[arg(long="redact-sequences", conflicts_with_all = ["tui", "pairs", "logo_dir"])]
+    redact_sequences: bool,
+    /// Sequence of the parent ASO the inputs were designed from, e.g. the
+    /// one they scramble: each input gets its distance to it, by every
+    /// metric of list-by. A parent column of the input file sets it per input.
+    #[arg(long="parent")]
+    parent: Option<String>,
+    /// Add positional liabilities to each input: GC skew of its 5' third,
+    /// middle and 3' third, and its longest purine and pyrimidine runs
+    #[arg(long="liabilities")]
+    liabilities: bool,
+    /// Flag inputs whose least balanced window of balance-window bases
+    /// has a composition entropy below this fraction (0-1) of the most
+    /// even one, e.g. all purines in the first half
+    #[arg(long="min-balance")]
+    min_balance: Option<f32>,
+    /// Window of min-balance, in bases
+    #[arg(long="balance-window", requires = "min_balance", default_value_t = 8)]
+    balance_window: usize,
+    /// Don't screen inputs below min-balance at all, with a warning
+    #[arg(long="reject-unbalanced", requires = "min_balance")]
+    reject_unbalanced: bool,
+    /// Add a verdict per input ASO: FAIL when a match violates
+    /// fail-on-close-match or fail-below, WARN when one is below
+    /// warn-below, REVIEW when any match is reported, PASS otherwise
+    #[arg(long="verdict")]
+    verdict: bool,
+    /// Decimal places of non-integer distances (sift3) in the output.
+    /// Hamming, Levenshtein and Damerau are always written as integers
+    #[arg(long="precision")]
+    precision: Option<usize>,
+    /// Score each input against a position weight matrix built from its
+    /// same-length matches: how typical is the candidate of that family
+    #[arg(long="pwm")]
+    pwm: bool,
+    /// Write a sequence logo (SVG) of each input's match family into
+    /// this directory
+    #[arg(long="logo-dir")]
+    logo_dir: Option<PathBuf>,
+    /// Matches of the input's length needed before a logo is drawn
+    #[arg(long="logo-min-matches", default_value_t = 5, requires = "logo_dir")]
+    logo_min_matches: usize,
+    /// Manifest of an earlier run against an older version of the
+    /// library: only new or changed library entries are compared, earlier
+    /// matches of unchanged entries are carried over
+    #[arg(long="previous-manifest")]
+    previous_manifest: Option<PathBuf>,
+    /// Write the reported matches as a similarity graph, edges weighted
+    /// by distance: GraphML (.graphml) for Cytoscape/Gephi or DOT (.dot)
+    #[arg(long="graph-file")]
+    graph_file: Option<PathBuf>,
+    /// Also write the results to a structured file, CSV or JSON lines
+    /// depending on the extension (.csv, .jsonl). Every row carries the
+    /// run identifier; in CSV, annotations are rows with the annotation
+    /// and value columns set.
+    #[arg(long="results-file")]
+    results_file: Option<PathBuf>,
+    /// Write the results to this file instead of stdout, in the chosen
+    /// output format. The file is replaced in one step once the run is
+    /// done, see append
+    #[arg(long="output", short='o')]
+    output: Option<PathBuf>,
+    /// Add to the output and results files instead of overwriting them,
+    /// so successive runs accumulate in one file
+    #[arg(long="append", requires = "destination")]
+    append: bool,
+    /// Identifier recorded with every result row and in the manifest.
+    /// Default: start time and process id
+    #[arg(long="run-id")]
+    run_id: Option<String>,
+    /// Write a machine-readable run manifest (JSON) to this path.
+    /// Captures parameters, file hashes, versions, timings, counters
+    /// and a summary of the results, for audit trails.
+    #[arg(long="manifest")]
+    manifest: Option<PathBuf>,
+}
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Run several checks described in a TOML job file. Each [[job]]
+    /// table holds long commandline options as keys, e.g.
+    /// library-aso-file = "lib.csv". Jobs sharing a library load it once.
+    Batch {
+        /// TOML job file
+        job_file: PathBuf,
+        /// Run jobs using different libraries in parallel
+        #[arg(long="parallel")]
+        parallel: bool,
+    },
+    /// Fit a max-distance for every metric to sequence pairs with known
+    /// cross-reactivity (sequence, sequence and outcome columns, outcome
+    /// 1/0, yes/no or true/false), print the fits and write the best to
+    /// a profile for --preset calibrated
+    Calibrate {
+        /// csv file of training pairs
+        file: PathBuf,
+        /// Calibration profile (TOML) to write
+        #[arg(long="profile")]
+        profile: PathBuf,
+        /// no headers in the file
+        #[arg(long="no-header", action=ArgAction::SetFalse)]
+        header: bool,
+    },
+    /// Compare two structured result files (JSON) and report which
+    /// matches appeared, disappeared, or changed distance
+    CompareRuns {
+        /// Results of the earlier run
+        old: PathBuf,
+        /// Results of the later run
+        new: PathBuf,
+    },
+    /// Write the candidates of a run that passed all checks to a csv
+    /// order file (name, sequence, scale, chemistry), ready for a
+    /// synthesis vendor. Nothing is ordered
+    ExportApproved {
+        /// Results of the run (JSON: --output-format json or the manifest)
+        results: PathBuf,
+        /// Order file to write, stdout without it
+        #[arg(long="output", short='o')]
+        output: Option<PathBuf>,
+        /// Verdicts approved: pass (no matches), review, warn or fail,
+        /// see --verdict
+        #[arg(long="accept", value_enum, ignore_case = true, value_delimiter = ',', default_value = "pass")]
+        accept: Vec<approved::Verdict>,
+        /// Candidates with a match at or below this distance fail
+        #[arg(long="max-distance")]
+        max_distance: Option<f32>,
+        /// Synthesis scale of every candidate, e.g. 25nm
+        #[arg(long="scale")]
+        scale: String,
+        /// Chemistry of every candidate, e.g. "PS 2'-MOE gapmer"
+        #[arg(long="chemistry")]
+        chemistry: String,
+    },
+    /// Reverse complement the sequences of a csv file (name in column1,
+    /// sequence in column2 or seq-column) or a FASTA file and write it to
+    /// stdout. Names, headers and any other columns are kept
+    Revcomp {
+        /// csv or FASTA file of sequences
+        file: PathBuf,
+        /// no headers in the file
+        #[arg(long="no-header", action=ArgAction::SetFalse)]
+        header: bool,
+        /// Sequence alphabet: dna, rna, iupac, or custom:SYMBOLS/COMPLEMENTS
+        #[arg(long="alphabet", value_parser = parse_alphabet, default_value = "dna")]
+        alphabet: Alphabet,
+        /// Column of the sequence: a number (from 1) or a header name.
+        /// Default: column2
+        #[arg(long="seq-column", value_parser = parse_column)]
+        seq_column: Option<ColumnSpec>,
+        /// Locale of the file, see --csv-locale
+        #[arg(long="csv-locale", value_enum, ignore_case = true, default_value_t = CsvLocale::Auto)]
+        csv_locale: CsvLocale,
+        #[command(flatten)]
+        dialect: DialectArgs,
+    },
+    /// Check every distance metric against sequence pairs with known
+    /// values, including empty sequences and ambiguity codes, and print
+    /// a pass/fail report. Exits with code 1 if any case fails
+    Selftest,
+    /// Lint a library or input csv file (name in column1, sequence in
+    /// column2, or as --name-column and --seq-column say) without comparing anything: column counts, duplicate
+    /// names, invalid characters, empty sequences. Prints a report per
+    /// row and exits with code 6 if any row has an error
+    Validate {
+        /// csv file of sequences
+        file: PathBuf,
+        /// no headers in the file
+        #[arg(long="no-header", action=ArgAction::SetFalse)]
+        header: bool,
+        /// Sequence alphabet: dna, rna, iupac, or custom:SYMBOLS/COMPLEMENTS
+        #[arg(long="alphabet", value_parser = parse_alphabet, default_value = "dna")]
+        alphabet: Alphabet,
+        /// aware: accept IUPAC ambiguity codes whatever the alphabet
+        #[arg(long="ambiguity", value_enum, ignore_case = true, default_value_t = Ambiguity::Strict)]
+        ambiguity: Ambiguity,
+        /// Rows must have exactly this many columns
+        #[arg(long="expect-columns")]
+        expect_columns: Option<usize>,
+        #[command(flatten)]
+        columns: ColumnArgs,
+        /// Locale of the file, see --csv-locale
+        #[arg(long="csv-locale", value_enum, ignore_case = true, default_value_t = CsvLocale::Auto)]
+        csv_locale: CsvLocale,
+        #[command(flatten)]
+        dialect: DialectArgs,
+    },
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize, Deserialize)]
+pub enum Dist {
+    Hamming,
+    Levenshtein,
+    Sift3,
+    Damerau,
+    Needleman,
+    SmithWaterman,
+    Qgram,
+    Cosine,
+    D2,
+    D2s,
+    Lcs,
+    All,
+}
+
+// Parameters of the metrics that have any (not a doc comment, see
+// alignment::AlignmentScores)
+#[derive(Args, Debug, Clone, Copy, Serialize)]
+pub struct MetricOptions {
+    #[command(flatten)]
+    #[serde(flatten)]
+    scores: AlignmentScores,
+    /// Length of the substrings counted by the qgram metric
+    #[arg(long="qgram-size", value_parser = qgram::parse_qgram_size,
+    default_value_t = qgram::DEFAULT_QGRAM_SIZE)]
+    qgram_size: usize,
+    /// Length of the k-mers whose frequencies the cosine metric compares
+    #[arg(long="cosine-k", value_parser = qgram::parse_qgram_size,
+    default_value_t = qgram::DEFAULT_QGRAM_SIZE)]
+    cosine_k: usize,
+    /// Length of the k-mers the d2 and d2s metrics count
+    #[arg(long="d2-k", value_parser = d2::parse_d2_k, default_value_t = qgram::DEFAULT_QGRAM_SIZE)]
+    d2_k: usize,
+    /// IUPAC codes in hamming and levenshtein.
+    /// strict: every symbol is a plain character, N differs from A;
+    /// aware: codes match the bases they allow, R matches A and G
+    #[arg(long="ambiguity", value_enum, ignore_case = true, default_value_t = Ambiguity::Strict)]
+    ambiguity: Ambiguity,
+}
+
+impl Default for MetricOptions {
+    fn default() -> Self {
+        MetricOptions {
+            scores: AlignmentScores::default(),
+            qgram_size: qgram::DEFAULT_QGRAM_SIZE,
+            cosine_k: qgram::DEFAULT_QGRAM_SIZE,
+            d2_k: qgram::DEFAULT_QGRAM_SIZE,
+            ambiguity: Ambiguity::Strict,
+        }
+    }
+}
+
+impl Dist {
+    /// The individual metrics computed for this choice
+    fn metrics(self) -> Vec<Dist> {
+        match self {
+            Dist::All => vec![Dist::Hamming, Dist::Levenshtein, Dist::Sift3, Dist::Damerau,
+                              Dist::Needleman, Dist::SmithWaterman, Dist::Qgram, Dist::Cosine,
+                              Dist::D2, Dist::D2s, Dist::Lcs],
+            metric => vec![metric],
+        }
+    }
+
+    /// The metric used for sorting and max-distance
+    fn primary(self) -> Dist {
+        match self {
+            Dist::All => Dist::Levenshtein,
+            metric => metric,
+        }
+    }
+
+    /// Distance between two sequences, or why the metric doesn't apply
+    /// to this pair (e.g. hamming of sequences of different lengths)
+    pub fn distance(self, a: &str, b: &str, options: &MetricOptions) -> Result<f32, String> {
+        match self {
+            Dist::Hamming => match options.ambiguity {
+                Ambiguity::Strict => hamming(a, b).map(|d| d as f32).ok(),
+                Ambiguity::Aware => iupac::hamming(a, b, options.ambiguity).map(|d| d as f32),
+            }.ok_or(String::from("sequences differ in length")),
+            Dist::Levenshtein => Ok(myers::levenshtein(a, b, options.ambiguity) as f32),
+            Dist::Sift3 => Ok(sift3(a, b)),
+            Dist::Damerau => Ok(damerau::damerau(a, b) as f32),
+            Dist::Needleman => Ok(alignment::needleman(a, b, &options.scores)),
+            Dist::SmithWaterman => Ok(alignment::smith_waterman(a, b, &options.scores)),
+            Dist::Qgram => Ok(qgram::qgram(a, b, options.qgram_size) as f32),
+            Dist::Cosine => Ok(qgram::cosine(a, b, options.cosine_k)),
+            Dist::D2 => Ok(d2::d2(a, b, options.d2_k)),
+            Dist::D2s => Ok(d2::d2s(a, b, options.d2_k)),
+            Dist::Lcs => Ok(lcs::lcs(a, b) as f32),
+            Dist::All => Err(String::from("all is not a single metric")),
+        }
+    }
+
+    /// Render a distance of this metric, integers without decimals
+    /// and never in scientific notation
+    fn format(self, value: f32, precision: Option<usize>) -> String {
+        // counting metrics are whole numbers, unless --softmask downweight
+        // made a difference count in part
+        let whole = value.fract() == 0.0;
+        match (self, precision) {
+            (Dist::Hamming | Dist::Levenshtein | Dist::Damerau | Dist::Qgram | Dist::Lcs, _) if whole => format!("{}", value as i64),
+            (_, Some(places)) => format!("{:.*}", places, value),
+            (_, None) => value.to_string(),
+        }
+    }
+}
+
+fn parse_threshold(s: &str) -> Result<(Dist, f32), String> {
+    let (metric, value) = s.split_once('=')
+        .ok_or(format!("Expected metric=value, got {}", s))?;
+    let metric = Dist::from_str(metric.trim(), true)?;
+    if metric == Dist::All {
+        return Err(String::from("Threshold needs a single metric"));
+    }
+    let value = value.trim().parse::<f32>().map_err(|e| e.to_string())?;
+    Ok((metric, value))
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum ThresholdMode {
+    Any,
+    All,
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum Prefilter {
+    Composition,
+    Length,
+    None,
+    Auto,
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum SortBy {
+    Distance,
+    Composition,
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum OnEmpty {
+    Skip,
+    Warn,
+    Fail,
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum LibraryOrientation {
+    Antisense,
+    Sense,
+    Mixed,
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum Orientation {
+    Forward,
+    Both,
+}
+
+/// The commandline tool: parse the arguments of the process, run the
+/// check and exit with its code
+pub fn run() {
+    env_logger::init(); // Start logging based on the RUST_LOG parameter
+    debug!("Parsing commandline arguments");
+    let cli = cli_from_matches(&Cli::command().get_matches()).unwrap_or_else(|e| e.exit());
+    match &cli.command {
+        Some(Command::Calibrate { file, profile, header }) => {
+            calibrate::calibrate(file, *header, profile)
+                .unwrap_or_else(|e| error::exit_with("Unable to calibrate", &*e));
+            return;
+        }
+        Some(Command::CompareRuns { old, new }) => {
+            compare::compare_runs(old, new)
+                .unwrap_or_else(|e| error::exit_with("Unable to compare result files", &*e));
+            return;
+        }
+        Some(Command::ExportApproved { results, output, accept, max_distance, scale, chemistry }) => {
+            let options = approved::ApprovalOptions { accept, max_distance: *max_distance, scale, chemistry };
+            approved::export_approved(results, output.as_deref(), &options)
+                .unwrap_or_else(|e| error::exit_with("Unable to export approved candidates", &*e));
+            return;
+        }
+        Some(Command::Batch { job_file, parallel }) => {
+            let exit_code = batch::run_jobs(job_file, *parallel)
+                .unwrap_or_else(|e| error::exit_with("Unable to run batch jobs", &*e));
+            std::process::exit(exit_code);
+        }
+        Some(Command::Revcomp { file, header, alphabet, seq_column, csv_locale, dialect }) => {
+            let options = revcomp::RevcompOptions { header: *header, alphabet, seq_column: seq_column.as_ref(),
+                                                    csv_locale: *csv_locale, dialect };
+            revcomp::revcomp_file(file, &options)
+                .unwrap_or_else(|e| error::exit_with("Unable to reverse complement file", &*e));
+            return;
+        }
+        Some(Command::Selftest) => {
+            let exit_code = selftest::selftest()
+                .unwrap_or_else(|e| error::exit_with("Unable to write self-test report", &e));
+            std::process::exit(exit_code);
+        }
+        Some(Command::Validate { file, header, alphabet, ambiguity, expect_columns, columns, csv_locale, dialect }) => {
+            let options = lint::LintOptions { alphabet, ambiguity: *ambiguity, expect_columns: *expect_columns,
+                                              columns, csv_locale: *csv_locale, dialect };
+            let exit_code = lint::lint_file(file, *header, &options)
+                .unwrap_or_else(|e| error::exit_with("Unable to validate file", &*e));
+            std::process::exit(exit_code);
+        }
+        None => {}
+    }
+    let input_file = cli.input_aso_file.as_deref().filter(|_| cli.multiple_aso);
+    read_once(cli.library_aso_file.iter().map(PathBuf::as_path)
+        .chain(cli.library_snapshot.as_deref())
+        .chain(cli.scan_file.as_deref())
+        .chain(cli.pairs.as_deref())
+        .chain(input_file))
+        .unwrap_or_else(|e| error::exit_with("Unable to read input files", &e));
+    // with --output the results are collected, then written in one go
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let mut buffer = Vec::new();
+    let out: &mut dyn Write = match cli.output {
+        Some(_) => &mut buffer,
+        None => &mut stdout,
+    };
+    let exit_code = if let Some(scan_file) = &cli.scan_file {
+        scan::scan(&cli, scan_file, out).unwrap_or_else(|e| error::exit_with("Unable to scan sequences", &*e));
+        0
+    } else {
+        // a sqlite:// library is read for the inputs, which are then read first
+        let inputs = sqlite::reads_by_prefilter(&cli)
+            .then(|| ReadInputs::load(&cli))
+            .transpose()
+            .unwrap_or_else(|e| error::exit_with("Unable to compute distances", &*e));
+        let keys = inputs.as_ref().map(|inputs| sqlite::PrefilterKeys::new(&inputs.asos, &cli));
+        let library = load_library(&cli, keys.as_ref())
+            .unwrap_or_else(|e| error::exit_with("Unable to load library", &*e));
+        if let Some(snapshot_path) = &cli.export_library_snapshot {
+            library.write_snapshot(snapshot_path, &cli)
+                .unwrap_or_else(|e| error::exit_with("Unable to write library snapshot", &*e));
+        }
+        if cli.export_library_snapshot.is_some() && cli.aso_seq.is_none() && !cli.multiple_aso && cli.pairs.is_none() {
+            0
+        } else if let Some(focus_name) = &cli.focus {
+            focus::focus(&cli, focus_name, &library, out)
+                .unwrap_or_else(|e| error::exit_with("Unable to rank inputs", &*e));
+            0
+        } else if cli.window.is_some() {
+            windows::scan_windows(&cli, &library, out)
+                .unwrap_or_else(|e| error::exit_with("Unable to screen windows", &*e));
+            0
+        } else if let Some(pairs_file) = &cli.pairs {
+            pairs::check_pairs(&cli, pairs_file, &library, out)
+                .unwrap_or_else(|e| error::exit_with("Unable to check pairs", &*e));
+            0
+        } else {
+            check(&cli, &library, inputs, out)
+                .unwrap_or_else(|e| error::exit_with("Unable to compute distances", &*e))
+                .exit_code()
+        }
+    };
+    if let Some(output_path) = &cli.output {
+        output::write_output(output_path, &buffer, cli.append)
+            .unwrap_or_else(|e| error::exit_with("Unable to write output file", &e));
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+}
+
+impl Cli {
+    /// Settings from commandline-style options, the first being the
+    /// program name, e.g. ["aso_scramble_check", "-l", "lib.csv", "-a", "ATGC"].
+    /// Presets are applied as on the commandline.
+    /// The library file is optional here, for Library::from_sequences.
+    pub fn from_args<I, T>(args: I) -> Result<Cli, clap::Error>
+    where I: IntoIterator<Item = T>, T: Into<std::ffi::OsString> + Clone {
+        let command = Cli::command().mut_arg("libfile", |arg| arg.required_unless_present(Resettable::Reset));
+        cli_from_matches(&command.try_get_matches_from(args)?)
+    }
+}
+
+/// Build the settings from parsed arguments, applying any preset
+fn cli_from_matches(matches: &ArgMatches) -> Result<Cli, clap::Error> {
+    let mut cli = Cli::from_arg_matches(matches)?;
+    if let Some(preset) = cli.preset {
+        info!("Applying screening preset {:?}", preset);
+        preset.apply(&mut cli, matches)
+            .map_err(|e| clap::Error::raw(clap::error::ErrorKind::ValueValidation, format!("{}\n", e)))?;
+    }
+    Ok(cli)
+}
+
+/// Path that reads standard input, for the input and library files
+const STDIN_PATH: &str = "-";
+
+/// Named pipes, other non-regular files and stdin can be read only once
+fn is_stream(path: &Path) -> bool {
+    path == Path::new(STDIN_PATH) || fs::metadata(path).is_ok_and(|meta| !meta.is_file())
+}
+
+/// Open an input or library file, stdin for -
+fn open_file(path: &Path) -> io::Result<Box<dyn Read>> {
+    match path == Path::new(STDIN_PATH) {
+        true => Ok(Box::new(io::stdin().lock())),
+        false => Ok(Box::new(File::open(path)?)),
+    }
+}
+
+/// Fail early, instead of hanging, when a pipe would be read twice
+fn read_once<'a>(paths: impl Iterator<Item = &'a Path>) -> Result<(), CheckError> {
+    let mut streams: Vec<&Path> = Vec::new();
+    for path in paths.filter(|path| is_stream(path)) {
+        if streams.contains(&path) {
+            return Err(CheckError::Usage(format!("{:?} is a pipe or stdin and can only be read once, but is used more than once",
+                                                 path)))
+        }
+        streams.push(path)
+    }
+    Ok(())
+}
+
+/// Library ASOs, parsed once and reusable across checks
+pub struct Library {
+    /// Empty for a library given in memory
+    files: Vec<FileRecord>,
+    /// Label of each library file, see AsoProfile::source
+    sources: Vec<String>,
+    asos: Vec<Arc<AsoProfile>>,
+    warnings: Warnings,
+    /// Names changed by --sanitize-names
+    renamed: Vec<Rename>,
+    load_time: Duration,
+}
+
+impl Library {
+    /// Read and profile the library file of the settings
+    /// (--library-aso-file and the options of how to read it)
+    pub fn load(cli: &Cli) -> Result<Library, Box<dyn Error>> {
+        load_library(cli, None)
+    }
+
+    /// Write the library as parsed to a snapshot file, which Library::load
+    /// reads back with --library-snapshot
+    pub fn write_snapshot(&self, path: &Path, cli: &Cli) -> Result<(), Box<dyn Error>> {
+        snapshot::write(self, path, cli)
+    }
+
+    /// Library of (name, sequence) pairs already in memory, read like the
+    /// rows of a library file with the options of the settings
+    pub fn from_sequences(sequences: &[(&str, &str)], cli: &Cli) -> Result<Library, Box<dyn Error>> {
+        let load_start = Instant::now();
+        let rows = sequences_csv(sequences)?;
+        let mut reader = ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(rows.as_slice());
+        let headers = reader.headers()?.clone();
+        let (asos, warnings, renamed) = read_library(reader.records(), Some(&headers), RecordColumns::default(),
+                                                     MEMORY_LABEL, 0, cli)?;
+        let mut library = Library {
+            files: vec![],
+            sources: vec![String::from(MEMORY_LABEL)],
+            asos,
+            warnings,
+            renamed,
+            load_time: Duration::ZERO,
+        };
+        conflicts::resolve(&mut library, cli.on_name_conflict)?;
+        library.load_time = load_start.elapsed();
+        Ok(library)
+    }
+
+    /// Library read from the contents of a library file (csv, FASTA or
+    /// FASTQ, possibly compressed), with the options of the settings;
+    /// `label` names it in messages. Malformed contents are an error,
+    /// never a panic
+    pub fn from_reader<R: io::Read>(reader: R, label: &str, cli: &Cli) -> Result<Library, Box<dyn Error>> {
+        let load_start = Instant::now();
+        let ((asos, warnings, renamed), _) = read_library_text(reader, label, 0, cli)?;
+        let mut library = Library {
+            files: vec![],
+            sources: vec![label.to_string()],
+            asos,
+            warnings,
+            renamed,
+            load_time: Duration::ZERO,
+        };
+        conflicts::resolve(&mut library, cli.on_name_conflict)?;
+        library.load_time = load_start.elapsed();
+        Ok(library)
+    }
+
+    /// The library ASOs, in file order
+    pub fn entries(&self) -> impl Iterator<Item = &AsoProfile> {
+        self.asos.iter().map(|aso| aso.as_ref())
+    }
+}
+
+/// Input ASOs to check against a library
+///
+/// ```no_run
+/// use aso_scramble_check::{Cli, Library, Query};
+///
+/// let cli = Cli::from_args(["aso_scramble_check", "-l", "lib.csv", "-a", "ATGCATGCAT"])?;
+/// let library = Library::load(&cli)?;
+/// for input in Query::load(&cli)?.compare(&library, &cli) {
+///     for hit in input.matches() {
+///         println!("{} {} {}", input.name(), hit.library_aso().name(), hit.distance());
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Query {
+    inputs: Vec<AsoProfile>,
+}
+
+impl Query {
+    /// Read the inputs of the settings (--aso-seq or --input-aso-file)
+    pub fn load(cli: &Cli) -> Result<Query, Box<dyn Error>> {
+        let (inputs, _) = load_inputs(cli, &mut RunStats::default())?;
+        Ok(Query { inputs })
+    }
+
+    /// Inputs from (name, sequence) pairs already in memory, read like the
+    /// rows of an input file with the options of the settings
+    ///
+    /// ```
+    /// use aso_scramble_check::{Cli, Library, Query};
+    ///
+    /// let cli = Cli::from_args(["aso_scramble_check", "--list-by", "hamming"])?;
+    /// let library = Library::from_sequences(&[("L1", "ATGCATGCAT")], &cli)?;
+    /// let inputs = Query::from_sequences(&[("I1", "ATGCATGCTA")], &cli)?.compare(&library, &cli);
+    /// assert_eq!(inputs[0].matches()[0].distance(), 2.0);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_sequences(sequences: &[(&str, &str)], cli: &Cli) -> Result<Query, Box<dyn Error>> {
+        let rows = sequences_csv(sequences)?;
+        let mut reader = ReaderBuilder::new()
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(rows.as_slice());
+        let inputs = read_inputs(reader.records(), &OverrideColumns::default(), RecordColumns::default(), false, cli,
+                                 MEMORY_LABEL,
+                                 &mut RunStats::default())?;
+        Ok(Query { inputs })
+    }
+
+    /// Inputs read from the contents of an input file (csv, FASTA or
+    /// FASTQ, possibly compressed), with the options of the settings;
+    /// `label` names it in messages. Malformed contents are an error,
+    /// never a panic
+    pub fn from_reader<R: io::Read>(reader: R, label: &str, cli: &Cli) -> Result<Query, Box<dyn Error>> {
+        let (inputs, _) = read_input_text(reader, label, cli, &mut RunStats::default())?;
+        Ok(Query { inputs })
+    }
+
+    /// Compare every input against the library, with the matching options
+    /// of the settings. Returns the inputs, each with its matches sorted.
+    pub fn compare(mut self, library: &Library, cli: &Cli) -> Vec<AsoProfile> {
+        compute_distance(library, &mut self.inputs, cli, None, &mut RunStats::default());
+        self.inputs
+    }
+}
+
+/// Label of sequences given in memory, in messages
+const MEMORY_LABEL: &str = "(in memory)";
+
+/// (name, sequence) pairs as csv with a header, to be read like a file
+fn sequences_csv(sequences: &[(&str, &str)]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["name", "seq"])?;
+    for (name, seq) in sequences {
+        writer.write_record([name, seq])?;
+    }
+    Ok(writer.into_inner().map_err(|e| e.to_string())?)
+}
+
+/// Read the library; sqlite:// libraries only the rows that can pass the
+/// prefilter when `keys` are given, see the sqlite module
+fn load_library(cli: &Cli, keys: Option<&sqlite::PrefilterKeys>) -> Result<Library, Box<dyn Error>> {
+    if let Some(snapshot_path) = &cli.library_snapshot {
+        return snapshot::read(snapshot_path, cli)
+    }
+    if cli.library_aso_file.is_empty() {
+        return Err(CheckError::Usage(String::from("Provide a library of ASOs")).into())
+    }
+    info!("Initialising library of ASOs");
+    let load_start = Instant::now();
+    let mut library = Library {
+        files: vec![],
+        sources: vec![],
+        asos: vec![],
+        warnings: Warnings::default(),
+        renamed: vec![],
+        load_time: Duration::ZERO,
+    };
+    for (source, library_file_path) in cli.library_aso_file.iter().enumerate() {
+        let library_label = library_file_path.display().to_string();
+        let ((asos, warnings, renamed), library_hash) = match sqlite::Location::parse(library_file_path)? {
+            Some(location) => {
+                let (headers, records, library_hash) = sqlite::read_records(&location, &library_label, keys, cli)?;
+                (read_library(records.into_iter().map(Ok), Some(&headers), RecordColumns::default(), &library_label,
+                              source, cli)?, library_hash)
+            }
+            None => read_library_file(library_file_path, &library_label, source, cli)?,
+        };
+        library.files.push(FileRecord::new(library_file_path.clone(), library_hash));
+        library.sources.push(library_label);
+        library.asos.extend(asos);
+        library.warnings.merge(warnings);
+        library.renamed.extend(renamed);
+    }
+    conflicts::resolve(&mut library, cli.on_name_conflict)?;
+    library.load_time = load_start.elapsed();
+    Ok(library)
+}
+
+/// Profile the rows of a library file, with the SHA-256 of the file
+fn read_library_file(path: &Path, label: &str, source: usize, cli: &Cli) -> Result<(LibraryRows, String), Box<dyn Error>> {
+    let library_file = open_file(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open library file {:?}: {}", path, e)))?;
+    let workbook = match cli.library_format {
+        FileFormat::Auto => xlsx::has_workbook_extension(path),
+        format => format == FileFormat::Xlsx,
+    };
+    if !workbook {
+        return read_library_text(library_file, label, source, cli)
+    }
+    info!("Reading library file {:?} as an Excel workbook", path);
+    let mut library_reader = HashingReader::new(library_file);
+    let mut bytes = Vec::new();
+    library_reader.read_to_end(&mut bytes)?;
+    let (headers, records) = xlsx::read_records(bytes, cli.sheet.as_deref(), cli.library_header_status, label)?;
+    let columns = RecordColumns::resolve(&cli.columns, headers.as_ref(), label)?;
+    let rows = read_library(records.into_iter().map(Ok), headers.as_ref(), columns, label, source, cli)?;
+    Ok((rows, library_reader.hex_digest()))
+}
+
+/// Profile the rows of a text library file (csv, FASTA or FASTQ), with
+/// the SHA-256 of the file
+fn read_library_text<R: io::Read>(file: R, label: &str, source: usize, cli: &Cli)
+    -> Result<(LibraryRows, String), Box<dyn Error>> {
+    let mut decoder = DecodingReader::new(HashingReader::new(file), label)?;
+    let format = cli.library_format.resolve(decoder.sample()?);
+    if format != FileFormat::Csv {
+        info!("Reading library file {} as {:?}", label, format);
+        let mut fasta_reader = BufReader::new(decoder);
+        let records = fasta::read_records(&mut fasta_reader, format, label)?;
+        let rows = read_library(records.into_iter().map(Ok), None, RecordColumns::default(), label, source, cli)?;
+        return Ok((rows, fasta_reader.into_inner().into_inner().hex_digest()))
+    }
+    if !cli.library_header_status {
+        warn!("Note: Library file has no header. First entry will be processed")
+    } else {
+        warn!("Note: Library has header, first entry will not be processed.")
+    }
+    let mut reader = dialect::reader_builder(cli, decoder.sample()?)
+        .has_headers(cli.library_header_status)
+        .from_reader(decoder);
+    let headers = match cli.library_header_status {
+        true => Some(reader.headers()?.clone()),
+        false => None,
+    };
+    let columns = RecordColumns::resolve(&cli.columns, headers.as_ref(), label)?;
+    let rows = read_library(reader.records(), headers.as_ref(), columns, label, source, cli)?;
+    Ok((rows, reader.into_inner().into_inner().hex_digest()))
+}
+
+/// Profiles of the library rows, warnings about them and renamed rows
+type LibraryRows = (Vec<Arc<AsoProfile>>, Warnings, Vec<Rename>);
+
+/// Profile the rows of a library, the `source`-th file of it; `headers`
+/// is None for a csv file without header row and for FASTA
+fn read_library(records: impl Iterator<Item = csv::Result<StringRecord>>, headers: Option<&StringRecord>,
+                columns: RecordColumns, label: &str, source: usize, cli: &Cli) -> Result<LibraryRows, Box<dyn Error>> {
+    let group_column = match &cli.group_by {
+        Some(column) => Some(headers.and_then(|headers| headers.iter().position(|header| header.trim() == column))
+            .ok_or(CheckError::Data(format!("{}: no column named {:?} for --group-by", label, column)))?),
+        None => None,
+    };
+    let orientation_column = match (cli.library_orientation, headers) {
+        (LibraryOrientation::Mixed, None) =>
+            return Err(CheckError::Usage(String::from("--library-orientation mixed needs a library header")).into()),
+        (LibraryOrientation::Mixed, Some(headers)) => Some(headers.iter()
+            .position(|header| header.trim().eq_ignore_ascii_case("orientation"))
+            .ok_or(CheckError::Data(format!("{}: no orientation column for --library-orientation mixed", label)))?),
+        _ => None,
+    };
+    let mut warnings = Warnings::default();
+    let mut asos = Vec::new();
+    let mut reversed = 0;
+    let mut sanitizer = cli.sanitize_names.then(|| NameSanitizer::new(cli.max_name_length));
+    for library_result in records {
+        let record = library_result?;
+        check_columns(&record, cli.library_expect_columns, label)?;
+        let (name, seq) = cleaned_fields(&record, columns, label, &mut warnings);
+        let line = record.position().map_or(0, |pos| pos.line());
+        let seq = validate::sequence(&seq, &name, label, line, cli, &mut warnings);
+        if cli.strict {
+            check_strict(&seq, &name, label, &record, &cli.alphabet, cli.softmask,
+                         cli.metric_options.ambiguity)?;
+        }
+        if skip_empty(&seq, &name, label, &record, cli.on_empty, &mut warnings)? {
+            continue
+        }
+        let name = match &mut sanitizer {
+            Some(sanitizer) => sanitizer.sanitize(&name, label, line),
+            None => name,
+        };
+        let sense = match orientation_column {
+            Some(column) => match record.get(column).unwrap_or("").trim().to_ascii_lowercase().as_str() {
+                "" | "antisense" => false,
+                "sense" => true,
+                other => return Err(CheckError::Data(format!("{}:{}: invalid orientation {:?} for {}, expected sense or antisense",
+                                                             label, line, other, name)).into()),
+            },
+            None => cli.library_orientation == LibraryOrientation::Sense,
+        };
+        // sense entries are target sites, compared as the ASO that binds them
+        let seq = match sense {
+            true => {
+                reversed += 1;
+                softmask::reverse_complement(&seq, cli)
+            }
+            false => seq,
+        };
+        let mut profile = softmask::profile(name, seq, cli);
+        profile.group = group_column
+            .and_then(|i| record.get(i))
+            .map(|group| group.trim().to_string())
+            .filter(|group| !group.is_empty());
+        profile.source = source;
+        profile.line = line;
+        asos.push(Arc::new(profile))
+    }
+    if reversed > 0 {
+        info!("Reverse complemented {} sense library entries", reversed);
+    }
+    Ok((asos, warnings, sanitizer.map(|sanitizer| sanitizer.renamed).unwrap_or_default()))
+}
+
+/// Profile the rows of a text input file (csv, FASTA or FASTQ), with the
+/// SHA-256 of the file
+fn read_input_text<R: io::Read>(file: R, label: &str, cli: &Cli, stats: &mut RunStats)
+    -> Result<(Vec<AsoProfile>, String), Box<dyn Error>> {
+    let mut decoder = DecodingReader::new(HashingReader::new(file), label)?;
+    let format = cli.input_format.resolve(decoder.sample()?);
+    if format == FileFormat::Xlsx {
+        return Err(CheckError::Usage(String::from("--input-format xlsx is not supported, export the sheet as csv")).into())
+    }
+    if format != FileFormat::Csv {
+        info!("Reading input ASO file {} as {:?}", label, format);
+        let mut fasta_reader = BufReader::new(decoder);
+        let records = fasta::read_records(&mut fasta_reader, format, label)?;
+        let props = read_inputs(records.into_iter().map(Ok), &OverrideColumns::default(), RecordColumns::default(),
+                                false, cli, label,
+                                stats)?;
+        return Ok((props, fasta_reader.into_inner().into_inner().hex_digest()))
+    }
+    let input_file_header = cli.input_header_status;
+    if !input_file_header {
+        warn!("Note: Input ASO file {} has no header. First entry will be processed", label)
+    } else {
+        warn!("Note: Input ASO file {} has header, first entry will not be processed.", label)
+    }
+    // strict mode must see the whitespace to report it
+    let input_trim = if cli.strict { Trim::None } else { Trim::All };
+    let mut input_aso_reader = dialect::reader_builder(cli, decoder.sample()?)
+        .has_headers(input_file_header)
+        .trim(input_trim)
+        .from_reader(decoder);
+    let override_columns = match input_file_header {
+        true => OverrideColumns::from_headers(input_aso_reader.headers()?, cli.csv_locale),
+        false => OverrideColumns::default(),
+    };
+    let input_headers = input_file_header.then_some(input_aso_reader.headers()?);
+    let columns = RecordColumns::resolve(&cli.columns, input_headers, label)?
+        .with_passenger(cli.passenger_column.as_ref(), input_headers, label)?;
+    let props = read_inputs(input_aso_reader.records(), &override_columns, columns, !input_file_header, cli, label,
+                            stats)?;
+    Ok((props, input_aso_reader.into_inner().into_inner().hex_digest()))
+}
+
+/// Read the input ASOs, from the input file or the single commandline
+/// sequence, masked by --mask-file, with the records of the files read
+fn load_inputs(cli: &Cli, stats: &mut RunStats)
+    -> Result<(Vec<AsoProfile>, Vec<FileRecord>), Box<dyn Error>> {
+    let (mut props, mut files) = match cli.multiple_aso {
+        true => {
+            debug!("Processing multiple ASO sequences");
+            let aso_input_file_path = cli.input_aso_file.clone()
+                .ok_or(CheckError::Usage(String::from("--multiple-aso-seq needs --input-aso-file")))?;
+            info!("Processing input ASO file {:?}", aso_input_file_path.as_path());
+            let input_file = open_file(&aso_input_file_path)
+                .map_err(|e| CheckError::Io(format!("Unable to open input ASO file {:?}: {}", aso_input_file_path, e)))?;
+            let input_label = aso_input_file_path.display().to_string();
+            let (props, input_hash) = read_input_text(input_file, &input_label, cli, stats)?;
+            (props, vec![FileRecord::new(aso_input_file_path, input_hash)])
+        }
+        false => {
+            let aso_seq = cli.aso_seq.clone()
+                .ok_or(CheckError::Usage(String::from("Enter an ASO sequence (--aso-seq) or a file of ASOs (--multiple-aso-seq --input-aso-file)")))?;
+            debug!("Processing the given input ASO sequence: {}", aso_seq);
+            info!("Naming the input ASO {} as testASO_001", aso_seq);
+            let aso_input = format!("testASO_001, {}", aso_seq);
+            let mut input_aso_reader = ReaderBuilder::new()
+                .has_headers(false)
+                .trim(Trim::All)
+                .from_reader(aso_input.as_bytes());
+            let props = read_inputs(input_aso_reader.records(), &OverrideColumns::default(), RecordColumns::default(),
+                                    false, cli,
+                                    "--aso-seq", stats)?;
+            (props, vec![])
+        }
+    };
+    if let Some(mask_path) = &cli.mask_file {
+        let mask_hash = mask::apply_masks(mask_path, &mut props, cli)?;
+        files.push(FileRecord::new(mask_path.clone(), mask_hash));
+    }
+    Ok((props, files))
+}
+
+/// Input ASOs read before the library, with the records of their files
+/// and what reading them counted
+struct ReadInputs {
+    asos: Vec<AsoProfile>,
+    files: Vec<FileRecord>,
+    stats: RunStats,
+}
+
+impl ReadInputs {
+    fn load(cli: &Cli) -> Result<ReadInputs, Box<dyn Error>> {
+        let load_start = Instant::now();
+        let mut stats = RunStats::default();
+        let (asos, files) = load_inputs(cli, &mut stats)?;
+        stats.load_time = load_start.elapsed();
+        Ok(ReadInputs { asos, files, stats })
+    }
+}
+
+/// Check the inputs, read here unless already `inputs`, against a loaded
+/// library and write the results to `out`.
+/// Returns whether the close-match gate was tripped.
+fn check(cli: &Cli, library: &Library, inputs: Option<ReadInputs>, out: &mut dyn Write)
+    -> Result<CheckOutcome, Box<dyn Error>> {
+    let run_start = Instant::now();
+    let mut stats = RunStats {
+        library_records: library.asos.len(),
+        warnings: library.warnings.clone(),
+        ..RunStats::default()
+    };
+    let load_start = Instant::now();
+    let (mut input_seq_props, input_files, inputs_load_time) = match inputs {
+        Some(inputs) => {
+            stats.input_records = inputs.stats.input_records;
+            stats.renamed = inputs.stats.renamed;
+            stats.warnings.merge(inputs.stats.warnings);
+            (inputs.asos, inputs.files, inputs.stats.load_time)
+        }
+        None => {
+            let (asos, files) = load_inputs(cli, &mut stats)?;
+            (asos, files, Duration::ZERO)
+        }
+    };
+    if !cli.no_group_identical {
+        dedup::group_identical(&mut input_seq_props);
+    }
+    stats.load_time = library.load_time + inputs_load_time + load_start.elapsed();
+    // results are still written, with zero matches, for pipelines reading them
+    let nothing_checked = match (library.asos.is_empty(), input_seq_props.is_empty()) {
+        (true, _) => Some(format!("the library ({}) has no usable records", library.sources.join(", "))),
+        (false, true) => Some(String::from("the input has no usable records")),
+        (false, false) => None,
+    };
+    let previous = cli.previous_manifest.as_deref()
+        .map(|path| PreviousRun::load(path, cli))
+        .transpose()?;
+    compute_distance(library, &mut input_seq_props, cli, previous.as_ref(), &mut stats);
+    if cli.tui {
+        tui::browse(&input_seq_props, cli.list_by, cli.precision, cli.tui_export.as_deref())?;
+    } else {
+        let written = match (cli.raw, cli.output_format) {
+            (true, _) => print_raw(out, &input_seq_props, library, cli),
+            (false, OutputFormat::Json) => print_json(out, &input_seq_props, cli),
+            (false, OutputFormat::Table) => print_results(out, &input_seq_props, library, cli)
+                .and_then(|_| gate::print_tiers(out, &input_seq_props, cli)),
+        };
+        // a closed pipe (e.g. `| head`) is not an error
+        if let Err(e) = written.and_then(|_| out.flush()) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                return Err(e.into())
+            }
+        }
+    }
+    stats.sources = sources::source_stats(library, &input_seq_props);
+    stats.warnings.print_summary();
+    sources::print_summary(&stats.sources);
+    if let Some(logo_dir) = &cli.logo_dir {
+        logo::write_logos(logo_dir, &input_seq_props, cli.logo_min_matches, &cli.alphabet)?;
+    }
+    if let Some(name_map_path) = &cli.name_map {
+        let renamed: Vec<Rename> = library.renamed.iter().chain(&stats.renamed).cloned().collect();
+        names::write_name_map(name_map_path, &renamed)?;
+    }
+    if let Some(graph_path) = &cli.graph_file {
+        graph::write_graph(graph_path, &input_seq_props, cli.redact_sequences)?;
+    }
+    let run_id = cli.run_id.clone().unwrap_or_else(export::generate_run_id);
+    if let Some(results_path) = &cli.results_file {
+        info!("Writing results to {:?}", results_path.as_path());
+        export::write_results(results_path, cli.append, &run_id, &input_seq_props,
+                              cli.precision, cli.redact_sequences)?;
+    }
+    if let Some(manifest_path) = &cli.manifest {
+        let mut files: Vec<FileRecord> = library.files.clone();
+        files.extend(input_files);
+        let timings = Timings {
+            load_ms: stats.load_time.as_millis(),
+            compare_ms: stats.compare_time.as_millis(),
+            total_ms: (library.load_time + run_start.elapsed()).as_millis(),
+        };
+        let manifest = Manifest::new(cli, &run_id, files, timings, &stats,
+                                     &library.asos, &input_seq_props);
+        info!("Writing run manifest to {:?}", manifest_path.as_path());
+        manifest.write(manifest_path)?;
+    }
+    let violations = gate::close_matches(&input_seq_props, &gate::gates(cli));
+    for violation in &violations {
+        eprintln!("{}", serde_json::to_string(violation)?);
+    }
+    if let Some(reason) = &stats.stopped_early {
+        eprintln!("Stopped early, results are partial: {}", reason);
+    }
+    if let Some(reason) = &nothing_checked {
+        eprintln!("Nothing was checked, {}", reason);
+    }
+    Ok(CheckOutcome {
+        gate_tripped: !violations.is_empty(),
+        stopped_early: stats.stopped_early.is_some(),
+        nothing_checked: nothing_checked.is_some(),
+    })
+}
+
+/// An input or library ASO, profiled for comparison
+#[derive(Clone)]
+pub struct AsoProfile {
+    name: String,
+    seq: String,
+    aso_len: usize,
+    /// Count of each symbol of the alphabet
+    composition: Vec<usize>,
+    /// Per-input --max-distance from the input file, see the overrides module
+    max_distance: Option<f32>,
+    /// Target/project of a library ASO, see --group-by
+    group: Option<String>,
+    /// Index of the library file of a library ASO, see the sources module
+    source: usize,
+    /// Line of a library ASO in its file, for messages
+    line: u64,
+    aso_names: Vec<AsoMatch>,
+    /// Extra named values of an input, see the annotate module
+    annotations: Vec<(&'static str, String)>,
+    /// Name of the duplex and sequence of the other strand, see --duplex
+    duplex: Option<(String, String)>,
+    /// 0-based positions excluded from comparison, see the mask module
+    mask: BTreeSet<usize>,
+    /// 0-based lowercase positions, see the softmask module
+    softmasked: BTreeSet<usize>,
+    /// Sequence of the parent ASO of an input, see --parent
+    parent: Option<String>,
+    /// Names of the inputs of a group of identical ones, see the dedup module
+    members: Vec<String>,
+    /// Input a derived strand (<name>_rc, <name>_passenger) was made from
+    strand_of: Option<String>,
+}
+
+/// A library ASO that passed the prefilter and thresholds for an input
+#[derive(Clone)]
+pub struct AsoMatch {
+    aso: Arc<AsoProfile>,
+    /// Distance by the primary metric, used for sorting
+    distance: f32,
+    /// Every computed metric
+    scores: Vec<(Dist, f32)>,
+    /// Extra named columns, see the annotate module
+    annotations: Vec<(&'static str, String)>,
+}
+
+impl AsoProfile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sequence as compared, 5' -> 3'
+    pub fn seq(&self) -> &str {
+        &self.seq
+    }
+
+    /// Matches of an input, in report order; empty for library ASOs
+    pub fn matches(&self) -> &[AsoMatch] {
+        &self.aso_names
+    }
+
+    fn new(name: String, seq: String, alphabet: &Alphabet) -> Self {
+        let aso_len = seq.len();
+        let composition = alphabet.composition(&seq);
+        AsoProfile {
+            name,
+            seq,
+            aso_len,
+            composition,
+            max_distance: None,
+            group: None,
+            source: 0,
+            line: 0,
+            aso_names: vec![],
+            annotations: vec![],
+            duplex: None,
+            mask: BTreeSet::new(),
+            softmasked: BTreeSet::new(),
+            parent: None,
+            members: vec![],
+            strand_of: None,
+        }
+    }
+}
+
+impl AsoMatch {
+    pub fn library_aso(&self) -> &AsoProfile {
+        &self.aso
+    }
+
+    /// Distance by the primary metric of --list-by
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Every computed metric, some may be missing when they don't apply
+    pub fn scores(&self) -> &[(Dist, f32)] {
+        &self.scores
+    }
+}
+
+/// Counters and timings gathered while computing distances
+#[derive(Debug, Default, Serialize)]
+pub struct RunStats {
+    input_records: usize,
+    library_records: usize,
+    warnings: Warnings,
+    prefilter_passed: usize,
+    hits: usize,
+    /// Matches carried over from --previous-manifest
+    reused_matches: usize,
+    /// Why the comparison stopped before the end of the library, see --max-runtime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_early: Option<String>,
+    /// Matches and duplicates per library file
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sources: Vec<SourceStats>,
+    /// Input names changed by --sanitize-names
+    #[serde(skip)]
+    renamed: Vec<Rename>,
+    #[serde(skip)]
+    load_time: Duration,
+    #[serde(skip)]
+    compare_time: Duration,
+}
+
+/// Exit code when the library or the inputs have no usable records, so
+/// an empty result isn't taken for a clean one
+const NOTHING_CHECKED_EXIT_CODE: i32 = 5;
+
+/// How a check ended, beyond its written results
+pub struct CheckOutcome {
+    /// A --fail-on-close-match gate was violated
+    gate_tripped: bool,
+    /// A resource limit cut the comparison short
+    stopped_early: bool,
+    /// The library or the inputs had no usable records
+    nothing_checked: bool,
+}
+
+impl CheckOutcome {
+    fn exit_code(&self) -> i32 {
+        if self.nothing_checked {
+            NOTHING_CHECKED_EXIT_CODE
+        } else if self.stopped_early {
+            limits::LIMIT_EXIT_CODE
+        } else if self.gate_tripped {
+            gate::CLOSE_MATCH_EXIT_CODE
+        } else {
+            0
+        }
+    }
+}
+
+fn read_inputs(input: impl Iterator<Item = csv::Result<StringRecord>>, override_columns: &OverrideColumns,
+               columns: RecordColumns, headerless: bool, cli: &Cli, input_label: &str, stats: &mut RunStats)
+    -> Result<Vec<AsoProfile>, Box<dyn Error>> {
+    let mut input_seq_props: Vec<AsoProfile> = Vec::new();
+    let mut sanitizer = cli.sanitize_names.then(|| NameSanitizer::new(cli.max_name_length));
+    for input_result in input {
+        let record = input_result?;
+        check_columns(&record, cli.input_expect_columns, input_label)?;
+        let (name, seq) = cleaned_fields(&record, columns, input_label, &mut stats.warnings);
+        let name = name.trim().to_string();
+        let line = record.position().map_or(0, |pos| pos.line());
+        let seq = validate::sequence(&seq, &name, input_label, line, cli, &mut stats.warnings);
+        let seq = trim_ends(&seq, cli.trim5, cli.trim3);
+        peptide::check(&name, &seq, input_label, line, headerless, &cli.alphabet, cli.metric_options.ambiguity)?;
+        if cli.strict {
+            check_strict(&seq, &name, input_label, &record, &cli.alphabet, cli.softmask,
+                         cli.metric_options.ambiguity)?;
+        }
+        if skip_empty(&seq, &name, input_label, &record, cli.on_empty, &mut stats.warnings)? {
+            continue
+        }
+        let name = match &mut sanitizer {
+            Some(sanitizer) => sanitizer.sanitize(&name, input_label, line),
+            None => name,
+        };
+        let overrides = override_columns.read(&record, input_label)?;
+        let max_distance = overrides.max_distance.or(cli.max_distance);
+        let orientation = overrides.orientation.unwrap_or(cli.orientation);
+        let parent = overrides.parent.or_else(|| cli.parent.clone())
+            .map(|parent| rna::normalized(&parent, cli).into_owned());
+        let mut strands = vec![(name.clone(), seq)];
+        if cli.duplex {
+            let passenger_name = format!("{}_passenger", name);
+            let partner = passenger_strand(&record, columns.passenger, &passenger_name, cli, input_label, stats)?;
+            strands.extend(partner.map(|partner| (passenger_name, partner)));
+        }
+        let duplex = (strands.len() == 2).then_some(&name);
+        for (index, (strand_name, strand_seq)) in strands.iter().enumerate() {
+            let mut profile = softmask::profile(strand_name.clone(), strand_seq.clone(), cli);
+            profile.max_distance = max_distance;
+            profile.duplex = duplex.map(|duplex| (duplex.clone(), strands[1 - index].1.clone()));
+            // the parent is that of the guide strand
+            profile.parent = parent.clone().filter(|_| index == 0);
+            profile.strand_of = (index > 0).then(|| name.clone());
+            input_seq_props.push(profile);
+            if orientation == Orientation::Both {
+                let mut rc_profile = softmask::profile(format!("{}_rc", strand_name),
+                                                       softmask::reverse_complement(strand_seq, cli), cli);
+                rc_profile.max_distance = max_distance;
+                rc_profile.parent = parent.as_deref()
+                    .filter(|_| index == 0)
+                    .map(|parent| softmask::reverse_complement(parent, cli));
+                rc_profile.strand_of = Some(name.clone());
+                input_seq_props.push(rc_profile)
+            }
+        }
+        stats.input_records += 1;
+    }
+    if let Some(min_balance) = cli.min_balance.filter(|_| cli.reject_unbalanced) {
+        input_seq_props.retain(|aso| {
+            let (score, _) = balance::balance(&aso.seq, cli.balance_window, &cli.alphabet);
+            if score < min_balance {
+                warn!("{}: not screening {}, composition balance {:.2} is below {}", input_label,
+                      aso.name, score, min_balance);
+                stats.warnings.add(WarningKind::Unbalanced, format!("{} {}", input_label, aso.name));
+            }
+            score >= min_balance
+        });
+    }
+    if let Some(sanitizer) = sanitizer {
+        stats.renamed.extend(sanitizer.renamed);
+    }
+    Ok(input_seq_props)
+}
+
+/// Second strand of a --duplex row (in column `passenger`), None when the
+/// cell is empty
+fn passenger_strand(record: &StringRecord, passenger: usize, name: &str, cli: &Cli, file: &str,
+                    stats: &mut RunStats) -> Result<Option<String>, CheckError> {
+    let (seq, changed) = clean::clean_field(record.get(passenger).unwrap_or(""));
+    let line = record.position().map_or(0, |pos| pos.line());
+    if changed {
+        warn!("{}:{}: removed invisible or typographic characters from {}", file, line, name);
+        stats.warnings.add(WarningKind::CleanedCharacters, format!("{}:{} {}", file, line, name));
+    }
+    let seq = validate::sequence(&seq, name, file, line, cli, &mut stats.warnings);
+    let seq = trim_ends(&seq, cli.trim5, cli.trim3);
+    if cli.strict {
+        check_strict(&seq, name, file, record, &cli.alphabet, cli.softmask,
+                     cli.metric_options.ambiguity)?;
+    }
+    match skip_empty(&seq, name, file, record, cli.on_empty, &mut stats.warnings)? {
+        true => Ok(None),
+        false => Ok(Some(seq)),
+    }
+}
+
+/// Matches of a previous run for library entries that haven't changed
+fn reuse_matches(library: &Library, input_seq_props: &mut [AsoProfile], previous: &PreviousRun,
+                 cli: &Cli, stats: &mut RunStats) {
+    let by_name: HashMap<&str, &Arc<AsoProfile>> = library.asos.iter()
+        .filter(|aso| previous.knows(aso))
+        .map(|aso| (aso.name.as_str(), aso))
+        .collect();
+    for in_aso in input_seq_props.iter_mut() {
+        let Some(matches) = previous.matches_of(in_aso) else {
+            continue
+        };
+        for summary in matches {
+            let Some(&library_aso) = by_name.get(summary.name.as_str()) else {
+                continue
+            };
+            let mut scores: Vec<(Dist, f32)> = summary.metrics.iter()
+                .filter_map(|(metric, score)| Dist::from_str(metric, true).ok().map(|m| (m, *score)))
+                .collect();
+            if scores.is_empty() {
+                scores.push((cli.list_by.primary(), summary.distance));
+            }
+            let mut hit = AsoMatch {
+                aso: library_aso.clone(),
+                distance: summary.distance,
+                scores,
+                annotations: vec![],
+            };
+            annotate::annotate(&mut hit, in_aso, cli);
+            in_aso.aso_names.push(hit);
+            stats.reused_matches += 1;
+        }
+    }
+    info!("Reused {} matches of the previous run", stats.reused_matches);
+}
+
+/// An input and library ASO that passed the prefilter
+struct Comparison {
+    /// Index of the input
+    input: usize,
+    /// Metrics that don't apply to the pair, as warning details
+    skipped: Vec<String>,
+    /// The library ASO, when it is within the cutoffs
+    hit: Option<AsoMatch>,
+}
+
+/// Compare one input with one library ASO, None when the prefilter rejects them
+fn compare_pair(input: usize, in_aso: &AsoProfile, aso_profile: &Arc<AsoProfile>, metrics: &[Dist],
+                cli: &Cli) -> Option<Comparison> {
+    let passes_prefilter = match cli.prefilter {
+        Prefilter::Composition => in_aso.aso_len == aso_profile.aso_len
+            && in_aso.composition == aso_profile.composition,
+        Prefilter::Length => in_aso.aso_len == aso_profile.aso_len,
+        Prefilter::None | Prefilter::Auto => true,
+    };
+    if !passes_prefilter || in_aso.seq == aso_profile.seq {
+        return None
+    }
+    let mut scores: Vec<(Dist, f32)> = Vec::new();
+    let mut skipped = Vec::new();
+    let library_seq = mask::masked(in_aso, &aso_profile.seq);
+    let ignoring_softmask = softmask::unmasked(in_aso, aso_profile, &library_seq);
+    // with a cutoff, pairs beyond it by levenshtein are dropped before any
+    // other metric is computed; soft-masked differences only add to it
+    let within = match (cli.list_by.primary(), in_aso.max_distance) {
+        (Dist::Levenshtein, Some(max)) if max < 0.0 => return Some(Comparison { input, skipped, hit: None }),
+        (Dist::Levenshtein, Some(max)) => match banded::levenshtein_within(&in_aso.seq, &ignoring_softmask,
+                                                                           max as usize,
+                                                                           cli.metric_options.ambiguity) {
+            Some(distance) => Some(distance as f32).filter(|_| cli.softmask != Some(Softmask::Downweight)),
+            None => return Some(Comparison { input, skipped, hit: None }),
+        },
+        _ => None,
+    };
+    for &metric in metrics {
+        let distance = match (metric, within) {
+            (Dist::Levenshtein, Some(distance)) => Ok(distance),
+            _ => softmask::distance(metric, &in_aso.seq, &library_seq, &ignoring_softmask,
+                                    &cli.metric_options, cli.softmask),
+        };
+        match distance {
+            Ok(score) => scores.push((metric, score)),
+            Err(reason) => {
+                debug!("Skipping {:?} for {} vs {}: {}", metric, in_aso.name, aso_profile.name, reason);
+                skipped.push(format!("{:?} {} vs {}: {}", metric, in_aso.name, aso_profile.name, reason));
+            }
+        }
+    }
+    // without the primary metric the pair can't be ranked
+    let hit = score_of(&scores, cli.list_by.primary())
+        .filter(|&dist| in_aso.max_distance.is_none_or(|max| dist <= max))
+        .filter(|_| thresholds_violated(&scores, &cli.thresholds, cli.threshold_mode))
+        .map(|dist| {
+            let mut hit = AsoMatch {
+                aso: aso_profile.clone(),
+                distance: dist,
+                scores,
+                annotations: vec![],
+            };
+            annotate::annotate(&mut hit, in_aso, cli);
+            hit
+        });
+    Some(Comparison { input, skipped, hit })
+}
+
+fn compute_distance(library: &Library, input_seq_props: &mut [AsoProfile], cli: &Cli,
+                    previous: Option<&PreviousRun>, stats: &mut RunStats) {
+    // compute the ATGC spread of each input source
+    // compute the ATGC spread of each library source
+    // if ATGC and length match found, calculate all three distances
+    let list_method = cli.list_by;
+    let mut metrics = list_method.metrics();
+    for (metric, _) in &cli.thresholds {
+        if !metrics.contains(metric) {
+            metrics.push(*metric)
+        }
+    }
+    if cli.flag_disagreement.is_some() && metrics.len() < 2 {
+        warn!("--flag-disagreement needs more than one metric, use --list-by all or --threshold");
+    }
+    let limits = Limits::new(cli.max_runtime, cli.max_memory);
+    let compare_start = Instant::now();
+    // pairs already compared in the previous run
+    let known: Vec<bool> = library.asos.iter()
+        .map(|aso| previous.is_some_and(|run| run.knows(aso)))
+        .collect();
+    let screened: Vec<bool> = input_seq_props.iter()
+        .map(|aso| previous.is_some_and(|run| run.matches_of(aso).is_some()))
+        .collect();
+    if let Some(previous) = previous {
+        reuse_matches(library, input_seq_props, previous, cli, stats);
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(cli.threads)
+        .build()
+        .expect("Unable to start comparison threads");
+    let candidates = (cli.prefilter == Prefilter::Auto)
+        .then(|| pool.install(|| Candidates::new(&library.asos, input_seq_props, cli)));
+    let mut batches = limits::Batches::new();
+    let mut index = 0;
+    while index < library.asos.len() {
+        if let Some(reason) = limits.exceeded() {
+            let reason = format!("{} after {} of {} library ASOs", reason, index, library.asos.len());
+            warn!("{}", reason);
+            stats.stopped_early = Some(reason);
+            break
+        }
+        let batch_start = Instant::now();
+        let chunk = &library.asos[index..library.asos.len().min(index + batches.size())];
+        // library ASOs of the chunk in parallel, collected back in library order
+        let inputs: &[AsoProfile] = input_seq_props;
+        let comparisons: Vec<Comparison> = match &candidates {
+            None => pool.install(|| chunk.par_iter().enumerate()
+                .flat_map_iter(|(offset, aso_profile)| {
+                    let known = known[index + offset];
+                    inputs.iter().zip(&screened).enumerate()
+                        .filter(move |(_, (_, &screened))| !(screened && known))
+                        .filter_map(|(input, (in_aso, _))| compare_pair(input, in_aso, aso_profile, &metrics, cli))
+                })
+                .collect()),
+            // per input, its candidates of the chunk in library order
+            Some(candidates) => pool.install(|| {
+                let (known, metrics) = (&known, &metrics);
+                inputs.par_iter().zip(&screened).enumerate()
+                    .flat_map_iter(|(input, (in_aso, &screened))| candidates.within(input, index..index + chunk.len())
+                        .filter(move |&library_index| !(screened && known[library_index]))
+                        .filter_map(move |library_index| compare_pair(input, in_aso, &library.asos[library_index],
+                                                                      metrics, cli)))
+                    .collect()
+            }),
+        };
+        for comparison in comparisons {
+            stats.prefilter_passed += 1;
+            for detail in comparison.skipped {
+                stats.warnings.add(WarningKind::MetricSkipped, detail);
+            }
+            if let Some(hit) = comparison.hit {
+                input_seq_props[comparison.input].aso_names.push(hit)
+            }
+        }
+        batches.timed(batch_start.elapsed());
+        index += chunk.len();
+    }
+    // ties are broken by name, then library order (the sort is stable), so the
+    // order is the same whatever the number of threads
+    for aso in input_seq_props.iter_mut() {
+        match cli.sort_by {
+            SortBy::Distance => aso.aso_names
+                .sort_by(|a, b| a.distance.total_cmp(&b.distance).then_with(|| a.aso.name.cmp(&b.aso.name))),
+            SortBy::Composition => {
+                let composition = aso.composition.clone();
+                aso.aso_names.sort_by(|a, b| {
+                    annotate::composition_difference(&composition, &a.aso.composition)
+                        .cmp(&annotate::composition_difference(&composition, &b.aso.composition))
+                        .then(a.distance.total_cmp(&b.distance))
+                        .then_with(|| a.aso.name.cmp(&b.aso.name))
+                })
+            }
+        }
+        if annotate::several_libraries(library) {
+            for hit in aso.aso_names.iter_mut() {
+                hit.annotations.push(("Library", library.sources[hit.aso.source].clone()));
+            }
+        }
+        annotate::annotate_input(aso, cli);
+        stats.hits += aso.aso_names.len();
+    }
+    if cli.duplex {
+        annotate::annotate_duplexes(input_seq_props, &cli.alphabet);
+    }
+    stats.compare_time = compare_start.elapsed();
+}
+
+/// Name and sequence of a record, with invisible characters stripped
+fn cleaned_fields(record: &StringRecord, columns: RecordColumns, file: &str, warnings: &mut Warnings)
+    -> (String, String) {
+    let (name, name_changed) = clean::clean_field(record.get(columns.name).unwrap_or(""));
+    // a missing sequence cell is treated like an empty one, see --on-empty
+    let (seq, seq_changed) = clean::clean_field(record.get(columns.seq).unwrap_or(""));
+    if name_changed || seq_changed {
+        let line = record.position().map_or(0, |pos| pos.line());
+        warn!("{}:{}: removed invisible or typographic characters from {}", file, line, name);
+        warnings.add(WarningKind::CleanedCharacters, format!("{}:{} {}", file, line, name));
+    }
+    (name, seq)
+}
+
+/// Sequence without --trim5/--trim3 bases at its ends, empty if nothing is left
+fn trim_ends(seq: &str, trim5: usize, trim3: usize) -> String {
+    let length = seq.chars().count();
+    seq.chars().skip(trim5).take(length.saturating_sub(trim5 + trim3)).collect()
+}
+
+/// Structural check of a row against --*-expect-columns
+fn check_columns(record: &StringRecord, expected: Option<usize>, file: &str) -> Result<(), CheckError> {
+    match expected {
+        Some(expected) if record.len() != expected => {
+            let line = record.position().map_or(0, |pos| pos.line());
+            Err(CheckError::Data(format!("{}:{}: expected {} columns, found {}", file, line, expected, record.len())))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether a row with an empty sequence should be skipped, per --on-empty
+fn skip_empty(seq: &str, name: &str, file: &str, record: &StringRecord, on_empty: OnEmpty,
+              warnings: &mut Warnings) -> Result<bool, CheckError> {
+    if !seq.is_empty() {
+        return Ok(false)
+    }
+    let line = record.position().map_or(0, |pos| pos.line());
+    match on_empty {
+        OnEmpty::Fail => return Err(CheckError::Data(format!("{}:{}: empty sequence for {}", file, line, name))),
+        OnEmpty::Warn => warn!("{}:{}: skipping {} with empty sequence", file, line, name),
+        OnEmpty::Skip => debug!("{}:{}: skipping {} with empty sequence", file, line, name),
+    }
+    warnings.add(WarningKind::EmptySequence, format!("{}:{} {}", file, line, name));
+    Ok(true)
+}
+
+/// Reject anything outside the alphabet with file:line:column context
+fn check_strict(seq: &str, name: &str, file: &str, record: &StringRecord,
+                alphabet: &Alphabet, softmask: Option<Softmask>, ambiguity: Ambiguity) -> Result<(), CheckError> {
+    let line = record.position().map_or(0, |pos| pos.line());
+    if seq.is_empty() {
+        return Err(CheckError::Data(format!("{}:{}: empty sequence for {}", file, line, name)))
+    }
+    for (column, c) in seq.chars().enumerate() {
+        let anomaly = match c {
+            c if validate::allowed(c, alphabet, softmask, ambiguity) => continue,
+            c if c.is_whitespace() => "whitespace",
+            c if alphabet.contains(c.to_ascii_uppercase()) => "lowercase base",
+            _ => "foreign character",
+        };
+        return Err(CheckError::Data(format!("{}:{}:{}: {} {:?} in sequence of {} ({} alphabet)", file, line,
+                                            column + 1, anomaly, c, name, alphabet.name())))
+    }
+    Ok(())
+}
+
+/// Score of a metric, None when it was skipped for this pair
+fn score_of(scores: &[(Dist, f32)], metric: Dist) -> Option<f32> {
+    scores.iter()
+        .find(|(m, _)| *m == metric)
+        .map(|(_, score)| *score)
+}
+
+fn thresholds_violated(scores: &[(Dist, f32)], thresholds: &[(Dist, f32)],
+                       mode: ThresholdMode) -> bool {
+    if thresholds.is_empty() {
+        return true
+    }
+    let mut violated = thresholds.iter()
+        .map(|&(metric, limit)| score_of(scores, metric).is_some_and(|score| score <= limit));
+    match mode {
+        ThresholdMode::Any => violated.any(|v| v),
+        ThresholdMode::All => violated.all(|v| v),
+    }
+}
+
+/// Tab-separated distance(s) of a match for the chosen list-by option,
+/// followed by any extra columns
+fn distance_columns(hit: &AsoMatch, list_by: Dist, precision: Option<usize>) -> String {
+    list_by.metrics().iter()
+        .map(|&metric| score_of(&hit.scores, metric)
+            .map_or(String::from("n/a"), |score| metric.format(score, precision)))
+        .chain(hit.annotations.iter().map(|(_, value)| value.clone()))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Matches of an input that are reported, the first --max-hits
+fn reported_hits<'a>(aso: &'a AsoProfile, cli: &Cli) -> &'a [AsoMatch] {
+    &aso.aso_names[..aso.aso_names.len().min(cli.max_hits.unwrap_or(usize::MAX))]
+}
+
+/// 4213 as 4,213
+fn with_thousands(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+fn print_raw(out: &mut dyn Write, input_seq_props: &[AsoProfile], library: &Library, cli: &Cli)
+    -> io::Result<()> {
+    for aso in input_seq_props {
+        let input_values = aso.annotations.iter()
+            .map(|(name, value)| format!("\t{}: {}", name, value))
+            .collect::<String>();
+        let hits = reported_hits(aso, cli);
+        for hit in hits {
+            writeln!(out, "{}\t{}\t{}\t{}\t{}{}", aso.name, aso.seq, hit.aso.name,
+                     redact::shown(&hit.aso.seq, cli.redact_sequences),
+                     distance_columns(hit, cli.list_by, cli.precision), input_values)?
+        }
+        // the values of an input without matches, e.g. its PASS verdict, on
+        // a row of its own with empty match columns
+        if hits.is_empty() && !input_values.is_empty() {
+            let match_columns = 2 + cli.list_by.metrics().len() + annotate::columns(cli, library).len();
+            writeln!(out, "{}\t{}{}{}", aso.name, aso.seq, "\t".repeat(match_columns), input_values)?
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonResults {
+    /// Metric of the distance of every match
+    metric: Dist,
+    results: Vec<manifest::InputSummary>,
+}
+
+fn print_json(out: &mut dyn Write, input_seq_props: &[AsoProfile], cli: &Cli) -> io::Result<()> {
+    let results = JsonResults {
+        metric: cli.list_by.primary(),
+        results: manifest::summarize(input_seq_props, cli.max_hits, cli.redact_sequences),
+    };
+    serde_json::to_writer_pretty(&mut *out, &results)?;
+    writeln!(out)
+}
+
+fn print_results(out: &mut dyn Write, input_seq_props: &[AsoProfile], library: &Library, cli: &Cli)
+    -> io::Result<()> {
+    let mut distance_header: Vec<String> = match cli.list_by {
+        Dist::All => Dist::All.metrics().iter().map(|metric| format!("{:?}", metric)).collect(),
+        _ => vec![String::from("Distance")],
+    };
+    distance_header.extend(annotate::columns(cli, library).into_iter().map(String::from));
+    writeln!(out, "{:<10}\t{:<20}\t{:<10}\t{:<20}\t{}", "Input ASO","Seq", "Matching ASO", "Seq",
+             distance_header.join("\t"))?;
+    if !cli.by_length {
+        return print_inputs(out, input_seq_props.iter(), cli)
+    }
+    let mut classes: BTreeMap<usize, Vec<&AsoProfile>> = BTreeMap::new();
+    for aso in input_seq_props {
+        classes.entry(aso.aso_len).or_default().push(aso);
+    }
+    for (length, inputs) in classes {
+        let matched = inputs.iter().filter(|aso| !aso.aso_names.is_empty()).count();
+        let matches: usize = inputs.iter().map(|aso| aso.aso_names.len()).sum();
+        writeln!(out, "## {}-mers: {} inputs, {} with matches, {} matches", length, inputs.len(),
+                 matched, matches)?;
+        print_inputs(out, inputs.into_iter(), cli)?;
+    }
+    Ok(())
+}
+
+fn print_inputs<'a>(out: &mut dyn Write, inputs: impl Iterator<Item = &'a AsoProfile>,
+                    cli: &Cli) -> io::Result<()> {
+    for aso in inputs {
+        let input_values = aso.annotations.iter()
+            .map(|(name, value)| format!("\t{}: {}", name, value))
+            .collect::<String>();
+        writeln!(out, "{:<10}\t{:<20}{}", aso.name, aso.seq, input_values)?;
+        let hits = reported_hits(aso, cli);
+        for hit in hits {
+            writeln!(out, "{:<10}\t{:<20}\t{:<10}\t{:<20}\t{}", "", "", hit.aso.name,
+                     redact::shown(&hit.aso.seq, cli.redact_sequences),
+                     distance_columns(hit, cli.list_by, cli.precision))?;
+            if cli.explain {
+                for line in explain::explain(aso, hit, cli) {
+                    writeln!(out, "{:<10}\t{:<20}\t  # {}", "", "", line)?;
+                }
+            }
+        }
+        if hits.len() < aso.aso_names.len() {
+            writeln!(out, "{:<10}\t{:<20}\t\u{2026}and {} more within threshold", "", "",
+                     with_thousands(aso.aso_names.len() - hits.len()))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+/*
+    Resource limits for shared servers
+        - --max-runtime: time spent comparing, e.g. 90s, 15m, 2h
+        - --max-memory: resident memory of the process, e.g. 512M, 4G
+          (read from /proc, Linux only)
+    When a limit is reached the comparison stops, the matches found so far
+    are written as usual, and the run exits with LIMIT_EXIT_CODE. Limits
+    are checked between batches of library entries sized to take about
+    CHECK_PERIOD each, however many inputs and whatever the metrics.
+*/
+use std::fs;
+use std::time::{Duration, Instant};
+use log::warn;
+
+/// Exit code when a run stopped early on --max-runtime or --max-memory
+pub const LIMIT_EXIT_CODE: i32 = 4;
+
+/// Time aimed at between two checks of the limits
+const CHECK_PERIOD: Duration = Duration::from_millis(100);
+/// Library entries of the first batch, before any batch was timed
+const FIRST_BATCH: usize = 1;
+/// A batch is at most this many times larger than the one before
+const MAX_GROWTH: usize = 4;
+
+/// Seconds, or a number with an s, m or h suffix
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_lowercase()),
+        _ => (s, 's'),
+    };
+    let value: f64 = number.trim().parse().map_err(|_| format!("Invalid duration {}", s))?;
+    let seconds = match unit {
+        's' => value,
+        'm' => value * 60.0,
+        'h' => value * 3600.0,
+        _ => return Err(format!("Unknown duration unit in {}, expected s, m or h", s)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())
+}
+
+/// Bytes, or a number with a K, M or G suffix (powers of 1024)
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s, 'B'),
+    };
+    let value: f64 = number.trim().parse().map_err(|_| format!("Invalid size {}", s))?;
+    let multiplier: f64 = match unit {
+        'B' => 1.0,
+        'K' => 1024.0,
+        'M' => 1024.0 * 1024.0,
+        'G' => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("Unknown size unit in {}, expected K, M or G", s)),
+    };
+    Ok((value * multiplier) as u64)
+}
+
+/// Resident set size of this process in bytes
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+pub struct Limits {
+    deadline: Option<Instant>,
+    max_memory: Option<u64>,
+}
+
+impl Limits {
+    pub fn new(max_runtime: Option<Duration>, max_memory: Option<u64>) -> Self {
+        if max_memory.is_some() && resident_memory().is_none() {
+            warn!("--max-memory is not supported on this platform and will be ignored");
+        }
+        Limits {
+            deadline: max_runtime.map(|runtime| Instant::now() + runtime),
+            max_memory,
+        }
+    }
+
+    /// Why the run has to stop, if it does
+    pub fn exceeded(&self) -> Option<String> {
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Some(String::from("--max-runtime reached"))
+        }
+        let max_memory = self.max_memory?;
+        let used = resident_memory()?;
+        (used > max_memory).then(|| format!("--max-memory reached ({} MiB in use)", used / (1024 * 1024)))
+    }
+}
+
+/// Size of the batches of library entries compared between two checks of
+/// the limits, each from the time the one before took
+pub struct Batches {
+    size: usize,
+}
+
+impl Batches {
+    pub fn new() -> Self {
+        Batches { size: FIRST_BATCH }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Size the next batch from the time the last one took
+    pub fn timed(&mut self, elapsed: Duration) {
+        let per_entry = elapsed.as_secs_f64() / self.size as f64;
+        let size = match per_entry > 0.0 {
+            true => (CHECK_PERIOD.as_secs_f64() / per_entry) as usize,
+            false => usize::MAX,
+        };
+        self.size = size.clamp(1, self.size.saturating_mul(MAX_GROWTH));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_take_about_the_check_period() {
+        let mut batches = Batches::new();
+        // fast entries grow the batch at most fourfold at a time
+        batches.timed(Duration::ZERO);
+        assert_eq!(batches.size(), 4);
+        batches.timed(Duration::from_micros(1));
+        assert_eq!(batches.size(), 16);
+        // 16 entries in 400 ms, 25 ms each: 4 fit in 100 ms
+        batches.timed(Duration::from_millis(400));
+        assert_eq!(batches.size(), 4);
+        // slow entries still leave one per batch
+        batches.timed(Duration::from_secs(10));
+        assert_eq!(batches.size(), 1);
+    }
+}
@@ -0,0 +1,184 @@
+/*
+    Lint a library or input file (validate subcommand)
+        - reads the file the way a check would, name in column1 and
+          sequence in column2 (or --name-column and --seq-column), in the
+          csv dialect of --delimiter, --quote-char and --comment-prefix, or
+          FASTA and FASTQ records, but compares nothing
+        - errors, rows a check would reject or can't use: missing or
+          unexpected columns, empty names and sequences, duplicate names,
+          characters outside the alphabet
+        - warnings, rows a check fixes as it reads them: whitespace and
+          lowercase in sequences, U/T of the other notation, invisible or
+          typographic characters, a column count unlike the first row's
+        - a line per row and a summary; exits with the invalid data code
+          if any row has an error
+*/
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use csv::StringRecord;
+use log::info;
+use crate::alphabet::Alphabet;
+use crate::clean;
+use crate::columns::{ColumnArgs, RecordColumns};
+use crate::dialect::DialectArgs;
+use crate::encoding::DecodingReader;
+use crate::error::{CheckError, DATA_EXIT_CODE};
+use crate::fasta::{self, FileFormat};
+use crate::iupac::Ambiguity;
+use crate::locale::CsvLocale;
+use crate::validate;
+
+/// Issues of a row, errors first
+#[derive(Default)]
+struct RowReport {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl RowReport {
+    fn status(&self) -> &'static str {
+        match (self.errors.is_empty(), self.warnings.is_empty()) {
+            (false, _) => "error",
+            (true, false) => "warning",
+            (true, true) => "ok",
+        }
+    }
+}
+
+/// What the lint expects of the rows
+pub struct LintOptions<'a> {
+    pub alphabet: &'a Alphabet,
+    pub ambiguity: Ambiguity,
+    pub expect_columns: Option<usize>,
+    pub columns: &'a ColumnArgs,
+    pub csv_locale: CsvLocale,
+    pub dialect: &'a DialectArgs,
+}
+
+fn lint_sequence(seq: &str, options: &LintOptions, report: &mut RowReport) {
+    if seq.trim().is_empty() {
+        report.errors.push(String::from("empty sequence"));
+        return
+    }
+    if seq.chars().any(char::is_whitespace) {
+        report.warnings.push(String::from("whitespace in the sequence, removed when read"));
+    }
+    if seq.chars().any(char::is_lowercase) {
+        report.warnings.push(String::from("lowercase bases, uppercased when read unless --softmask"));
+    }
+    let alphabet = options.alphabet;
+    // the other notation of the alphabet's T or U, normalized when read
+    let notation = match (alphabet.contains('T'), alphabet.contains('U')) {
+        (true, false) => Some(('U', 'T')),
+        (false, true) => Some(('T', 'U')),
+        _ => None,
+    };
+    let mut invalid = BTreeSet::new();
+    let mut renotated = false;
+    for c in seq.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_ascii_uppercase()) {
+        if notation.is_some_and(|(from, _)| c == from) {
+            renotated = true
+        } else if !validate::allowed(c, alphabet, None, options.ambiguity) {
+            invalid.insert(c);
+        }
+    }
+    if let Some((from, to)) = notation.filter(|_| renotated) {
+        report.warnings.push(format!("{} bases, read as {} unless --no-rna-normalization", from, to));
+    }
+    if !invalid.is_empty() {
+        let invalid: String = invalid.into_iter().collect();
+        report.errors.push(format!("characters outside the {} alphabet: {:?}", alphabet.name(), invalid));
+    }
+}
+
+fn lint_row(record: &StringRecord, line: u64, first_columns: usize, columns: RecordColumns, options: &LintOptions,
+            first_lines: &mut HashMap<String, u64>) -> (String, RowReport) {
+    let mut report = RowReport::default();
+    match options.expect_columns {
+        Some(expected) if record.len() != expected =>
+            report.errors.push(format!("expected {} columns, found {}", expected, record.len())),
+        _ if record.len() <= columns.seq =>
+            report.errors.push(format!("no sequence column, found {} columns", record.len())),
+        None if record.len() != first_columns =>
+            report.warnings.push(format!("{} columns, the first row has {}", record.len(), first_columns)),
+        _ => {}
+    }
+    let (name, name_changed) = clean::clean_field(record.get(columns.name).unwrap_or(""));
+    let (seq, seq_changed) = clean::clean_field(record.get(columns.seq).unwrap_or(""));
+    let name = name.trim().to_string();
+    if name_changed || seq_changed {
+        report.warnings.push(String::from("invisible or typographic characters, removed when read"));
+    }
+    if name.is_empty() {
+        report.errors.push(String::from("empty name"));
+    } else if let Some(first) = first_lines.get(&name) {
+        report.errors.push(format!("duplicate name, first on line {}", first));
+    } else {
+        first_lines.insert(name.clone(), line);
+    }
+    if record.len() > columns.seq {
+        lint_sequence(&seq, options, &mut report);
+    }
+    (name, report)
+}
+
+fn print_report(out: &mut dyn Write, path: &Path, header: bool,
+                options: &LintOptions) -> Result<usize, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut decoder = DecodingReader::new(file, &label)?;
+    let format = FileFormat::Auto.resolve(decoder.sample()?);
+    let (records, columns, mut first_columns) = match format {
+        FileFormat::Csv => {
+            let mut reader = options.dialect.reader_builder(options.csv_locale, decoder.sample()?)
+                .has_headers(header)
+                .from_reader(decoder);
+            let headers = match header {
+                true => Some(reader.headers()?.clone()),
+                false => None,
+            };
+            let columns = RecordColumns::resolve(options.columns, headers.as_ref(), &label)?;
+            let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+            (records, columns, headers.as_ref().map(StringRecord::len))
+        }
+        format => {
+            info!("Reading {} as {:?}", label, format);
+            let records = fasta::read_records(BufReader::new(decoder), format, &label)?;
+            (records, RecordColumns::default(), Some(2))
+        }
+    };
+    let mut first_lines = HashMap::new();
+    let (mut rows, mut errors, mut warnings) = (0, 0, 0);
+    writeln!(out, "Line\tName\tStatus\tIssues")?;
+    for record in records {
+        let line = record.position().map_or(0, |pos| pos.line());
+        let first_count = *first_columns.get_or_insert(record.len());
+        let (name, report) = lint_row(&record, line, first_count, columns, options, &mut first_lines);
+        rows += 1;
+        if !report.errors.is_empty() {
+            errors += 1
+        } else if !report.warnings.is_empty() {
+            warnings += 1
+        }
+        let issues: Vec<String> = report.errors.iter().chain(&report.warnings).cloned().collect();
+        writeln!(out, "{}\t{}\t{}\t{}", line, name, report.status(), issues.join("; "))?;
+    }
+    writeln!(out, "{} rows: {} with errors, {} with warnings only", rows, errors, warnings)?;
+    Ok(errors)
+}
+
+/// Lint `path`, print the report and return the exit code
+pub fn lint_file(path: &Path, header: bool, options: &LintOptions) -> Result<i32, Box<dyn Error>> {
+    info!("Validating {:?} against the {} alphabet", path, options.alphabet.name());
+    let mut out = io::stdout().lock();
+    let errors = print_report(&mut out, path, header, options)?;
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+        _ => {}
+    }
+    Ok(if errors == 0 { 0 } else { DATA_EXIT_CODE })
+}
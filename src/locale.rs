@@ -0,0 +1,44 @@
+/*
+    Locale of csv files
+        - standard: comma delimiter, decimal point (1.5)
+        - european: semicolon delimiter, decimal comma (1,5), as written by
+          Excel in most continental European locales
+        - auto: delimiter (comma, semicolon or tab) guessed from the first
+          line, either decimal mark accepted
+*/
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Default, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum CsvLocale {
+    #[default]
+    Auto,
+    Standard,
+    European,
+}
+
+impl CsvLocale {
+    /// Field delimiter, `sample` being the start of the file
+    pub fn delimiter(self, sample: &[u8]) -> u8 {
+        match self {
+            CsvLocale::Standard => b',',
+            CsvLocale::European => b';',
+            CsvLocale::Auto => {
+                let first_line = sample.split(|&b| b == b'\n').next().unwrap_or(&[]);
+                let count = |delimiter: u8| first_line.iter().filter(|&&b| b == delimiter).count();
+                [b',', b';', b'\t'].into_iter().rev().max_by_key(|&delimiter| count(delimiter)).unwrap_or(b',')
+            }
+        }
+    }
+
+    /// Parse a number written with this locale's decimal mark
+    pub fn parse_number(self, text: &str) -> Result<f32, String> {
+        let text = match self {
+            CsvLocale::Standard => text.to_string(),
+            CsvLocale::European => text.replace('.', "").replace(',', "."),
+            CsvLocale::Auto if !text.contains('.') => text.replace(',', "."),
+            CsvLocale::Auto => text.to_string(),
+        };
+        text.parse::<f32>().map_err(|e| e.to_string())
+    }
+}
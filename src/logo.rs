@@ -0,0 +1,107 @@
+/*
+    Sequence logos of match families
+        - one SVG per input with enough same-length matches
+        - letter stacks scaled by information content (bits), so the
+          conserved core of the family stands out at a glance
+*/
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use log::info;
+use crate::alphabet::Alphabet;
+use crate::AsoProfile;
+
+const COLUMN_WIDTH: f32 = 30.0;
+/// Height in pixels of a fully conserved position (2 bits for DNA)
+const BITS_HEIGHT: f32 = 100.0;
+const FONT_SIZE: f32 = 40.0;
+/// Approximate cap height of the font, relative to its size
+const CAP_HEIGHT: f32 = 0.72;
+const MARGIN: f32 = 20.0;
+const OTHER_COLOUR: &str = "#7F7F7F";
+
+fn colour(symbol: char) -> &'static str {
+    match symbol {
+        'A' => "#109648",
+        'C' => "#255C99",
+        'G' => "#F7B32B",
+        'T' | 'U' => "#D62839",
+        _ => OTHER_COLOUR,
+    }
+}
+
+/// SVG logo of equal-length sequences
+fn logo_svg(title: &str, family: &[Vec<char>], alphabet: &Alphabet) -> String {
+    let length = family[0].len();
+    let width = length as f32 * COLUMN_WIDTH + 2.0 * MARGIN;
+    let height = BITS_HEIGHT + 3.0 * MARGIN;
+    let baseline = BITS_HEIGHT + 2.0 * MARGIN;
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+                     width, height);
+    let _ = writeln!(svg, r#"<text x="{}" y="{}" font-family="sans-serif" font-size="12">{} ({} matches)</text>"#,
+                     MARGIN, MARGIN, xml_escape(title), family.len());
+    let max_bits = (alphabet.symbols().len() as f32).log2();
+    for position in 0..length {
+        let mut frequencies: Vec<(f32, char, &str)> = alphabet.symbols().iter()
+            .map(|&base| {
+                let count = family.iter().filter(|seq| seq[position] == base).count();
+                (count as f32 / family.len() as f32, base, colour(base))
+            })
+            .collect();
+        let entropy: f32 = frequencies.iter()
+            .filter(|(f, _, _)| *f > 0.0)
+            .map(|(f, _, _)| -f * f.log2())
+            .sum();
+        let information = max_bits - entropy;
+        // smallest letter at the bottom
+        frequencies.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let x = MARGIN + (position as f32 + 0.5) * COLUMN_WIDTH;
+        let mut y = baseline;
+        for (frequency, base, colour) in frequencies {
+            let letter_height = frequency * information / max_bits * BITS_HEIGHT;
+            if letter_height < 0.5 {
+                continue
+            }
+            let _ = writeln!(svg, r#"<text transform="translate({:.1},{:.1}) scale(1,{:.3})" font-family="monospace" font-weight="bold" font-size="{}" fill="{}" text-anchor="middle">{}</text>"#,
+                             x, y, letter_height / (FONT_SIZE * CAP_HEIGHT), FONT_SIZE, colour,
+                             base);
+            y -= letter_height;
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn file_name(name: &str) -> String {
+    let stem: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}.svg", stem)
+}
+
+/// Write a logo for every input with at least `min_matches` matches of
+/// its own length
+pub fn write_logos(dir: &Path, input_seq_props: &[AsoProfile], min_matches: usize,
+                   alphabet: &Alphabet) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    for aso in input_seq_props {
+        let length = aso.seq.chars().count();
+        let family: Vec<Vec<char>> = aso.aso_names.iter()
+            .map(|hit| hit.aso.seq.chars().collect::<Vec<char>>())
+            .filter(|seq| seq.len() == length)
+            .collect();
+        if family.is_empty() || family.len() < min_matches {
+            continue
+        }
+        let path = dir.join(file_name(&aso.name));
+        info!("Writing sequence logo of {} to {:?}", aso.name, path);
+        fs::write(path, logo_svg(&aso.name, &family, alphabet))?;
+    }
+    Ok(())
+}
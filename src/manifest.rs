@@ -0,0 +1,189 @@
+/*
+    Run manifest for GLP-style audit trails
+        - parameters, file hashes, versions
+        - timings, counters
+        - summary of the results
+        - fingerprints of the library entries, for incremental checks
+    Written separately from the results themselves.
+*/
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::incremental::{entry_digest, input_digest};
+use crate::{redact, AsoProfile, Cli, RunStats};
+
+/// Wraps a reader and hashes every byte passing through it, so files
+/// can be fingerprinted without reading them twice.
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// SHA-256 of everything read so far, as lowercase hex
+    pub fn hex_digest(self) -> String {
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct FileRecord {
+    path: PathBuf,
+    sha256: String,
+}
+
+impl FileRecord {
+    pub fn new(path: PathBuf, sha256: String) -> Self {
+        FileRecord { path, sha256 }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+#[derive(Serialize)]
+pub struct Timings {
+    pub load_ms: u128,
+    pub compare_ms: u128,
+    pub total_ms: u128,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MatchSummary {
+    pub name: String,
+    pub seq: String,
+    pub distance: f32,
+    /// Every computed metric, when more than one was requested
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f32>,
+    /// Library file of the match, when several were loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InputSummary {
+    pub name: String,
+    pub seq: String,
+    /// Per-input values such as the verdict
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// All matches within threshold, also those left out by --max-hits
+    #[serde(default)]
+    pub match_count: usize,
+    pub matches: Vec<MatchSummary>,
+    /// Names of the inputs of a group of identical ones, see the dedup module
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<String>,
+    /// Input a derived strand (<name>_rc, <name>_passenger) was made from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strand_of: Option<String>,
+}
+
+/// Inputs with their matches, as in the manifest and --output-format json,
+/// the first `max_hits` matches of each input when given
+pub fn summarize(input_seq_props: &[AsoProfile], max_hits: Option<usize>, redact: bool) -> Vec<InputSummary> {
+    input_seq_props.iter()
+        .map(|aso| InputSummary {
+            name: aso.name.clone(),
+            seq: aso.seq.clone(),
+            annotations: aso.annotations.iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+            match_count: aso.aso_names.len(),
+            matches: aso.aso_names.iter()
+                .take(max_hits.unwrap_or(usize::MAX))
+                .map(|hit| MatchSummary {
+                    name: hit.aso.name.clone(),
+                    seq: redact::shown(&hit.aso.seq, redact).into_owned(),
+                    distance: hit.distance,
+                    metrics: match hit.scores.len() {
+                        1 => BTreeMap::new(),
+                        _ => hit.scores.iter()
+                            .map(|(metric, score)| (format!("{:?}", metric), *score))
+                            .collect(),
+                    },
+                    library: hit.annotations.iter()
+                        .find(|(column, _)| *column == "Library")
+                        .map(|(_, library)| library.clone()),
+                })
+                .collect(),
+            members: aso.members.clone(),
+            strand_of: aso.strand_of.clone(),
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+pub struct Manifest<'a> {
+    tool: &'static str,
+    version: &'static str,
+    run_id: &'a str,
+    started_at: u64,
+    parameters: &'a Cli,
+    files: Vec<FileRecord>,
+    timings: Timings,
+    counters: &'a RunStats,
+    /// Fingerprint of every library entry, for --previous-manifest
+    library_entries: Vec<String>,
+    /// Fingerprint of every input, in the order of the results
+    input_entries: Vec<String>,
+    results: Vec<InputSummary>,
+}
+
+impl<'a> Manifest<'a> {
+    pub fn new(cli: &'a Cli, run_id: &'a str, files: Vec<FileRecord>, timings: Timings,
+               counters: &'a RunStats, library: &[Arc<AsoProfile>],
+               input_seq_props: &[AsoProfile]) -> Self {
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let started_at = (finished_at.saturating_sub(timings.total_ms) / 1000) as u64;
+        let results = summarize(input_seq_props, None, cli.redact_sequences);
+        Manifest {
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            run_id,
+            started_at,
+            parameters: cli,
+            files,
+            timings,
+            counters,
+            library_entries: library.iter().map(|aso| entry_digest(aso)).collect(),
+            input_entries: input_seq_props.iter().map(input_digest).collect(),
+            results,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+/*
+    Masked positions (--mask-file)
+        - csv of name,start,end rows: 1-based inclusive position ranges of
+          an input that are excluded from comparison, e.g. positions known
+          to tolerate changes; several rows per name add up
+        - positions are those of the sequence as compared, after
+          --trim5/--trim3; the reverse complement of a masked input
+          (<name>_rc) gets the mirrored positions
+        - a masked position never counts as a mismatch: the library
+          sequence takes the input's base there before every metric, the
+          identity gate and the null model
+        - the manifest records the SHA-256 of the mask file
+*/
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+use log::{info, warn};
+use crate::encoding::DecodingReader;
+use crate::error::CheckError;
+use crate::manifest::HashingReader;
+use crate::{dialect, AsoProfile, Cli};
+
+/// 0-based masked positions by input name
+type Masks = BTreeMap<String, BTreeSet<usize>>;
+
+/// Masked positions of every name, with the SHA-256 of the file
+fn load_masks(path: &Path, cli: &Cli) -> Result<(Masks, String), Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open mask file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut decoder = DecodingReader::new(HashingReader::new(file), &label)?;
+    let mut reader = dialect::reader_builder(cli, decoder.sample()?)
+        .has_headers(cli.mask_header_status)
+        .from_reader(decoder);
+    let mut masks = Masks::new();
+    for result in reader.records() {
+        let record = result?;
+        let line = record.position().map_or(0, |pos| pos.line());
+        let position = |i: usize| -> Result<usize, CheckError> {
+            let cell = record.get(i).unwrap_or("").trim();
+            match cell.parse::<usize>() {
+                Ok(position) if position > 0 => Ok(position),
+                _ => Err(CheckError::Data(format!("{}:{}: invalid position {:?}, expected a number from 1",
+                                                  label, line, cell))),
+            }
+        };
+        let (start, end) = (position(1)?, position(2)?);
+        if start > end {
+            return Err(CheckError::Data(format!("{}:{}: start {} is after end {}", label, line, start, end)).into())
+        }
+        let name = record.get(0).unwrap_or("").trim().to_string();
+        masks.entry(name).or_default().extend(start - 1..end);
+    }
+    Ok((masks, reader.into_inner().into_inner().hex_digest()))
+}
+
+/// Attach the masked positions of the mask file to the inputs they name,
+/// returns the SHA-256 of the file
+pub fn apply_masks(path: &Path, inputs: &mut [AsoProfile], cli: &Cli) -> Result<String, Box<dyn Error>> {
+    let (masks, sha256) = load_masks(path, cli)?;
+    let mut used = BTreeSet::new();
+    for input in inputs.iter_mut() {
+        if let Some(positions) = masks.get(&input.name) {
+            input.mask = positions.clone();
+            used.insert(input.name.as_str());
+        } else if let Some(positions) = input.name.strip_suffix("_rc").and_then(|name| masks.get(name)) {
+            input.mask = positions.iter()
+                .filter(|&&position| position < input.aso_len)
+                .map(|position| input.aso_len - 1 - position)
+                .collect();
+        }
+        if input.mask.last().is_some_and(|&position| position >= input.aso_len) {
+            warn!("Mask of {} reaches past its {} bases", input.name, input.aso_len);
+        }
+    }
+    for name in masks.keys().filter(|name| !used.contains(name.as_str())) {
+        warn!("Mask file names {}, which is not an input", name);
+    }
+    info!("Masked positions of {} inputs", used.len());
+    Ok(sha256)
+}
+
+/// `other` with the input's bases at the input's masked positions, so
+/// they can't differ
+pub fn masked<'a>(input: &AsoProfile, other: &'a str) -> Cow<'a, str> {
+    if input.mask.is_empty() {
+        return Cow::Borrowed(other)
+    }
+    Cow::Owned(other.chars()
+        .zip(input.seq.chars().map(Some).chain(std::iter::repeat(None)))
+        .enumerate()
+        .map(|(position, (base, input_base))| match input_base {
+            Some(input_base) if input.mask.contains(&position) => input_base,
+            _ => base,
+        })
+        .collect())
+}
+
+/// Masked positions as 1-based ranges, e.g. 3-5,9
+pub fn describe(mask: &BTreeSet<usize>) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &position in mask {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == position => *end = position,
+            _ => ranges.push((position, position)),
+        }
+    }
+    ranges.iter()
+        .map(|&(start, end)| match start == end {
+            true => (start + 1).to_string(),
+            false => format!("{}-{}", start + 1, end + 1),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,100 @@
+/*
+    Bit-parallel Levenshtein distance (Myers, with Hyyrö's formulation)
+        - one column of the edit distance table is kept as bit vectors of
+          vertical +1/-1 differences in a u64, so each base of the text is
+          a handful of word operations instead of a row of the table
+        - used for ASCII sequences of at most 64 bases, the normal ASO
+          range; longer or non-ASCII ones go to the distance crate
+        - with a cutoff, stops once the distance can't come back under it
+        - with --ambiguity aware, a text base matches every pattern position
+          whose IUPAC code it is compatible with
+    Gives exactly the same distances as the plain algorithm.
+*/
+
+use crate::banded;
+use crate::iupac::{self, Ambiguity};
+
+/// Longest pattern that fits the bit vectors
+const WORD: usize = u64::BITS as usize;
+
+/// The shorter sequence is the pattern, kept in the bit vectors
+fn pattern_and_text<'a>(a: &'a str, b: &'a str) -> (&'a str, &'a str) {
+    if a.len() <= b.len() { (a, b) } else { (b, a) }
+}
+
+/// The pair can be compared with the bit vectors
+pub fn fits(a: &str, b: &str) -> bool {
+    let (pattern, text) = pattern_and_text(a, b);
+    pattern.len() <= WORD && pattern.is_ascii() && text.is_ascii()
+}
+
+/// Levenshtein distance of two sequences
+pub fn levenshtein(a: &str, b: &str, ambiguity: Ambiguity) -> usize {
+    if !fits(a, b) {
+        return match ambiguity {
+            Ambiguity::Strict => distance::levenshtein(a, b),
+            // no distance exceeds the longer length
+            Ambiguity::Aware => banded::levenshtein_within(a, b, a.len().max(b.len()), ambiguity)
+                .expect("within the longer length"),
+        }
+    }
+    let (pattern, text) = pattern_and_text(a, b);
+    myers(pattern.as_bytes(), text.as_bytes(), usize::MAX, ambiguity).expect("no cutoff")
+}
+
+/// Levenshtein distance of a pair that fits, None when it is above `max`
+pub fn levenshtein_within(a: &str, b: &str, max: usize, ambiguity: Ambiguity) -> Option<usize> {
+    let (pattern, text) = pattern_and_text(a, b);
+    myers(pattern.as_bytes(), text.as_bytes(), max, ambiguity)
+}
+
+/// Pattern positions each symbol matches
+fn match_vectors(pattern: &[u8], text: &[u8], ambiguity: Ambiguity) -> [u64; 128] {
+    let mut peq = [0u64; 128];
+    for (i, &symbol) in pattern.iter().enumerate() {
+        peq[symbol as usize] |= 1 << i;
+    }
+    if ambiguity == Ambiguity::Aware {
+        let mut done = [false; 128];
+        for &symbol in text {
+            if !std::mem::replace(&mut done[symbol as usize], true) {
+                peq[symbol as usize] = pattern.iter().enumerate()
+                    .filter(|&(_, &other)| iupac::compatible(symbol as char, other as char, ambiguity))
+                    .fold(0, |eq, (i, _)| eq | 1 << i);
+            }
+        }
+    }
+    peq
+}
+
+fn myers(pattern: &[u8], text: &[u8], max: usize, ambiguity: Ambiguity) -> Option<usize> {
+    if pattern.is_empty() {
+        return Some(text.len()).filter(|&distance| distance <= max)
+    }
+    let peq = match_vectors(pattern, text, ambiguity);
+    let last = 1u64 << (pattern.len() - 1);
+    let (mut pv, mut mv) = (!0u64, 0u64);
+    let mut score = pattern.len();
+    for (column, &symbol) in text.iter().enumerate() {
+        let eq = peq[symbol as usize];
+        let xv = eq | mv;
+        let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+        let mut ph = mv | !(xh | pv);
+        let mut mh = pv & xh;
+        if ph & last != 0 {
+            score += 1;
+        } else if mh & last != 0 {
+            score -= 1;
+        }
+        // the top row of the table grows by one per text base
+        ph = (ph << 1) | 1;
+        mh <<= 1;
+        pv = mh | !(xv | ph);
+        mv = ph & xv;
+        // the last row drops by at most one per remaining text base
+        if score.saturating_sub(text.len() - column - 1) > max {
+            return None
+        }
+    }
+    Some(score)
+}
@@ -0,0 +1,84 @@
+/*
+    Name sanitization (--sanitize-names)
+        - surrounding whitespace is stripped, inner runs of whitespace
+          become a single _
+        - names are cut to --max-name-length characters
+        - a name already taken in the same file gets a suffix: _2, _3, ...
+        - every changed name is recorded, and with --name-map written as a
+          csv mapping table (source, line, original, sanitized)
+    Keeps messy vendor names from producing ambiguous or colliding rows.
+*/
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+use log::info;
+
+/// A name changed by sanitization
+#[derive(Debug, Clone)]
+pub struct Rename {
+    source: String,
+    line: u64,
+    original: String,
+    sanitized: String,
+}
+
+impl Rename {
+    pub fn new(source: String, line: u64, original: String, sanitized: String) -> Self {
+        Rename { source, line, original, sanitized }
+    }
+}
+
+/// Sanitizes the names of one file, keeping them unique
+#[derive(Debug, Default)]
+pub struct NameSanitizer {
+    max_length: Option<usize>,
+    taken: HashSet<String>,
+    pub renamed: Vec<Rename>,
+}
+
+fn truncated(name: &str, length: Option<usize>) -> String {
+    match length {
+        Some(length) => name.chars().take(length).collect(),
+        None => name.to_string(),
+    }
+}
+
+impl NameSanitizer {
+    pub fn new(max_length: Option<usize>) -> Self {
+        NameSanitizer { max_length, ..NameSanitizer::default() }
+    }
+
+    pub fn sanitize(&mut self, name: &str, source: &str, line: u64) -> String {
+        let collapsed = name.split_whitespace().collect::<Vec<_>>().join("_");
+        let mut sanitized = truncated(&collapsed, self.max_length);
+        let mut copy = 1;
+        while self.taken.contains(&sanitized) {
+            copy += 1;
+            let suffix = format!("_{}", copy);
+            let room = self.max_length.map(|length| length.saturating_sub(suffix.len()));
+            sanitized = format!("{}{}", truncated(&collapsed, room), suffix);
+        }
+        self.taken.insert(sanitized.clone());
+        if sanitized != name {
+            self.renamed.push(Rename {
+                source: source.to_string(),
+                line,
+                original: name.to_string(),
+                sanitized: sanitized.clone(),
+            });
+        }
+        sanitized
+    }
+}
+
+/// Write the mapping table of every renamed library and input ASO
+pub fn write_name_map(path: &Path, renamed: &[Rename]) -> Result<(), Box<dyn Error>> {
+    info!("Writing {} renamed ASOs to {:?}", renamed.len(), path);
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["source", "line", "original", "sanitized"])?;
+    for rename in renamed {
+        writer.write_record([&rename.source, &rename.line.to_string(), &rename.original, &rename.sanitized])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+/*
+    Shuffling null model (--null-shuffles N)
+        - each reported match is shuffled N times, keeping its composition
+        - the input is scored against every shuffle with the primary metric
+        - z-score of the observed distance against those, and the empirical
+          p-value: how often a shuffle came at least as close
+    A low p-value means the similarity is more than composition alone
+    would explain. Shuffles are seeded from the sequences and --null-seed,
+    so reruns give the same numbers.
+*/
+use crate::{mask, AsoMatch, AsoProfile, Dist, MetricOptions};
+
+/// splitmix64, small and reproducible across platforms
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// FNV-1a, to derive a per-pair seed
+fn fnv(text: &str) -> u64 {
+    text.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+fn shuffle(seq: &mut [char], rng: &mut Rng) {
+    for i in (1..seq.len()).rev() {
+        seq.swap(i, rng.below(i + 1));
+    }
+}
+
+/// z-score and empirical p-value of the match distance against shuffles
+pub fn null_model(input: &AsoProfile, hit: &AsoMatch, metric: Dist, options: &MetricOptions,
+                  shuffles: usize, seed: u64) -> Option<(f32, f32)> {
+    if shuffles == 0 {
+        return None
+    }
+    let mut rng = Rng(seed ^ fnv(&input.seq) ^ fnv(&hit.aso.seq).rotate_left(1));
+    let mut shuffled: Vec<char> = hit.aso.seq.chars().collect();
+    let mut distances = Vec::with_capacity(shuffles);
+    for _ in 0..shuffles {
+        shuffle(&mut shuffled, &mut rng);
+        let candidate: String = shuffled.iter().collect();
+        distances.push(metric.distance(&input.seq, &mask::masked(input, &candidate), options).ok()?);
+    }
+    let mean = distances.iter().sum::<f32>() / shuffles as f32;
+    let variance = distances.iter().map(|d| (d - mean).powi(2)).sum::<f32>() / shuffles as f32;
+    let z = match variance.sqrt() {
+        sd if sd > 0.0 => (hit.distance - mean) / sd,
+        _ => 0.0,
+    };
+    let as_close = distances.iter().filter(|&&d| d <= hit.distance).count();
+    let p = (as_close + 1) as f32 / (shuffles + 1) as f32;
+    Some((z, p))
+}
@@ -0,0 +1,43 @@
+/*
+    Results written to a file (--output)
+        - the results go to a temporary file next to the destination,
+          which is then renamed over it: readers never see a half-written
+          file, and a failed run leaves the previous results intact
+        - with --append the existing content is kept in front of the new
+          results, so successive runs or batch jobs accumulate in one file
+*/
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use log::info;
+
+/// Hidden temporary file in the destination's directory, for the rename
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+fn write_temporary(temporary: &Path, path: &Path, contents: &[u8], append: bool) -> io::Result<()> {
+    let mut file = match append && path.exists() {
+        true => {
+            fs::copy(path, temporary)?;
+            OpenOptions::new().append(true).open(temporary)?
+        }
+        false => File::create(temporary)?,
+    };
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Replace (or with `append`, extend) the file at `path` with `contents`
+pub fn write_output(path: &Path, contents: &[u8], append: bool) -> io::Result<()> {
+    info!("Writing output to {:?}", path);
+    let temporary = temporary_path(path);
+    let written = write_temporary(&temporary, path, contents, append)
+        .and_then(|_| fs::rename(&temporary, path));
+    if written.is_err() {
+        // the destination is untouched, don't leave the partial copy behind
+        let _ = fs::remove_file(&temporary);
+    }
+    written
+}
@@ -0,0 +1,74 @@
+/*
+    Per-input parameter overrides
+        - optional named columns of the input file (requires a header)
+        - max_distance: replaces --max-distance for that input
+        - orientation: replaces --orientation (forward or both)
+        - parent: replaces --parent, the sequence the input was designed from
+        - an empty cell keeps the global setting
+        - numbers follow --csv-locale, so 1,5 works in European files
+    Lets a single run screen inputs that need different settings,
+    e.g. gapmers and steric blockers.
+*/
+use clap::ValueEnum;
+use csv::StringRecord;
+use log::info;
+use crate::error::CheckError;
+use crate::locale::CsvLocale;
+use crate::Orientation;
+
+/// Positions of the override columns in the input file
+#[derive(Debug, Default)]
+pub struct OverrideColumns {
+    max_distance: Option<usize>,
+    orientation: Option<usize>,
+    parent: Option<usize>,
+    locale: CsvLocale,
+}
+
+/// Settings of one input that differ from the commandline
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    pub max_distance: Option<f32>,
+    pub orientation: Option<Orientation>,
+    pub parent: Option<String>,
+}
+
+fn column_key(header: &str) -> String {
+    header.trim().to_ascii_lowercase().replace('-', "_")
+}
+
+impl OverrideColumns {
+    pub fn from_headers(headers: &StringRecord, locale: CsvLocale) -> Self {
+        let find = |key: &str| headers.iter().position(|h| column_key(h) == key);
+        let columns = OverrideColumns {
+            max_distance: find("max_distance"),
+            orientation: find("orientation"),
+            parent: find("parent"),
+            locale,
+        };
+        if columns.max_distance.is_some() || columns.orientation.is_some() || columns.parent.is_some() {
+            info!("Using per-input overrides from the input file: {:?}", columns);
+        }
+        columns
+    }
+
+    fn cell(record: &StringRecord, column: Option<usize>) -> Option<&str> {
+        column.and_then(|i| record.get(i)).map(str::trim).filter(|cell| !cell.is_empty())
+    }
+
+    /// Overrides of a record, with file:line context on bad values
+    pub fn read(&self, record: &StringRecord, file: &str) -> Result<Overrides, CheckError> {
+        let line = record.position().map_or(0, |pos| pos.line());
+        let max_distance = Self::cell(record, self.max_distance)
+            .map(|cell| self.locale.parse_number(cell)
+                .map_err(|_| CheckError::Data(format!("{}:{}: invalid max_distance {:?}", file, line, cell))))
+            .transpose()?;
+        let orientation = Self::cell(record, self.orientation)
+            .map(|cell| Orientation::from_str(cell, true)
+                .map_err(|_| CheckError::Data(format!("{}:{}: invalid orientation {:?}, expected forward or both",
+                                                      file, line, cell))))
+            .transpose()?;
+        let parent = Self::cell(record, self.parent).map(String::from);
+        Ok(Overrides { max_distance, orientation, parent })
+    }
+}
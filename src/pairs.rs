@@ -0,0 +1,79 @@
+/*
+    Explicit pairs (--pairs)
+        - csv of name1,name2 rows, names of library or input ASOs
+        - every metric is computed for just those pairs, with no prefilter
+          or cutoffs, to re-check combinations flagged in earlier reviews
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use log::{info, warn};
+use crate::encoding::DecodingReader;
+use crate::error::CheckError;
+use crate::{dialect, load_inputs, Cli, Dist, Library, RunStats};
+
+fn print_pairs(out: &mut dyn Write, pairs: &[(String, String)], sequences: &HashMap<String, String>,
+               cli: &Cli) -> io::Result<()> {
+    let metrics = Dist::All.metrics();
+    if !cli.raw {
+        let header: Vec<String> = metrics.iter().map(|metric| format!("{:?}", metric)).collect();
+        writeln!(out, "{:<10}\t{:<20}\t{:<10}\t{:<20}\t{}", "Name 1", "Seq", "Name 2", "Seq",
+                 header.join("\t"))?;
+    }
+    for (a, b) in pairs {
+        let (seq_a, seq_b) = (&sequences[a], &sequences[b]);
+        let scores: Vec<String> = metrics.iter()
+            .map(|metric| match metric.distance(seq_a, seq_b, &cli.metric_options) {
+                Ok(score) => metric.format(score, cli.precision),
+                Err(_) => String::from("n/a"),
+            })
+            .collect();
+        writeln!(out, "{:<10}\t{:<20}\t{:<10}\t{:<20}\t{}", a, seq_a, b, seq_b, scores.join("\t"))?;
+    }
+    Ok(())
+}
+
+pub fn check_pairs(cli: &Cli, path: &Path, library: &Library,
+                   out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut sequences: HashMap<String, String> = library.asos.iter()
+        .map(|aso| (aso.name.clone(), aso.seq.clone()))
+        .collect();
+    if cli.multiple_aso || cli.aso_seq.is_some() {
+        let (inputs, _) = load_inputs(cli, &mut RunStats::default())?;
+        for aso in inputs {
+            if let Some(previous) = sequences.insert(aso.name.clone(), aso.seq) {
+                warn!("Input {} shadows the library ASO of the same name ({})", aso.name, previous);
+            }
+        }
+    }
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open pairs file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut decoder = DecodingReader::new(file, &label)?;
+    let mut reader = dialect::reader_builder(cli, decoder.sample()?)
+        .has_headers(cli.pairs_header_status)
+        .from_reader(decoder);
+    let mut pairs = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let line = record.position().map_or(0, |pos| pos.line());
+        let name = |i: usize| -> Result<String, CheckError> {
+            let name = record.get(i).unwrap_or("").trim().to_string();
+            match sequences.contains_key(&name) {
+                true => Ok(name),
+                false => Err(CheckError::Data(format!("{}:{}: no library or input ASO named {:?}", label, line, name))),
+            }
+        };
+        pairs.push((name(0)?, name(1)?));
+    }
+    info!("Computing distances of {} pairs", pairs.len());
+    // a closed pipe (e.g. `| head`) is not an error
+    if let Err(e) = print_pairs(out, &pairs, &sequences, cli).and_then(|_| out.flush()) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e.into())
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+/*
+    Guard against inputs that are not nucleotide sequences
+        - an input whose letters are mostly outside ACGTU, N and the
+          alphabet (and the IUPAC codes, with --ambiguity aware) is
+          rejected before any comparison, distances to it would mean
+          nothing
+        - the error names the likely mistake: a peptide (amino-acid
+          sequence), a header row read as data, name and sequence columns
+          swapped, or a number
+    Case is ignored, so soft-masked bases are never counted.
+*/
+use crate::alphabet::Alphabet;
+use crate::error::CheckError;
+use crate::iupac::{self, Ambiguity};
+
+/// Above this fraction of non-nucleotide letters an input is rejected
+const MAX_FOREIGN_FRACTION: f32 = 0.25;
+
+/// Amino-acid one-letter codes that are no nucleotide or IUPAC code
+const AMINO_ACID_ONLY: &str = "EFILPQ";
+
+fn is_nucleotide(c: char, alphabet: &Alphabet, ambiguity: Ambiguity) -> bool {
+    let c = c.to_ascii_uppercase();
+    "ACGTUN".contains(c) || alphabet.contains(c) || ambiguity == Ambiguity::Aware && iupac::is_code(c)
+}
+
+/// Fraction of the characters of `seq` that are no nucleotide
+fn foreign_fraction(seq: &str, alphabet: &Alphabet, ambiguity: Ambiguity) -> f32 {
+    let length = seq.chars().count();
+    if length == 0 {
+        return 0.0
+    }
+    seq.chars().filter(|&c| !is_nucleotide(c, alphabet, ambiguity)).count() as f32 / length as f32
+}
+
+fn likely_mistake(name: &str, seq: &str, line: u64, headerless: bool, alphabet: &Alphabet,
+                  ambiguity: Ambiguity) -> &'static str {
+    let letters = seq.chars().filter(|c| c.is_ascii_alphabetic()).count();
+    if headerless && line == 1 {
+        "this looks like a header row, drop --input-no-header"
+    } else if foreign_fraction(name, alphabet, ambiguity) <= MAX_FOREIGN_FRACTION && !name.is_empty() {
+        "the name looks like the sequence, are the name and sequence columns swapped?"
+    } else if letters == 0 && seq.chars().any(|c| c.is_ascii_digit()) {
+        "this looks like a number, is the sequence in the second column?"
+    } else if seq.chars().any(|c| AMINO_ACID_ONLY.contains(c.to_ascii_uppercase())) {
+        "this looks like a peptide (amino-acid sequence), ASOs are compared as nucleotides"
+    } else {
+        "expected a nucleotide sequence"
+    }
+}
+
+/// Reject an input that doesn't look like a nucleotide sequence, with
+/// file:line context and the likely mistake; `headerless` when the first
+/// row of a file is read as data
+pub fn check(name: &str, seq: &str, file: &str, line: u64, headerless: bool,
+             alphabet: &Alphabet, ambiguity: Ambiguity) -> Result<(), CheckError> {
+    let fraction = foreign_fraction(seq, alphabet, ambiguity);
+    if fraction <= MAX_FOREIGN_FRACTION {
+        return Ok(())
+    }
+    Err(CheckError::Data(format!("{}:{}: {} is not a nucleotide sequence, {:.0}% of {:?} is outside ACGTUN: {}",
+                                 file, line, name, fraction * 100.0, seq,
+                                 likely_mistake(name, seq, line, headerless, alphabet, ambiguity))))
+}
@@ -0,0 +1,75 @@
+/*
+    Screening presets
+        - strict: length prefilter, Levenshtein, distance <= 5, both orientations
+        - standard: composition prefilter, Levenshtein, distance <= 3
+        - lenient: composition prefilter, Levenshtein, distance <= 2
+        - calibrated: the metric and distance of the --calibration
+          profile (see the calibrate module), auto prefilter
+    Flags given on the commandline always win over the preset.
+*/
+use std::error::Error;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Serialize;
+use crate::error::CheckError;
+use crate::{calibrate, Cli, Dist, Orientation, Prefilter};
+
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum Preset {
+    Strict,
+    Standard,
+    Lenient,
+    Calibrated,
+}
+
+impl Preset {
+    fn prefilter(self) -> Prefilter {
+        match self {
+            Preset::Strict => Prefilter::Length,
+            Preset::Standard | Preset::Lenient => Prefilter::Composition,
+            // exact for any metric and cutoff, the profile was fitted without a prefilter
+            Preset::Calibrated => Prefilter::Auto,
+        }
+    }
+
+    /// Metric and distance cutoff
+    fn cutoff(self, cli: &Cli) -> Result<(Dist, f32), Box<dyn Error>> {
+        Ok(match self {
+            Preset::Strict => (Dist::Levenshtein, 5.0),
+            Preset::Standard => (Dist::Levenshtein, 3.0),
+            Preset::Lenient => (Dist::Levenshtein, 2.0),
+            Preset::Calibrated => {
+                let path = cli.calibration.as_deref()
+                    .ok_or(CheckError::Usage(String::from("--preset calibrated needs a --calibration profile")))?;
+                let profile = calibrate::load(path)?;
+                (profile.metric, profile.max_distance)
+            }
+        })
+    }
+
+    fn orientation(self) -> Orientation {
+        match self {
+            Preset::Strict => Orientation::Both,
+            Preset::Standard | Preset::Lenient | Preset::Calibrated => Orientation::Forward,
+        }
+    }
+
+    /// Fill in every setting the user did not give explicitly
+    pub fn apply(self, cli: &mut Cli, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+        let from_user = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let (metric, max_distance) = self.cutoff(cli)?;
+        if !from_user("List") {
+            cli.list_by = metric;
+        }
+        if !from_user("prefilter") {
+            cli.prefilter = self.prefilter();
+        }
+        if !from_user("max_distance") {
+            cli.max_distance = Some(max_distance);
+        }
+        if !from_user("orientation") {
+            cli.orientation = self.orientation();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+/*
+    q-gram distance (--list-by qgram)
+        - every substring of --qgram-size bases is counted in both sequences
+        - the distance is the sum over all q-grams of the difference of
+          their counts (Ukkonen), 0 for sequences with the same q-grams
+        - a sequence shorter than q has no q-grams, so the distance is the
+          q-gram count of the other one
+        - cosine: one minus the cosine similarity of the k-mer count vectors
+          of --cosine-k bases, 0 for the same k-mer frequencies and 1 for
+          none in common; by frequency rather than count, so it compares
+          compositions of sequences of different lengths too
+    Alignment-free and fast: a coarse screen for scrambles that keep much
+    of the local context of the original.
+*/
+use std::collections::HashMap;
+
+/// Default --qgram-size and --cosine-k
+pub const DEFAULT_QGRAM_SIZE: usize = 3;
+
+pub fn parse_qgram_size(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(format!("Expected a k-mer size of at least 1, got {}", s)),
+    }
+}
+
+/// Sum of the q-gram count differences of two sequences
+pub fn qgram(a: &str, b: &str, q: usize) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut counts: HashMap<&[char], isize> = HashMap::new();
+    for gram in a.windows(q) {
+        *counts.entry(gram).or_default() += 1;
+    }
+    for gram in b.windows(q) {
+        *counts.entry(gram).or_default() -= 1;
+    }
+    counts.values().map(|count| count.unsigned_abs()).sum()
+}
+
+/// Count of every k-mer of a sequence
+fn kmer_counts(seq: &[char], k: usize) -> HashMap<&[char], usize> {
+    let mut counts: HashMap<&[char], usize> = HashMap::new();
+    for kmer in seq.windows(k) {
+        *counts.entry(kmer).or_default() += 1;
+    }
+    counts
+}
+
+/// One minus the cosine similarity of the k-mer count vectors
+pub fn cosine(a: &str, b: &str, k: usize) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (a, b) = (kmer_counts(&a, k), kmer_counts(&b, k));
+    let squared = |counts: &HashMap<&[char], usize>| counts.values().map(|&count| count * count).sum::<usize>();
+    match (squared(&a), squared(&b)) {
+        (0, 0) => 0.0,
+        (0, _) | (_, 0) => 1.0,
+        (squared_a, squared_b) => {
+            let dot: usize = a.iter().map(|(kmer, count)| count * b.get(kmer).unwrap_or(&0)).sum();
+            // one square root of the product, so equal vectors come out at exactly 1
+            (1.0 - dot as f64 / ((squared_a * squared_b) as f64).sqrt()).max(0.0) as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qgram_sums_the_count_differences() {
+        assert_eq!(qgram("ACGT", "ACGT", 2), 0);
+        // AC, CG, GT against AC, CG, GA: GT and GA once each
+        assert_eq!(qgram("ACGT", "ACGA", 2), 2);
+        // AA three times against twice
+        assert_eq!(qgram("AAAA", "AAA", 2), 1);
+        // AC twice against once, GT once against twice
+        assert_eq!(qgram("ACGTAC", "GTACGT", 2), 2);
+    }
+
+    #[test]
+    fn qgram_of_a_sequence_shorter_than_q_counts_the_other() {
+        assert_eq!(qgram("A", "ACGT", 2), 3);
+        assert_eq!(qgram("A", "C", 2), 0);
+        assert!(parse_qgram_size("0").is_err());
+        assert_eq!(parse_qgram_size(" 4 "), Ok(4));
+    }
+
+    #[test]
+    fn cosine_compares_kmer_frequencies() {
+        // one A and one C each
+        assert_eq!(cosine("AACC", "ACAC", 1), 0.0);
+        assert_eq!(cosine("AAAA", "CCCC", 1), 1.0);
+        // (1, 1) against (2, 0): 1 - 2 / sqrt(2 * 4)
+        assert!((cosine("AC", "AA", 1) - 0.292893).abs() < 1e-6);
+        // the same frequencies at another length
+        assert_eq!(cosine("ACGT", "ACGTACGTACGT", 1), 0.0);
+    }
+
+    #[test]
+    fn cosine_of_sequences_shorter_than_k() {
+        assert_eq!(cosine("AC", "GT", 3), 0.0);
+        assert_eq!(cosine("AC", "ACG", 3), 1.0);
+    }
+}
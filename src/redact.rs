@@ -0,0 +1,26 @@
+/*
+    Redacted library sequences (--redact-sequences)
+        - every library sequence in the results, the results file, the
+          graph and the manifest is replaced by a short SHA-256 digest,
+          e.g. sha256:5d41402abc4b2a76
+        - names, distances, extra columns and verdicts are kept, and the
+          same sequence always gets the same digest, so reports can be
+          cross-checked without revealing the library
+    For reports shared with external partners. Input sequences are shown
+    as usual.
+*/
+use std::borrow::Cow;
+use sha2::{Digest, Sha256};
+
+/// Digest shown instead of a redacted sequence
+pub fn digest(seq: &str) -> String {
+    format!("sha256:{}", &format!("{:x}", Sha256::digest(seq.as_bytes()))[..16])
+}
+
+/// A library sequence as it may be shown
+pub fn shown(seq: &str, redact: bool) -> Cow<'_, str> {
+    match redact {
+        true => Cow::Owned(digest(seq)),
+        false => Cow::Borrowed(seq),
+    }
+}
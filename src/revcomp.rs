@@ -0,0 +1,111 @@
+/*
+    Reverse complement an ASO file
+        - same csv layout as the inputs: name in column1, sequence in column2
+          or --seq-column, in the dialect of --csv-locale, --delimiter,
+          --quote-char and --comment-prefix
+        - every other column and the header row are written unchanged, in
+          the delimiter and quote character read; comment lines are left
+          out
+        - a FASTA file is written back as FASTA, each record as >name and
+          its sequence on one line; FASTQ is refused, its qualities would
+          be left in the wrong order
+        - lowercase bases stay lowercase, unknown characters are kept
+        - always written as UTF-8, whatever the encoding of the file
+*/
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use csv::WriterBuilder;
+use log::info;
+use crate::alphabet::Alphabet;
+use crate::columns::{self, ColumnSpec};
+use crate::dialect::DialectArgs;
+use crate::encoding::DecodingReader;
+use crate::error::CheckError;
+use crate::fasta::{self, FileFormat};
+use crate::locale::CsvLocale;
+
+/// How the file to reverse complement is read
+pub struct RevcompOptions<'a> {
+    pub header: bool,
+    pub alphabet: &'a Alphabet,
+    pub seq_column: Option<&'a ColumnSpec>,
+    pub csv_locale: CsvLocale,
+    pub dialect: &'a DialectArgs,
+}
+
+/// Reverse complement that keeps the case of each base
+pub fn reverse_complement(seq: &str, alphabet: &Alphabet) -> String {
+    seq.chars()
+        .rev()
+        .map(|c| match alphabet.complement(c.to_ascii_uppercase()) {
+            Some(complement) if c.is_ascii_lowercase() => complement.to_ascii_lowercase(),
+            Some(complement) => complement,
+            None => c,
+        })
+        .collect()
+}
+
+/// Write `path` to stdout with the sequence column reverse complemented
+pub fn revcomp_file(path: &Path, options: &RevcompOptions) -> Result<(), Box<dyn Error>> {
+    info!("Reverse complementing {:?} using the {} alphabet", path, options.alphabet.name());
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut decoder = DecodingReader::new(file, &label)?;
+    let written = match FileFormat::Auto.resolve(decoder.sample()?) {
+        FileFormat::Csv => revcomp_csv(decoder, &label, options),
+        FileFormat::Fasta => revcomp_fasta(decoder, &label, options.alphabet),
+        format => Err(CheckError::Usage(format!("{}: {:?} files can't be reverse complemented, only csv and FASTA",
+                                                label, format)).into()),
+    };
+    match written {
+        Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) => Ok(()),
+        other => other,
+    }
+}
+
+fn revcomp_csv<R: io::Read>(mut decoder: DecodingReader<R>, label: &str, options: &RevcompOptions)
+    -> Result<(), Box<dyn Error>> {
+    let sample = decoder.sample()?;
+    let delimiter = options.dialect.delimiter.unwrap_or_else(|| options.csv_locale.delimiter(sample));
+    let mut reader = options.dialect.reader_builder(options.csv_locale, sample)
+        .has_headers(options.header)
+        .from_reader(decoder);
+    let headers = match options.header {
+        true => Some(reader.headers()?.clone()),
+        false => None,
+    };
+    let seq_column = columns::position(options.seq_column, 1, "--seq-column", headers.as_ref(), label)?;
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .quote(options.dialect.quote_char)
+        .flexible(true)
+        .from_writer(io::stdout().lock());
+    if let Some(headers) = &headers {
+        writer.write_record(headers)?;
+    }
+    for result in reader.records() {
+        let record = result?;
+        let row: Vec<String> = record.iter()
+            .enumerate()
+            .map(|(i, field)| match i == seq_column {
+                true => reverse_complement(field.trim(), options.alphabet),
+                false => field.to_string(),
+            })
+            .collect();
+        writer.write_record(&row)?;
+    }
+    Ok(writer.flush()?)
+}
+
+fn revcomp_fasta<R: io::Read>(decoder: DecodingReader<R>, label: &str, alphabet: &Alphabet)
+    -> Result<(), Box<dyn Error>> {
+    let records = fasta::read_records(BufReader::new(decoder), FileFormat::Fasta, label)?;
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for record in records {
+        writeln!(out, ">{}\n{}", &record[0], reverse_complement(&record[1], alphabet))?;
+    }
+    Ok(out.flush()?)
+}
@@ -0,0 +1,42 @@
+/*
+    U/T normalization
+        - RNA sequences and the U of 2'-modified ASO notation are read as
+          T with a DNA alphabet, and T as U with an RNA one, so DNA and RNA
+          notations of the same ASO compare equal
+        - applied to inputs, library entries, parents and scan targets as
+          they are read, before validation, composition and distances
+        - lowercase stays lowercase, for --softmask
+    --no-rna-normalization keeps sequences as written.
+*/
+use std::borrow::Cow;
+use crate::Cli;
+
+/// The (from, to) base of the normalization for the alphabet, if any
+pub fn direction(cli: &Cli) -> Option<(char, char)> {
+    if cli.no_rna_normalization {
+        return None
+    }
+    match (cli.alphabet.contains('T'), cli.alphabet.contains('U')) {
+        (true, false) => Some(('U', 'T')),
+        (false, true) => Some(('T', 'U')),
+        _ => None,
+    }
+}
+
+/// `seq` with U read as T (or T as U for an RNA alphabet)
+pub fn normalized<'a>(seq: &'a str, cli: &Cli) -> Cow<'a, str> {
+    let Some((from, to)) = direction(cli) else {
+        return Cow::Borrowed(seq)
+    };
+    let lower = (from.to_ascii_lowercase(), to.to_ascii_lowercase());
+    if !seq.contains([from, lower.0]) {
+        return Cow::Borrowed(seq)
+    }
+    Cow::Owned(seq.chars()
+        .map(|c| match c {
+            c if c == from => to,
+            c if c == lower.0 => lower.1,
+            c => c,
+        })
+        .collect())
+}
@@ -0,0 +1,225 @@
+/*
+    K-mismatch substring search
+        - targets of any length (transcripts, genomic regions) from a csv
+          file, name in column1, sequence in column2
+        - every position where an input, or its reverse complement, matches
+          with at most --scan-mismatches mismatches
+        - pigeonhole (PEX) search: split the input into K+1 pieces, at least
+          one of which must match exactly, then verify around each hit
+        - start/end on both sequences, and optionally the sites as BED or
+          GFF3 (--sites-file) for genome browsers
+        - library coverage of the targets (--coverage-file), see the
+          coverage module
+    Answers "can my ASO bind anywhere in these sequences?" without going
+    through the pairwise distance path.
+*/
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use log::info;
+use crate::coverage::{write_coverage, TargetSites};
+use crate::encoding::DecodingReader;
+use crate::error::CheckError;
+use crate::{dialect, load_inputs, rna, AsoProfile, Cli, RunStats};
+
+struct Target {
+    name: String,
+    seq: Vec<u8>,
+}
+
+/// A place an input can bind, positions 1-based on the target as given
+struct Site {
+    strand: char,
+    position: usize,
+    mismatches: usize,
+}
+
+/// A site with everything needed to report it, coordinates 1-based inclusive
+struct Hit<'a> {
+    input: &'a AsoProfile,
+    target: &'a Target,
+    site: Site,
+    target_end: usize,
+    input_start: usize,
+    input_end: usize,
+}
+
+enum SitesFormat {
+    Bed,
+    Gff,
+}
+
+impl SitesFormat {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase()) {
+            Some(ext) if ext == "bed" => Ok(SitesFormat::Bed),
+            Some(ext) if ext == "gff" || ext == "gff3" => Ok(SitesFormat::Gff),
+            _ => Err(format!("Sites file {:?} must end in .bed or .gff", path)),
+        }
+    }
+}
+
+fn read_targets(path: &Path, cli: &Cli) -> Result<Vec<Target>, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open scan file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut decoder = DecodingReader::new(file, &label)?;
+    let mut reader = dialect::reader_builder(cli, decoder.sample()?)
+        .has_headers(cli.scan_header_status)
+        .from_reader(decoder);
+    let mut targets = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let name = record.get(0).unwrap_or("").trim().to_string();
+        let seq = rna::normalized(record.get(1).unwrap_or("").trim(), cli).to_ascii_uppercase().into_bytes();
+        targets.push(Target { name, seq });
+    }
+    info!("Read {} scan targets from {:?}", targets.len(), path);
+    Ok(targets)
+}
+
+fn mismatches(pattern: &[u8], window: &[u8], max: usize) -> Option<usize> {
+    let mut count = 0;
+    for (a, b) in pattern.iter().zip(window) {
+        if a != b {
+            count += 1;
+            if count > max {
+                return None
+            }
+        }
+    }
+    Some(count)
+}
+
+/// Start of every window of `text` within `max` mismatches of `pattern`
+fn k_mismatch_search(pattern: &[u8], text: &[u8], max: usize) -> Vec<(usize, usize)> {
+    if pattern.is_empty() || pattern.len() > text.len() {
+        return vec![]
+    }
+    let last_start = text.len() - pattern.len();
+    let pieces = max + 1;
+    // too short to split into K+1 pieces: check every window
+    if pattern.len() < pieces {
+        return (0..=last_start)
+            .filter_map(|start| mismatches(pattern, &text[start..], max).map(|m| (start, m)))
+            .collect()
+    }
+    let mut candidates = BTreeSet::new();
+    for piece in 0..pieces {
+        let from = piece * pattern.len() / pieces;
+        let to = (piece + 1) * pattern.len() / pieces;
+        let needle = &pattern[from..to];
+        for (offset, window) in text.windows(needle.len()).enumerate() {
+            if window == needle && offset >= from && offset - from <= last_start {
+                candidates.insert(offset - from);
+            }
+        }
+    }
+    candidates.into_iter()
+        .filter_map(|start| mismatches(pattern, &text[start..], max).map(|m| (start, m)))
+        .collect()
+}
+
+fn scan_sites(seq: &[u8], rc_seq: &[u8], target: &[u8], max: usize) -> Vec<Site> {
+    let mut sites: Vec<Site> = k_mismatch_search(seq, target, max).into_iter()
+        .map(|(start, mismatches)| Site { strand: '+', position: start + 1, mismatches })
+        .collect();
+    if rc_seq != seq {
+        sites.extend(k_mismatch_search(rc_seq, target, max).into_iter()
+            .map(|(start, mismatches)| Site { strand: '-', position: start + 1, mismatches }));
+    }
+    sites.sort_by_key(|site| (site.mismatches, site.position));
+    sites
+}
+
+fn find_hits<'a>(inputs: &'a [AsoProfile], targets: &'a [Target], cli: &Cli) -> Vec<Hit<'a>> {
+    let mut hits = Vec::new();
+    for input in inputs {
+        let seq = input.seq.to_ascii_uppercase();
+        let rc_seq = cli.alphabet.reverse_complement(&seq);
+        for target in targets {
+            for site in scan_sites(seq.as_bytes(), rc_seq.as_bytes(), &target.seq, cli.scan_mismatches) {
+                hits.push(Hit {
+                    input,
+                    target,
+                    target_end: site.position + seq.len() - 1,
+                    input_start: 1,
+                    input_end: seq.len(),
+                    site,
+                })
+            }
+        }
+    }
+    hits
+}
+
+fn print_sites(out: &mut dyn Write, hits: &[Hit], cli: &Cli) -> io::Result<()> {
+    if !cli.raw {
+        writeln!(out, "{:<10}\t{:<20}\t{:<10}\tStrand\tStart\tEnd\tInput start\tInput end\tMismatches\tTarget site",
+                 "Input ASO", "Seq", "Target")?;
+    }
+    for hit in hits {
+        let window = &hit.target.seq[hit.site.position - 1..hit.target_end];
+        writeln!(out, "{:<10}\t{:<20}\t{:<10}\t{}\t{}\t{}\t{}\t{}\t{}\t{}", hit.input.name,
+                 hit.input.seq, hit.target.name, hit.site.strand, hit.site.position, hit.target_end,
+                 hit.input_start, hit.input_end, hit.site.mismatches,
+                 String::from_utf8_lossy(window))?;
+    }
+    Ok(())
+}
+
+/// BED (0-based, half-open) or GFF3 (1-based, inclusive) track of the sites
+fn write_sites(path: &Path, hits: &[Hit]) -> Result<(), Box<dyn Error>> {
+    let format = SitesFormat::from_path(path)?;
+    info!("Writing {} sites to {:?}", hits.len(), path);
+    let mut writer = BufWriter::new(File::create(path)?);
+    if let SitesFormat::Gff = format {
+        writeln!(writer, "##gff-version 3")?;
+    }
+    for (i, hit) in hits.iter().enumerate() {
+        match format {
+            SitesFormat::Bed => writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}", hit.target.name,
+                                         hit.site.position - 1, hit.target_end, hit.input.name,
+                                         hit.site.mismatches, hit.site.strand)?,
+            SitesFormat::Gff => writeln!(writer, "{}\t{}\tnucleotide_match\t{}\t{}\t{}\t{}\t.\tID=site{};Name={};Mismatches={}",
+                                         hit.target.name, env!("CARGO_PKG_NAME"), hit.site.position,
+                                         hit.target_end, hit.site.mismatches, hit.site.strand,
+                                         i + 1, hit.input.name, hit.site.mismatches)?,
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Scan every input against the targets of `path` and write the sites to `out`
+pub fn scan(cli: &Cli, path: &Path, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let targets = read_targets(path, cli)?;
+    let (inputs, _) = load_inputs(cli, &mut RunStats::default())?;
+    info!("Scanning {} inputs with up to {} mismatches", inputs.len(), cli.scan_mismatches);
+    let hits = find_hits(&inputs, &targets, cli);
+    if let Some(sites_path) = &cli.sites_file {
+        write_sites(sites_path, &hits)?;
+    }
+    if let Some(coverage_path) = &cli.coverage_file {
+        let target_sites: Vec<TargetSites> = targets.iter()
+            .map(|target| TargetSites {
+                name: &target.name,
+                length: target.seq.len(),
+                sites: hits.iter()
+                    .filter(|hit| std::ptr::eq(hit.target, target))
+                    .map(|hit| (hit.site.position, hit.target_end))
+                    .collect(),
+            })
+            .collect();
+        write_coverage(coverage_path, &target_sites)?;
+    }
+    // a closed pipe (e.g. `| head`) is not an error
+    if let Err(e) = print_sites(out, &hits, cli).and_then(|_| out.flush()) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e.into())
+        }
+    }
+    Ok(())
+}
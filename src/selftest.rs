@@ -0,0 +1,104 @@
+/*
+    Self-test of the distance metrics (selftest subcommand)
+        - every metric against a table of sequence pairs with known values
+        - edge cases: empty sequences, different lengths, ambiguity codes
+          (compared as plain characters: N differs from every base)
+        - a pass/fail line per case and a summary, exit code 1 on failure
+    Meant for checking a build on a new platform and as validation record.
+*/
+use std::io::{self, Write};
+use crate::{Dist, MetricOptions};
+
+/// Exit code when a metric gives an unexpected value
+const SELFTEST_FAILED_EXIT_CODE: i32 = 1;
+
+/// Metric, sequences and expected distance; None when the metric
+/// doesn't apply to the pair. Metrics use the default options: alignment
+/// scores 1/-1/-2, q-grams, cosine and D2 k-mers of 3 bases
+const CASES: [(Dist, &str, &str, Option<f32>); 55] = [
+    (Dist::Hamming, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Hamming, "ACGTACGT", "ACGAACGT", Some(1.0)),
+    (Dist::Hamming, "ACGTACGT", "TGCATGCA", Some(8.0)),
+    (Dist::Hamming, "", "", Some(0.0)),
+    (Dist::Hamming, "ACGT", "ACG", None),
+    (Dist::Hamming, "ACGN", "ACGA", Some(1.0)),
+    (Dist::Hamming, "ACGTRY", "ACGTRY", Some(0.0)),
+    (Dist::Levenshtein, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Levenshtein, "ACGTACGT", "ACGAACGT", Some(1.0)),
+    (Dist::Levenshtein, "ACGTACGT", "CGTACGTA", Some(2.0)),
+    (Dist::Levenshtein, "ACGT", "ACG", Some(1.0)),
+    (Dist::Levenshtein, "", "ACGT", Some(4.0)),
+    (Dist::Levenshtein, "", "", Some(0.0)),
+    (Dist::Levenshtein, "ACGTN", "ACGTA", Some(1.0)),
+    (Dist::Sift3, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Sift3, "ACGT", "ACCT", Some(1.0)),
+    (Dist::Sift3, "", "ACGT", Some(4.0)),
+    (Dist::Sift3, "", "", Some(0.0)),
+    (Dist::Damerau, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Damerau, "ACGTACGT", "CAGTACGT", Some(1.0)),
+    (Dist::Damerau, "ACGTACGT", "CAGTACTG", Some(2.0)),
+    (Dist::Damerau, "ACGT", "ACG", Some(1.0)),
+    (Dist::Damerau, "", "AC", Some(2.0)),
+    (Dist::Damerau, "ACGTW", "ACGWT", Some(1.0)),
+    (Dist::Needleman, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Needleman, "ACGTACGT", "ACGAACGT", Some(2.0)),
+    (Dist::Needleman, "ACGT", "ACG", Some(3.0)),
+    (Dist::Needleman, "", "AC", Some(6.0)),
+    (Dist::Needleman, "", "", Some(0.0)),
+    (Dist::SmithWaterman, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::SmithWaterman, "ACGT", "TTACGTTT", Some(0.0)),
+    (Dist::SmithWaterman, "ACGTACGT", "ACGAACGT", Some(2.0)),
+    (Dist::SmithWaterman, "AAAA", "CCCC", Some(4.0)),
+    (Dist::SmithWaterman, "", "AC", Some(0.0)),
+    (Dist::Qgram, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Qgram, "ACGTACGT", "CGTACGTA", Some(2.0)),
+    (Dist::Qgram, "ACGTACGT", "ACGAACGT", Some(6.0)),
+    (Dist::Qgram, "AC", "ACGT", Some(2.0)),
+    (Dist::Qgram, "", "", Some(0.0)),
+    (Dist::Cosine, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Cosine, "AAAA", "CCCC", Some(1.0)),
+    (Dist::Cosine, "AC", "ACGT", Some(1.0)),
+    (Dist::Cosine, "", "", Some(0.0)),
+    (Dist::D2, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::D2, "AAAA", "CCCC", Some(0.5)),
+    (Dist::D2, "AC", "ACGT", Some(0.5)),
+    (Dist::D2, "", "", Some(0.0)),
+    (Dist::D2s, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::D2s, "AAAA", "AAAA", Some(0.0)),
+    (Dist::D2s, "AAAA", "CCCC", Some(0.5)),
+    (Dist::D2s, "", "", Some(0.0)),
+    (Dist::Lcs, "ACGTACGT", "ACGTACGT", Some(0.0)),
+    (Dist::Lcs, "ACGTACGT", "CGTACGTA", Some(1.0)),
+    (Dist::Lcs, "ACGT", "AGT", Some(1.0)),
+    (Dist::Lcs, "", "AC", Some(2.0)),
+];
+
+fn show(value: Option<f32>) -> String {
+    value.map_or(String::from("n/a"), |value| value.to_string())
+}
+
+fn print_report(out: &mut dyn Write) -> io::Result<usize> {
+    let mut failed = 0;
+    for (metric, a, b, expected) in CASES {
+        let got = metric.distance(a, b, &MetricOptions::default()).ok();
+        let status = match got == expected {
+            true => "PASS",
+            false => {
+                failed += 1;
+                "FAIL"
+            }
+        };
+        writeln!(out, "{}\t{:?}\t{:?} vs {:?}\texpected {}, got {}", status, metric, a, b, show(expected),
+                 show(got))?;
+    }
+    writeln!(out, "{} of {} cases passed", CASES.len() - failed, CASES.len())?;
+    Ok(failed)
+}
+
+/// Run every case, print the report and return the exit code
+pub fn selftest() -> io::Result<i32> {
+    let mut out = io::stdout().lock();
+    let failed = print_report(&mut out)?;
+    out.flush()?;
+    Ok(if failed == 0 { 0 } else { SELFTEST_FAILED_EXIT_CODE })
+}
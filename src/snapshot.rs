@@ -0,0 +1,163 @@
+/*
+    Library snapshots (--export-library-snapshot, --library-snapshot)
+        - the library as parsed: every entry after cleanup, validation,
+          orientation and name sanitizing, with its group and source, and
+          the files it was read from with their hashes (for the manifest)
+        - loading one skips reading and validating the library files; the
+          profiles (composition, soft-masked positions) are rebuilt with
+          the --softmask of the run
+        - a versioned byte format, little-endian and length-prefixed, with
+          no platform-dependent layout: a snapshot written on one machine
+          loads on any other
+    A snapshot made with another alphabet is refused, the orientation and
+    U/T normalization of its entries depend on it.
+*/
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use log::info;
+use crate::error::CheckError;
+use crate::manifest::FileRecord;
+use crate::warnings::Warnings;
+use crate::{softmask, Cli, Library};
+
+const MAGIC: &[u8; 8] = b"ASOSNAP\0";
+const VERSION: u32 = 1;
+
+fn write_u32(out: &mut impl Write, value: usize) -> io::Result<()> {
+    let value = u32::try_from(value).map_err(|_| io::Error::other("value too large for a snapshot"))?;
+    out.write_all(&value.to_le_bytes())
+}
+
+fn write_str(out: &mut impl Write, text: &str) -> io::Result<()> {
+    write_u32(out, text.len())?;
+    out.write_all(text.as_bytes())
+}
+
+/// Write the parsed library to `path`
+pub fn write(library: &Library, path: &Path, cli: &Cli) -> Result<(), Box<dyn Error>> {
+    let file = File::create(path)
+        .map_err(|e| CheckError::Io(format!("Unable to create snapshot file {:?}: {}", path, e)))?;
+    let mut out = BufWriter::new(file);
+    out.write_all(MAGIC)?;
+    write_u32(&mut out, VERSION as usize)?;
+    write_str(&mut out, cli.alphabet.name())?;
+    write_u32(&mut out, library.files.len())?;
+    for record in &library.files {
+        write_str(&mut out, &record.path().display().to_string())?;
+        write_str(&mut out, record.sha256())?;
+    }
+    write_u32(&mut out, library.sources.len())?;
+    for source in &library.sources {
+        write_str(&mut out, source)?;
+    }
+    write_u32(&mut out, library.asos.len())?;
+    for aso in &library.asos {
+        // soft-masked bases as read, so --softmask can rebuild the profile
+        let seq: String = aso.seq.chars()
+            .enumerate()
+            .map(|(position, base)| match aso.softmasked.contains(&position) {
+                true => base.to_ascii_lowercase(),
+                false => base,
+            })
+            .collect();
+        write_str(&mut out, &aso.name)?;
+        write_str(&mut out, &seq)?;
+        write_str(&mut out, aso.group.as_deref().unwrap_or(""))?;
+        write_u32(&mut out, aso.source)?;
+    }
+    out.flush()?;
+    info!("Wrote a snapshot of {} library ASOs to {:?}", library.asos.len(), path);
+    Ok(())
+}
+
+struct SnapshotReader<R> {
+    inner: R,
+    label: String,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn bytes(&mut self, length: usize) -> Result<Vec<u8>, CheckError> {
+        // a corrupt length must not allocate more than the file holds
+        let mut buffer = Vec::new();
+        (&mut self.inner).take(length as u64).read_to_end(&mut buffer)
+            .map_err(|e| CheckError::Io(format!("Unable to read snapshot file {}: {}", self.label, e)))?;
+        if buffer.len() != length {
+            return Err(CheckError::Data(format!("{}: truncated library snapshot", self.label)))
+        }
+        Ok(buffer)
+    }
+
+    fn u32(&mut self) -> Result<usize, CheckError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn string(&mut self) -> Result<String, CheckError> {
+        let length = self.u32()?;
+        String::from_utf8(self.bytes(length)?)
+            .map_err(|_| CheckError::Data(format!("{}: invalid text in library snapshot", self.label)))
+    }
+}
+
+/// Load a library written by `write`, profiled with the options of the run
+pub fn read(path: &Path, cli: &Cli) -> Result<Library, Box<dyn Error>> {
+    let load_start = Instant::now();
+    let file = File::open(path)
+        .map_err(|e| CheckError::Io(format!("Unable to open snapshot file {:?}: {}", path, e)))?;
+    let label = path.display().to_string();
+    let mut reader = SnapshotReader { inner: BufReader::new(file), label: label.clone() };
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return Err(CheckError::Data(format!("{}: not a library snapshot", label)).into())
+    }
+    let version = reader.u32()?;
+    if version != VERSION as usize {
+        return Err(CheckError::Data(format!("{}: library snapshot version {}, expected {}", label, version,
+                                            VERSION)).into())
+    }
+    let alphabet = reader.string()?;
+    if alphabet != cli.alphabet.name() {
+        return Err(CheckError::Usage(format!("{}: library snapshot made with the {} alphabet, not {}", label,
+                                             alphabet, cli.alphabet.name())).into())
+    }
+    let mut files = Vec::new();
+    for _ in 0..reader.u32()? {
+        let path = PathBuf::from(reader.string()?);
+        files.push(FileRecord::new(path, reader.string()?));
+    }
+    let mut sources = Vec::new();
+    for _ in 0..reader.u32()? {
+        sources.push(reader.string()?);
+    }
+    let mut asos = Vec::new();
+    for _ in 0..reader.u32()? {
+        let name = reader.string()?;
+        let seq = reader.string()?;
+        let group = reader.string()?;
+        let source = reader.u32()?;
+        if source >= sources.len() {
+            return Err(CheckError::Data(format!("{}: library snapshot entry {} has no source", label, name)).into())
+        }
+        // without --softmask soft-masked bases are plain bases
+        let seq = match cli.softmask {
+            Some(_) => seq,
+            None => seq.to_uppercase(),
+        };
+        let mut profile = softmask::profile(name, seq, cli);
+        profile.group = Some(group).filter(|group| !group.is_empty());
+        profile.source = source;
+        asos.push(Arc::new(profile));
+    }
+    info!("Loaded {} library ASOs from snapshot {:?}", asos.len(), path);
+    Ok(Library {
+        files,
+        sources,
+        asos,
+        warnings: Warnings::default(),
+        renamed: vec![],
+        load_time: load_start.elapsed(),
+    })
+}
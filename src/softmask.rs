@@ -0,0 +1,97 @@
+/*
+    Soft-masked (lowercase) bases (--softmask)
+        - none: lowercase bases are read as uppercase, nothing else changes
+        - ignore: lowercase positions are left out of the composition and
+          never count as a difference, in the input or the library ASO
+        - downweight: differences at lowercase positions count
+          DOWNWEIGHT of a full one: the distance is the one ignoring them
+          plus DOWNWEIGHT of what they add to it
+    Without --softmask lowercase bases are uppercased as they are read,
+    see the validate module, and --strict rejects them.
+*/
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::alphabet::Alphabet;
+use crate::{revcomp, AsoProfile, Cli, Dist, MetricOptions};
+
+/// Weight of a difference at a soft-masked position, with downweight
+pub const DOWNWEIGHT: f32 = 0.5;
+
+#[derive(Debug, PartialEq, Copy, Clone, ValueEnum, Serialize)]
+pub enum Softmask {
+    None,
+    Ignore,
+    Downweight,
+}
+
+/// Profile of a sequence, uppercased with its lowercase positions recorded
+/// when soft-masking is on
+pub fn profile(name: String, seq: String, cli: &Cli) -> AsoProfile {
+    let Some(softmask) = cli.softmask else {
+        return AsoProfile::new(name, seq, &cli.alphabet)
+    };
+    let softmasked: BTreeSet<usize> = seq.chars()
+        .enumerate()
+        .filter(|(_, base)| base.is_lowercase())
+        .map(|(position, _)| position)
+        .collect();
+    let mut profile = AsoProfile::new(name, seq.to_uppercase(), &cli.alphabet);
+    if softmask == Softmask::Ignore {
+        let unmasked: String = profile.seq.chars()
+            .enumerate()
+            .filter(|(position, _)| !softmasked.contains(position))
+            .map(|(_, base)| base)
+            .collect();
+        profile.composition = cli.alphabet.composition(&unmasked);
+    }
+    if softmask != Softmask::None {
+        profile.softmasked = softmasked;
+    }
+    profile
+}
+
+/// Reverse complement, keeping lowercase bases lowercase when soft-masking
+pub fn reverse_complement(seq: &str, cli: &Cli) -> String {
+    match cli.softmask {
+        Some(_) => revcomp::reverse_complement(seq, &cli.alphabet),
+        None => cli.alphabet.reverse_complement(seq),
+    }
+}
+
+/// `other` with the input's bases at the soft-masked positions of either,
+/// so they can't differ
+pub fn unmasked<'a>(input: &AsoProfile, library: &AsoProfile, other: &'a str) -> Cow<'a, str> {
+    if input.softmasked.is_empty() && library.softmasked.is_empty() {
+        return Cow::Borrowed(other)
+    }
+    Cow::Owned(other.chars()
+        .zip(input.seq.chars().map(Some).chain(std::iter::repeat(None)))
+        .enumerate()
+        .map(|(position, (base, input_base))| match input_base {
+            Some(input_base) if input.softmasked.contains(&position)
+                || library.softmasked.contains(&position) => input_base,
+            _ => base,
+        })
+        .collect())
+}
+
+/// Distance of the input to a library sequence, `ignoring` being that
+/// sequence with the soft-masked positions made equal
+pub fn distance(metric: Dist, input: &str, library: &str, ignoring: &str, options: &MetricOptions,
+                softmask: Option<Softmask>) -> Result<f32, String> {
+    match softmask {
+        Some(Softmask::Downweight) if library != ignoring => {
+            let full = metric.distance(input, library, options)?;
+            let ignored = metric.distance(input, ignoring, options)?;
+            Ok(ignored + DOWNWEIGHT * (full - ignored))
+        }
+        _ => metric.distance(input, ignoring, options),
+    }
+}
+
+/// Whether --strict should let a lowercase base of the alphabet through
+pub fn allows(c: char, alphabet: &Alphabet, softmask: Option<Softmask>) -> bool {
+    softmask.is_some() && alphabet.contains(c.to_ascii_uppercase())
+}
@@ -0,0 +1,59 @@
+/*
+    Per-library-source statistics
+        - with several --library-aso-file, each file is a source
+        - per source: entries, duplicates (sequence already in an earlier
+          entry of the library, from any source), matches reported against
+          its entries and inputs with at least one of those
+        - printed after the warnings when more than one source is loaded,
+          and recorded in the manifest counters
+    Shows which collection is producing the conflicts.
+*/
+use std::collections::HashSet;
+use serde::Serialize;
+use crate::{AsoProfile, Library};
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SourceStats {
+    name: String,
+    entries: usize,
+    duplicates: usize,
+    matches: usize,
+    matched_inputs: usize,
+}
+
+/// Statistics of every source of the library, in load order
+pub fn source_stats(library: &Library, inputs: &[AsoProfile]) -> Vec<SourceStats> {
+    let mut stats: Vec<SourceStats> = library.sources.iter()
+        .map(|name| SourceStats { name: name.clone(), ..SourceStats::default() })
+        .collect();
+    let mut seen = HashSet::new();
+    for aso in &library.asos {
+        stats[aso.source].entries += 1;
+        if !seen.insert(aso.seq.as_str()) {
+            stats[aso.source].duplicates += 1;
+        }
+    }
+    for input in inputs {
+        let mut matched = vec![false; stats.len()];
+        for hit in &input.aso_names {
+            stats[hit.aso.source].matches += 1;
+            matched[hit.aso.source] = true;
+        }
+        for (source, matched) in stats.iter_mut().zip(matched) {
+            source.matched_inputs += matched as usize;
+        }
+    }
+    stats
+}
+
+/// Print the per-source table to stderr, next to the warnings summary
+pub fn print_summary(stats: &[SourceStats]) {
+    if stats.len() < 2 {
+        return
+    }
+    eprintln!("Library sources:");
+    for source in stats {
+        eprintln!("  {}: {} entries, {} duplicates, {} matches, {} inputs matched", source.name,
+                  source.entries, source.duplicates, source.matches, source.matched_inputs);
+    }
+}
@@ -0,0 +1,247 @@
+/*
+    SQLite libraries (--library sqlite://registry.db?table=asos)
+        - a library given as sqlite://path?table=... is read from that
+          table of the database, opened read-only
+        - name and sequence come from the columns named name and seq, or
+          those set with &name=...&seq=...; the other columns are kept,
+          after them, for --group-by and --library-orientation mixed
+        - NULL is an empty cell, numbers are read as written
+        - the line of a row in messages is its rowid
+        - with --prefilter length or composition, the lengths (and counts
+          of each symbol) of the inputs are pushed into the query as plain
+          SQL, which an index on length(seq) can serve: only the rows that
+          can pass the prefilter are read, validated and profiled. Rows
+          that aren't letters only are read and keyed as the library is
+          profiled. Rows left out are not counted and their warnings are
+          not reported; a run that needs every row (--focus, --pairs,
+          --window, --previous-manifest, --export-library-snapshot, batch
+          jobs) reads them all
+    The manifest records the hash of the database file.
+*/
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use csv::{Position, StringRecord};
+use log::info;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use crate::error::CheckError;
+use crate::manifest::HashingReader;
+use crate::softmask::Softmask;
+use crate::{clean, rna, softmask, validate, AsoProfile, Cli, LibraryOrientation, Prefilter};
+
+const SCHEME: &str = "sqlite://";
+
+/// A table of a database, from a sqlite:// library
+pub struct Location {
+    path: String,
+    table: String,
+    name_column: String,
+    seq_column: String,
+}
+
+impl Location {
+    /// The location of a sqlite:// library, None for a library file
+    pub fn parse(library: &Path) -> Result<Option<Location>, CheckError> {
+        let Some(url) = library.to_str().and_then(|library| library.strip_prefix(SCHEME)) else {
+            return Ok(None)
+        };
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let mut location = Location {
+            path: path.to_string(),
+            table: String::new(),
+            name_column: String::from("name"),
+            seq_column: String::from("seq"),
+        };
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            match parameter.split_once('=') {
+                Some(("table", table)) => location.table = table.to_string(),
+                Some(("name", column)) => location.name_column = column.to_string(),
+                Some(("seq", column)) => location.seq_column = column.to_string(),
+                _ => return Err(CheckError::Usage(format!("{}: unknown parameter {:?}, expected table, name or seq",
+                                                          library.display(), parameter))),
+            }
+        }
+        if location.path.is_empty() {
+            return Err(CheckError::Usage(format!("{}: no database path", library.display())))
+        }
+        Ok(Some(location))
+    }
+}
+
+/// The length, and composition for --prefilter composition, of every
+/// input: the library rows the prefilter can pass
+pub struct PrefilterKeys {
+    keys: HashSet<(usize, Option<Vec<usize>>)>,
+    composition: bool,
+}
+
+impl PrefilterKeys {
+    /// The keys of the inputs of the run, already read
+    pub fn new(inputs: &[AsoProfile], cli: &Cli) -> PrefilterKeys {
+        let composition = cli.prefilter == Prefilter::Composition;
+        let keys = inputs.iter()
+            .map(|input| (input.aso_len, composition.then(|| input.composition.clone())))
+            .collect();
+        PrefilterKeys { keys, composition }
+    }
+
+    /// SQL condition on `column` true for every row that can pass: rows of
+    /// letters only must have the length, and count of each symbol, of
+    /// some input; other rows, which cleaning may shorten, are all read
+    fn condition(&self, column: &str, cli: &Cli) -> String {
+        let column = quoted(column);
+        let mut lengths: Vec<usize> = self.keys.iter().map(|(length, _)| *length).collect();
+        lengths.sort();
+        lengths.dedup();
+        let lengths = lengths.iter().map(usize::to_string).collect::<Vec<_>>().join(", ");
+        let mut bounds = vec![format!("length({}) IN ({})", column, lengths)];
+        // soft-masked bases are left out of the composition of the key
+        let symbols = match self.composition && cli.softmask != Some(Softmask::Ignore) {
+            true => cli.alphabet.symbols(),
+            false => &[],
+        };
+        // --strict keeps lowercase bases, which no symbol counts, unless soft-masking
+        let seq = match cli.strict && cli.softmask.is_none() {
+            true => column.clone(),
+            false => format!("upper({})", column),
+        };
+        for (i, &symbol) in symbols.iter().enumerate() {
+            let counts = self.keys.iter().filter_map(|(_, composition)| composition.as_ref().map(|c| c[i]));
+            let (Some(min), Some(max)) = (counts.clone().min(), counts.max()) else { continue };
+            let letters = stored_letters(symbol, cli);
+            if letters.is_empty() {
+                continue
+            }
+            let removed = letters.iter()
+                .fold(seq.clone(), |seq, letter| format!("replace({}, '{}', '')", seq, letter));
+            bounds.push(format!("length({}) - length({}) BETWEEN {} AND {}", column, removed, min, max));
+        }
+        format!("{} GLOB '*[^A-Za-z]*' OR ({})", column, bounds.join(" AND "))
+    }
+}
+
+/// Whether the library is read only for the rows the prefilter can pass:
+/// a sqlite:// library with --prefilter length or composition, in a run
+/// that doesn't need every row
+pub fn reads_by_prefilter(cli: &Cli) -> bool {
+    matches!(cli.prefilter, Prefilter::Composition | Prefilter::Length)
+        && cli.library_aso_file.iter().any(|library| library.to_str().is_some_and(|library| library.starts_with(SCHEME)))
+        && cli.library_orientation != LibraryOrientation::Mixed && cli.focus.is_none() && cli.pairs.is_none()
+        && cli.previous_manifest.is_none() && cli.export_library_snapshot.is_none() && cli.window.is_none()
+}
+
+/// Uppercase letters of a stored sequence of letters counted as `symbol`
+/// in the key, after RNA normalization and, for --library-orientation
+/// sense, the reverse complement
+fn stored_letters(symbol: char, cli: &Cli) -> Vec<char> {
+    let symbol = match cli.library_orientation {
+        LibraryOrientation::Sense => cli.alphabet.complement(symbol).unwrap_or(symbol),
+        _ => symbol,
+    };
+    let mut letters = vec![symbol];
+    if let Some((from, _)) = rna::direction(cli).filter(|&(_, to)| to == symbol) {
+        letters.push(from);
+    }
+    letters.retain(char::is_ascii_uppercase);
+    letters
+}
+
+/// Prefilter key of a library sequence as stored, profiled the way
+/// read_library profiles it
+fn key(seq: &str, composition: bool, cli: &Cli) -> (usize, Option<Vec<usize>>) {
+    let (seq, _) = clean::clean_field(seq);
+    let seq = validate::normalized(&seq, cli);
+    let seq = match cli.library_orientation {
+        LibraryOrientation::Sense => softmask::reverse_complement(&seq, cli),
+        _ => seq,
+    };
+    let profile = softmask::profile(String::new(), seq, cli);
+    (profile.aso_len, composition.then_some(profile.composition))
+}
+
+fn quoted(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn cell(value: ValueRef, column: &str, label: &str, line: u64) -> Result<String, CheckError> {
+    Ok(match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(value) => value.to_string(),
+        ValueRef::Real(value) => value.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+        ValueRef::Blob(_) => return Err(CheckError::Data(format!("{}:{}: binary data in column {}", label, line,
+                                                                 column))),
+    })
+}
+
+/// Header (the column names, name and sequence first) and rows of the
+/// table, only those that can pass the prefilter when `keys` are given,
+/// with the SHA-256 of the database file
+pub fn read_records(location: &Location, label: &str, keys: Option<&PrefilterKeys>, cli: &Cli)
+    -> Result<(StringRecord, Vec<StringRecord>, String), Box<dyn Error>> {
+    let database = File::open(&location.path)
+        .map_err(|e| CheckError::Io(format!("Unable to open library database {:?}: {}", location.path, e)))?;
+    let mut hashing = HashingReader::new(database);
+    io::copy(&mut hashing, &mut io::sink())?;
+    let connection = Connection::open_with_flags(&location.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| CheckError::Io(format!("Unable to open library database {:?}: {}", location.path, e)))?;
+    let data_error = |e: rusqlite::Error| CheckError::Data(format!("{}: {}", label, e));
+    let tables: Vec<String> = connection
+        .prepare("SELECT name FROM sqlite_schema WHERE type IN ('table', 'view') ORDER BY name")
+        .and_then(|mut statement| statement.query_map([], |row| row.get(0))?.collect())
+        .map_err(data_error)?;
+    if !tables.contains(&location.table) {
+        return Err(CheckError::Usage(format!("{}: no table named {:?}, set ?table= to one of: {}", label,
+                                             location.table, tables.join(", "))).into())
+    }
+    let mut columns: Vec<String> = connection.prepare("SELECT name FROM pragma_table_info(?1)")
+        .and_then(|mut statement| statement.query_map([&location.table], |row| row.get(0))?.collect())
+        .map_err(data_error)?;
+    for column in [&location.name_column, &location.seq_column] {
+        if !columns.contains(column) {
+            return Err(CheckError::Data(format!("{}: no column named {:?} in table {}, the columns are {}", label,
+                                                column, location.table, columns.join(", "))).into())
+        }
+    }
+    columns.retain(|column| *column != location.name_column && *column != location.seq_column);
+    columns.splice(0..0, [location.name_column.clone(), location.seq_column.clone()]);
+    let filter = match keys {
+        Some(keys) => {
+            info!("{}: reading only the rows that can pass the {:?} prefilter", label, cli.prefilter);
+            format!(" WHERE {}", keys.condition(&location.seq_column, cli))
+        }
+        None => String::new(),
+    };
+    let selected = columns.iter().map(|column| quoted(column)).collect::<Vec<_>>().join(", ");
+    // views and WITHOUT ROWID tables have no rowid, their rows are numbered as read
+    let mut statement = connection
+        .prepare(&format!("SELECT rowid, {} FROM {}{}", selected, quoted(&location.table), filter))
+        .or_else(|_| connection.prepare(&format!("SELECT NULL, {} FROM {}{}", selected, quoted(&location.table),
+                                                 filter)))
+        .map_err(data_error)?;
+    let mut rows = statement.query([]).map_err(data_error)?;
+    let mut records = Vec::new();
+    while let Some(row) = rows.next().map_err(data_error)? {
+        let line = match row.get_ref(0).map_err(data_error)? {
+            ValueRef::Integer(rowid) => rowid as u64,
+            _ => records.len() as u64 + 1,
+        };
+        let mut record = StringRecord::new();
+        for (i, column) in columns.iter().enumerate() {
+            record.push_field(&cell(row.get_ref(i + 1).map_err(data_error)?, column, label, line)?);
+        }
+        // the condition only bounds the keys, the exact ones are checked here
+        if keys.is_some_and(|keys| !keys.keys.contains(&key(&record[1], keys.composition, cli))) {
+            continue
+        }
+        let mut position = Position::new();
+        position.set_line(line);
+        record.set_position(Some(position));
+        records.push(record);
+    }
+    info!("{}: read {} rows from table {}", label, records.len(), location.table);
+    Ok((columns.into_iter().collect(), records, hashing.hex_digest()))
+}
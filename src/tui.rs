@@ -0,0 +1,278 @@
+/*
+    Interactive results browser
+        - inputs on the left, matches of the selected input on the right
+        - alignment view of the selected match
+        - keys: up/down (j/k) move, tab switch pane, s sort, / filter names,
+          +/- adjust max distance, e export current view (to --tui-export),
+          q quit
+*/
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::terminal;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+use crate::error::CheckError;
+use crate::{AsoMatch, AsoProfile, Dist};
+
+#[derive(PartialEq)]
+enum Focus {
+    Inputs,
+    Matches,
+}
+
+#[derive(PartialEq)]
+enum SortKey {
+    Distance,
+    Name,
+}
+
+struct App<'a> {
+    inputs: &'a [AsoProfile],
+    list_by: Dist,
+    precision: Option<usize>,
+    export_path: Option<&'a Path>,
+    input_state: ListState,
+    match_state: TableState,
+    focus: Focus,
+    sort: SortKey,
+    max_distance: Option<f32>,
+    name_filter: String,
+    editing_filter: bool,
+    status: String,
+}
+
+impl<'a> App<'a> {
+    fn new(inputs: &'a [AsoProfile], list_by: Dist, precision: Option<usize>, export_path: Option<&'a Path>) -> Self {
+        let mut input_state = ListState::default();
+        if !inputs.is_empty() {
+            input_state.select(Some(0));
+        }
+        App {
+            inputs,
+            list_by,
+            precision,
+            export_path,
+            input_state,
+            match_state: TableState::default(),
+            focus: Focus::Inputs,
+            sort: SortKey::Distance,
+            max_distance: None,
+            name_filter: String::new(),
+            editing_filter: false,
+            status: String::from("q quit | tab switch | s sort | / filter | +/- max distance | e export"),
+        }
+    }
+
+    fn selected_input(&self) -> Option<&'a AsoProfile> {
+        self.input_state.selected().and_then(|i| self.inputs.get(i))
+    }
+
+    /// Matches of the selected input after filtering and sorting
+    fn visible_matches(&self) -> Vec<&'a AsoMatch> {
+        let Some(input) = self.selected_input() else {
+            return vec![]
+        };
+        let mut hits: Vec<&AsoMatch> = input.aso_names.iter()
+            .filter(|hit| self.max_distance.is_none_or(|max| hit.distance <= max))
+            .filter(|hit| hit.aso.name.contains(&self.name_filter))
+            .collect();
+        if self.sort == SortKey::Name {
+            hits.sort_by(|a, b| a.aso.name.cmp(&b.aso.name));
+        }
+        hits
+    }
+
+    fn move_selection(&mut self, step: isize) {
+        let (len, selected) = match self.focus {
+            Focus::Inputs => (self.inputs.len(), self.input_state.selected()),
+            Focus::Matches => (self.visible_matches().len(), self.match_state.selected()),
+        };
+        if len == 0 {
+            return
+        }
+        let next = selected
+            .map_or(0, |i| (i as isize + step).clamp(0, len as isize - 1) as usize);
+        match self.focus {
+            Focus::Inputs => {
+                self.input_state.select(Some(next));
+                self.match_state.select(None);
+            }
+            Focus::Matches => self.match_state.select(Some(next)),
+        }
+    }
+
+    fn adjust_max_distance(&mut self, step: f32) {
+        let current = self.max_distance.unwrap_or_else(|| {
+            self.selected_input()
+                .and_then(|input| input.aso_names.iter().map(|hit| hit.distance).reduce(f32::max))
+                .unwrap_or(0.0)
+        });
+        self.max_distance = Some((current + step).max(0.0));
+        self.match_state.select(None);
+    }
+
+    fn export(&mut self) {
+        let Some(path) = self.export_path else {
+            self.status = String::from("Export needs a file, start with --tui-export PATH");
+            return
+        };
+        self.status = match self.write_export(path) {
+            Ok(n) => format!("Exported {} matches to {}", n, path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        };
+    }
+
+    fn write_export(&self, path: &Path) -> Result<usize, Box<dyn Error>> {
+        let Some(input) = self.selected_input() else {
+            return Ok(0)
+        };
+        let hits = self.visible_matches();
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "Input ASO\tSeq\tMatching ASO\tSeq\tDistance")?;
+        for hit in &hits {
+            writeln!(writer, "{}\t{}\t{}\t{}\t{}", input.name, input.seq, hit.aso.name,
+                     hit.aso.seq, self.list_by.primary().format(hit.distance, self.precision))?;
+        }
+        writer.flush()?;
+        Ok(hits.len())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+            .areas(frame.area());
+        let [left, right] = Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .areas(main);
+        let [table_area, alignment_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(6)])
+            .areas(right);
+
+        let items: Vec<ListItem> = self.inputs.iter()
+            .map(|aso| ListItem::new(format!("{} ({})", aso.name, aso.aso_names.len())))
+            .collect();
+        let inputs = List::new(items)
+            .block(pane_block("Inputs", self.focus == Focus::Inputs))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(inputs, left, &mut self.input_state);
+
+        let hits = self.visible_matches();
+        let rows: Vec<Row> = hits.iter()
+            .map(|hit| Row::new(vec![hit.aso.name.clone(), hit.aso.seq.clone(),
+                                     self.list_by.primary().format(hit.distance, self.precision)]))
+            .collect();
+        let sort = match self.sort {
+            SortKey::Distance => "distance",
+            SortKey::Name => "name",
+        };
+        let limit = self.max_distance.map_or(String::from("-"), |max| max.to_string());
+        let title = format!("Matches [sort: {}, max: {}, filter: {}{}]", sort, limit,
+                            self.name_filter, if self.editing_filter { "_" } else { "" });
+        let header = Row::new(vec![String::from("Matching ASO"), String::from("Seq"),
+                                   format!("{:?}", self.list_by.primary())]).bold();
+        let table = Table::new(rows, [Constraint::Percentage(30), Constraint::Percentage(50),
+                                      Constraint::Percentage(20)])
+            .header(header)
+            .block(pane_block(&title, self.focus == Focus::Matches))
+            .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table, table_area, &mut self.match_state);
+
+        let alignment = match (self.selected_input(), self.match_state.selected().and_then(|i| hits.get(i))) {
+            (Some(input), Some(hit)) => alignment_lines(input, hit, self.precision),
+            _ => vec![Line::from("Select a match to see the alignment")],
+        };
+        frame.render_widget(Paragraph::new(alignment).block(Block::bordered().title("Alignment")),
+                            alignment_area);
+        frame.render_widget(Paragraph::new(self.status.as_str()), status);
+    }
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'static> {
+    let block = Block::bordered().title(title.to_string());
+    if focused {
+        block.border_style(Style::new().yellow())
+    } else {
+        block
+    }
+}
+
+/// Position-by-position view of the input over the match
+fn alignment_lines(input: &AsoProfile, hit: &AsoMatch, precision: Option<usize>) -> Vec<Line<'static>> {
+    let bars: String = input.seq.chars()
+        .zip(hit.aso.seq.chars())
+        .map(|(a, b)| if a == b { '|' } else { ' ' })
+        .collect();
+    let scores = hit.scores.iter()
+        .map(|(metric, score)| format!("{:?}: {}", metric, metric.format(*score, precision)))
+        .collect::<Vec<_>>()
+        .join("  ");
+    vec![
+        Line::from(format!("{:<12} 5' {} 3'", input.name, input.seq)),
+        Line::from(format!("{:<12}    {}", "", bars)),
+        Line::from(format!("{:<12} 5' {} 3'", hit.aso.name, hit.aso.seq)),
+        Line::from(scores),
+    ]
+}
+
+pub fn browse(inputs: &[AsoProfile], list_by: Dist, precision: Option<usize>,
+              export_path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(CheckError::Usage(String::from("--tui needs an interactive terminal")).into())
+    }
+    let mut terminal = ratatui::try_init().map_err(|e| {
+        let _ = terminal::disable_raw_mode();
+        CheckError::Usage(format!("--tui needs an interactive terminal: {}", e))
+    })?;
+    let mut app = App::new(inputs, list_by, precision, export_path);
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn run(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> Result<(), Box<dyn Error>> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue
+        };
+        if key.kind != KeyEventKind::Press {
+            continue
+        }
+        if app.editing_filter {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => app.editing_filter = false,
+                KeyCode::Backspace => { app.name_filter.pop(); }
+                KeyCode::Char(c) => app.name_filter.push(c),
+                _ => {}
+            }
+            app.match_state.select(None);
+            continue
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                app.focus = match app.focus {
+                    Focus::Inputs => Focus::Matches,
+                    Focus::Matches => Focus::Inputs,
+                }
+            }
+            KeyCode::Char('s') => {
+                app.sort = match app.sort {
+                    SortKey::Distance => SortKey::Name,
+                    SortKey::Name => SortKey::Distance,
+                };
+                app.match_state.select(None);
+            }
+            KeyCode::Char('/') => app.editing_filter = true,
+            KeyCode::Char('+') => app.adjust_max_distance(1.0),
+            KeyCode::Char('-') => app.adjust_max_distance(-1.0),
+            KeyCode::Char('e') => app.export(),
+            _ => {}
+        }
+    }
+}
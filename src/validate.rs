@@ -0,0 +1,69 @@
+/*
+    Sequence validation and normalization, as records are read
+        - whitespace anywhere in a sequence is removed and lowercase bases
+          are uppercased (unless --softmask reads the case), so they can't
+          throw off the composition counts and the prefilter
+        - U/T as the alphabet expects, see the rna module
+        - characters outside the alphabet are kept but reported
+        - every record changed or reported is logged with file:line and
+          counted in the warning summary
+    --strict skips the cleanup and rejects such records instead.
+*/
+use std::collections::BTreeSet;
+use log::warn;
+use crate::alphabet::Alphabet;
+use crate::iupac::{self, Ambiguity};
+use crate::softmask::{self, Softmask};
+use crate::warnings::{WarningKind, Warnings};
+use crate::{rna, Cli};
+
+/// A character a sequence may contain
+pub fn allowed(c: char, alphabet: &Alphabet, softmask: Option<Softmask>, ambiguity: Ambiguity) -> bool {
+    alphabet.contains(c) || softmask::allows(c, alphabet, softmask)
+        || ambiguity == Ambiguity::Aware && iupac::is_code(c)
+}
+
+/// The sequence of a record as it is compared
+pub fn sequence(seq: &str, name: &str, file: &str, line: u64, cli: &Cli, warnings: &mut Warnings) -> String {
+    if cli.strict {
+        return rna::normalized(seq, cli).into_owned()
+    }
+    let detail = || format!("{}:{} {}", file, line, name);
+    let mut changes = Vec::new();
+    let mut cleaned: String = seq.chars().filter(|c| !c.is_whitespace()).collect();
+    if cleaned.len() != seq.len() {
+        changes.push("removed whitespace");
+    }
+    if cli.softmask.is_none() && cleaned.chars().any(char::is_lowercase) {
+        cleaned = cleaned.to_uppercase();
+        changes.push("uppercased");
+    }
+    if !changes.is_empty() {
+        warn!("{}:{}: {} in the sequence of {}", file, line, changes.join(" and "), name);
+        warnings.add(WarningKind::NormalizedSequence, detail());
+    }
+    let cleaned = rna::normalized(&cleaned, cli).into_owned();
+    let invalid: BTreeSet<char> = cleaned.chars()
+        .filter(|&c| !allowed(c, &cli.alphabet, cli.softmask, cli.metric_options.ambiguity))
+        .collect();
+    if !invalid.is_empty() {
+        let invalid: String = invalid.into_iter().collect();
+        warn!("{}:{}: sequence of {} has characters outside the {} alphabet: {:?}", file, line, name,
+              cli.alphabet.name(), invalid);
+        warnings.add(WarningKind::InvalidCharacters, detail());
+    }
+    cleaned
+}
+
+/// The sequence as `sequence` returns it, without logging or counting
+/// anything, for rows that may not be read at all (see the sqlite module)
+pub fn normalized(seq: &str, cli: &Cli) -> String {
+    if cli.strict {
+        return rna::normalized(seq, cli).into_owned()
+    }
+    let mut cleaned: String = seq.chars().filter(|c| !c.is_whitespace()).collect();
+    if cli.softmask.is_none() {
+        cleaned = cleaned.to_uppercase();
+    }
+    rna::normalized(&cleaned, cli).into_owned()
+}
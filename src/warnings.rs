@@ -0,0 +1,77 @@
+/*
+    Warnings collected during a run
+        - printed as a grouped summary with counts at the end of the run,
+          independent of RUST_LOG
+        - recorded in the run manifest
+*/
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::Serialize;
+
+/// Examples listed per category in the summary
+const MAX_EXAMPLES: usize = 5;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Serialize)]
+pub enum WarningKind {
+    EmptySequence,
+    CleanedCharacters,
+    NormalizedSequence,
+    InvalidCharacters,
+    MetricSkipped,
+    Unbalanced,
+    NameConflict,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            WarningKind::EmptySequence => "rows skipped for empty sequence",
+            WarningKind::CleanedCharacters => "rows with invisible characters removed",
+            WarningKind::NormalizedSequence => "sequences uppercased or with whitespace removed",
+            WarningKind::InvalidCharacters => "sequences with characters outside the alphabet",
+            WarningKind::MetricSkipped => "metrics skipped for pairs they don't apply to",
+            WarningKind::Unbalanced => "inputs not screened for uneven composition",
+            WarningKind::NameConflict => "library rows whose name has another sequence",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(transparent)]
+pub struct Warnings {
+    entries: BTreeMap<WarningKind, Vec<String>>,
+}
+
+impl Warnings {
+    /// Record one occurrence, `detail` says where (file:line name)
+    pub fn add(&mut self, kind: WarningKind, detail: String) {
+        self.entries.entry(kind).or_default().push(detail)
+    }
+
+    /// Add the warnings of another file
+    pub fn merge(&mut self, other: Warnings) {
+        for (kind, details) in other.entries {
+            self.entries.entry(kind).or_default().extend(details)
+        }
+    }
+
+    /// Print the grouped summary to stderr, keeping stdout for results
+    pub fn print_summary(&self) {
+        if self.entries.is_empty() {
+            return
+        }
+        eprintln!("Warnings:");
+        for (kind, details) in &self.entries {
+            let mut examples = details.iter()
+                .take(MAX_EXAMPLES)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if details.len() > MAX_EXAMPLES {
+                examples.push_str(", ...");
+            }
+            eprintln!("  {:>6} {} ({})", details.len(), kind, examples)
+        }
+    }
+}
@@ -0,0 +1,159 @@
+/*
+    Sliding window scan of long inputs (--window LENGTH)
+        - every window of --window bases of an input, --window-step apart,
+          is screened like an ASO of its own: library ASOs within the
+          input's max distance by the --list-by metric, and the closest one
+        - and checked for liabilities: composition balance below
+          --min-balance (0.5 without it, over --balance-window bases), a
+          purine or pyrimidine run of LONG_RUN or more, a GC skew of
+          STRONG_SKEW or more in any third of the window (one liability)
+        - the score of a window is its library hits plus its liabilities,
+          lower is better; on a tie the window farther from the library is
+          better
+        - the --window-top best and worst windows of each input are
+          reported, an input no longer than the window is one window
+    Narrows down where in a candidate region to design. Inputs are read
+    as ASOs, 5' -> 3': give a target region as its reverse complement
+    (see the revcomp subcommand).
+*/
+use std::error::Error;
+use std::io::{self, Write};
+use log::info;
+use rayon::prelude::*;
+use crate::error::CheckError;
+use crate::{balance, liabilities, load_inputs, AsoProfile, Cli, Library, RunStats};
+
+/// Balance below which a window is flagged without --min-balance
+const DEFAULT_MIN_BALANCE: f32 = 0.5;
+/// Purine or pyrimidine run length that is a liability
+const LONG_RUN: usize = 6;
+/// GC skew, of either sign, that is a liability
+const STRONG_SKEW: f32 = 0.5;
+
+struct Window {
+    /// 0-based start in the input
+    start: usize,
+    seq: String,
+    hits: usize,
+    /// Closest library ASO and its distance
+    closest: Option<(String, f32)>,
+    liabilities: Vec<String>,
+}
+
+impl Window {
+    fn score(&self) -> usize {
+        self.hits + self.liabilities.len()
+    }
+
+    /// Better windows first: lower score, then farther from the library
+    fn rank(&self, other: &Window) -> std::cmp::Ordering {
+        let distance = |window: &Window| window.closest.as_ref().map_or(f32::INFINITY, |(_, distance)| *distance);
+        self.score().cmp(&other.score())
+            .then(distance(other).total_cmp(&distance(self)))
+            .then(self.start.cmp(&other.start))
+    }
+}
+
+fn window_liabilities(seq: &str, cli: &Cli) -> Vec<String> {
+    let mut found = Vec::new();
+    let min_balance = cli.min_balance.unwrap_or(DEFAULT_MIN_BALANCE);
+    let (score, start) = balance::balance(seq, cli.balance_window, &cli.alphabet);
+    if score < min_balance {
+        found.push(format!("balance {:.2} at {}-{}", score, start + 1,
+                           (start + cli.balance_window).min(seq.chars().count())));
+    }
+    let runs = [("purines", liabilities::longest_run(seq, liabilities::is_purine)),
+                ("pyrimidines", liabilities::longest_run(seq, liabilities::is_pyrimidine))];
+    for (name, (length, start)) in runs {
+        if length >= LONG_RUN {
+            found.push(format!("{} {} at {}-{}", length, name, start + 1, start + length));
+        }
+    }
+    let skewed: Vec<String> = ["5'", "middle", "3'"].iter().zip(liabilities::gc_skew(seq))
+        .filter_map(|(part, skew)| skew.filter(|skew| skew.abs() >= STRONG_SKEW)
+            .map(|skew| format!("{:+.2} {}", skew, part)))
+        .collect();
+    if !skewed.is_empty() {
+        found.push(format!("GC skew {}", skewed.join(", ")));
+    }
+    found
+}
+
+fn screen_window(input: &AsoProfile, start: usize, seq: String, library: &Library, cli: &Cli) -> Window {
+    let metric = cli.list_by.primary();
+    let max_distance = input.max_distance.or(cli.max_distance);
+    let mut hits = 0;
+    let mut closest: Option<(String, f32)> = None;
+    for aso in &library.asos {
+        let Ok(distance) = metric.distance(&seq, &aso.seq, &cli.metric_options) else {
+            continue
+        };
+        if max_distance.is_some_and(|max| distance <= max) {
+            hits += 1;
+        }
+        if closest.as_ref().is_none_or(|(_, nearest)| distance < *nearest) {
+            closest = Some((aso.name.clone(), distance));
+        }
+    }
+    let liabilities = window_liabilities(&seq, cli);
+    Window { start, seq, hits, closest, liabilities }
+}
+
+/// Every window of an input, screened, best first
+fn screen_input(input: &AsoProfile, library: &Library, cli: &Cli) -> Vec<Window> {
+    let bases: Vec<char> = input.seq.chars().collect();
+    let length = cli.window.unwrap_or(bases.len()).min(bases.len());
+    let starts: Vec<usize> = (0..=bases.len() - length).step_by(cli.window_step.max(1)).collect();
+    let mut windows: Vec<Window> = starts.into_par_iter()
+        .map(|start| screen_window(input, start, bases[start..start + length].iter().collect(), library, cli))
+        .collect();
+    windows.sort_by(|a, b| a.rank(b));
+    windows
+}
+
+fn print_windows(out: &mut dyn Write, input: &AsoProfile, windows: &[Window], cli: &Cli) -> io::Result<()> {
+    let best = cli.window_top.min(windows.len());
+    let worst = cli.window_top.min(windows.len() - best);
+    let shown = windows[..best].iter().map(|window| ("best", window))
+        .chain(windows[windows.len() - worst..].iter().rev().map(|window| ("worst", window)));
+    for (summary, window) in shown {
+        let closest = window.closest.as_ref()
+            .map_or(String::from("n/a"), |(name, distance)| {
+                format!("{} {}", name, cli.list_by.primary().format(*distance, cli.precision))
+            });
+        let liabilities = match window.liabilities.is_empty() {
+            true => String::from("none"),
+            false => window.liabilities.join(", "),
+        };
+        writeln!(out, "{:<10}\t{}-{}\t{:<20}\t{}\t{}\t{}\t{}\t{}", input.name, window.start + 1,
+                 window.start + window.seq.chars().count(), window.seq, summary, window.score(), window.hits,
+                 closest, liabilities)?;
+    }
+    Ok(())
+}
+
+pub fn scan_windows(cli: &Cli, library: &Library, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    if cli.window == Some(0) {
+        return Err(CheckError::Usage(String::from("--window must be at least 1 base")).into())
+    }
+    let (inputs, _) = load_inputs(cli, &mut RunStats::default())?;
+    info!("Screening the windows of {} inputs against {} library ASOs", inputs.len(), library.asos.len());
+    let mut report = || -> io::Result<()> {
+        if !cli.raw {
+            writeln!(out, "{:<10}\tWindow\t{:<20}\tSummary\tScore\tLibrary hits\tClosest\tLiabilities", "Input ASO",
+                     "Seq")?;
+        }
+        for input in &inputs {
+            let windows = screen_input(input, library, cli);
+            print_windows(out, input, &windows, cli)?;
+        }
+        out.flush()
+    };
+    // a closed pipe (e.g. `| head`) is not an error
+    if let Err(e) = report() {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e.into())
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+/*
+    Excel workbook libraries (--library-format xlsx, --sheet)
+        - one worksheet, the first unless --sheet names another, read with
+          the csv semantics: name in column1, sequence in column2, a
+          header row unless --library-no-header, other columns kept for
+          --group-by and --library-orientation mixed
+        - cells are read as their text, numbers as written (1001, not
+          1001.0); empty rows are skipped like blank csv lines
+        - the line of a row in messages is its row number in the sheet
+        - auto reads files ending in .xlsx or .xlsm as workbooks
+    The workbook is read into memory whole, the zip format needs seeking.
+*/
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+use calamine::{Data, Reader, Xlsx};
+use csv::{Position, StringRecord};
+use log::info;
+use crate::error::CheckError;
+
+/// Whether `path` has the extension of an Excel workbook
+pub fn has_workbook_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("xlsx") || extension.eq_ignore_ascii_case("xlsm"))
+}
+
+/// Header row (when `has_headers`) and data rows of a sheet of the
+/// workbook in `bytes`, each positioned at its row number
+pub fn read_records(bytes: Vec<u8>, sheet: Option<&str>, has_headers: bool, label: &str)
+    -> Result<(Option<StringRecord>, Vec<StringRecord>), Box<dyn Error>> {
+    let mut workbook = Xlsx::new(Cursor::new(bytes))
+        .map_err(|e| CheckError::Data(format!("{}: not a readable xlsx workbook: {}", label, e)))?;
+    let names = workbook.sheet_names();
+    let sheet = match sheet {
+        Some(sheet) if names.iter().any(|name| name == sheet) => sheet.to_string(),
+        Some(sheet) => return Err(CheckError::Usage(format!("{}: no sheet named {:?}, the sheets are {}", label,
+                                                           sheet, names.join(", "))).into()),
+        None => names.first()
+            .ok_or(CheckError::Data(format!("{}: workbook without sheets", label)))?
+            .clone(),
+    };
+    info!("{}: reading sheet {:?}", label, sheet);
+    let range = workbook.worksheet_range(&sheet)
+        .map_err(|e| CheckError::Data(format!("{}: unable to read sheet {:?}: {}", label, sheet, e)))?;
+    // the range starts at the first used cell, rows and columns keep their place in the sheet
+    let (first_row, first_column) = range.start().map_or((0, 0), |(row, column)| (row as u64, column as usize));
+    let mut rows = range.rows()
+        .enumerate()
+        .filter(|(_, cells)| cells.iter().any(|cell| *cell != Data::Empty))
+        .map(|(i, cells)| {
+            // the range is as wide as the widest row
+            let width = cells.iter().rposition(|cell| *cell != Data::Empty).map_or(0, |last| last + 1);
+            let mut record: StringRecord = std::iter::repeat_n(String::new(), first_column)
+                .chain(cells[..width].iter().map(|cell| cell.to_string()))
+                .collect();
+            let mut position = Position::new();
+            position.set_line(first_row + i as u64 + 1);
+            record.set_position(Some(position));
+            record
+        });
+    let headers = match has_headers {
+        true => rows.next(),
+        false => None,
+    };
+    Ok((headers, rows.collect()))
+}
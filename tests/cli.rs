@@ -0,0 +1,450 @@
+/*
+    End-to-end tests of the commandline tool
+        - every test writes its files to a scratch directory of its own and
+          runs the built binary there, asserting on what it prints and
+          its exit code
+        - the check in its output modes (table, raw, json), with --window,
+          --duplex, a sqlite library, a library snapshot and an earlier
+          manifest
+        - every subcommand: batch, calibrate, compare-runs,
+          export-approved, revcomp, selftest and validate
+        - --tui only as far as it goes without a terminal
+    Exit codes are those of the error module: 2 usage, 6 data.
+*/
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use rusqlite::Connection;
+
+const LIBRARY: &str = "name,seq\nL1,ACGTTGCAAC\nL2,ACGTTGCATC\nL3,GGGCCCAAAT\nL4,TTGACCGTAG\n";
+const INPUTS: &str = "name,seq\nq1,ACGTTGCAAC\nq2,GGGCCCAAAA\n";
+
+/// Empty directory for the files of a test
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aso_scramble_check-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("scratch directory");
+    dir
+}
+
+fn write(dir: &Path, name: &str, contents: &str) {
+    fs::write(dir.join(name), contents).expect("test file");
+}
+
+/// Run the tool in `dir`, with no terminal and no logging
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aso_scramble_check"))
+        .current_dir(dir)
+        .args(args)
+        .env_remove("RUST_LOG")
+        .stdin(Stdio::null())
+        .output()
+        .expect("run aso_scramble_check")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// Stdout of a run that must succeed
+fn success(dir: &Path, args: &[&str]) -> String {
+    let output = run(dir, args);
+    assert!(output.status.success(), "{:?} failed: {}", args, stderr(&output));
+    stdout(&output)
+}
+
+fn sqlite_library(dir: &Path, rows: &[(&str, &str)]) {
+    let connection = Connection::open(dir.join("lib.db")).expect("database");
+    connection.execute("CREATE TABLE asos (name TEXT, seq TEXT)", []).expect("table");
+    for (name, seq) in rows {
+        connection.execute("INSERT INTO asos (name, seq) VALUES (?1, ?2)", [name, seq]).expect("row");
+    }
+}
+
+const LIBRARY_ROWS: [(&str, &str); 4] = [("L1", "ACGTTGCAAC"), ("L2", "ACGTTGCATC"), ("L3", "GGGCCCAAAT"),
+                                         ("L4", "TTGACCGTAG")];
+
+#[test]
+fn table_lists_matches_under_their_input() {
+    let dir = scratch("table");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let table = success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                                "--prefilter", "length", "--max-distance", "2"]);
+    let lines: Vec<&str> = table.lines().collect();
+    assert!(lines[0].starts_with("Input ASO"));
+    assert!(lines[1].starts_with("q1"));
+    assert!(lines[2].contains("L2") && lines[2].trim_end().ends_with('1'));
+    assert!(lines[3].starts_with("q2"));
+    assert!(lines[4].contains("L3"));
+    assert_eq!(lines.len(), 5);
+}
+
+#[test]
+fn raw_rows_carry_the_verdict() {
+    let dir = scratch("raw");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", "name,seq\nq1,ACGTTGCAAC\nq3,CCCCCCCCCC\n");
+    let raw = success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                              "--prefilter", "length", "--max-distance", "2", "--verdict", "--raw"]);
+    assert_eq!(raw, "q1\tACGTTGCAAC\tL2\tACGTTGCATC\t1\tVerdict: REVIEW\n\
+                     q3\tCCCCCCCCCC\t\t\t\tVerdict: PASS\n");
+}
+
+#[test]
+fn window_scan_reads_csv_and_sqlite_alike() {
+    let dir = scratch("window");
+    write(&dir, "lib.csv", LIBRARY);
+    sqlite_library(&dir, &LIBRARY_ROWS);
+    write(&dir, "long.csv", "name,seq\nlong1,GGACGTTGCAACTTGACCGTAGAA\n");
+    let scan = |library: &str| success(&dir, &["--multiple-aso-seq", "--input-aso-file", "long.csv",
+                                               "--library-aso-file", library, "--window", "10", "--window-top", "2",
+                                               "--max-distance", "2"]);
+    let csv = scan("lib.csv");
+    assert_eq!(csv, scan("sqlite://lib.db?table=asos"));
+    let worst: Vec<&str> = csv.lines().filter(|line| line.contains("\tworst\t")).collect();
+    assert_eq!(worst.len(), 2);
+    assert!(worst[0].starts_with("long1     \t3-12\tACGTTGCAAC"));
+    assert!(worst[0].contains("\t3\t2\tL1 0\t"));
+}
+
+#[test]
+fn window_of_zero_bases_is_a_usage_error() {
+    let dir = scratch("window-zero");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let output = run(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                             "--window", "0"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--window must be at least 1 base"));
+}
+
+#[test]
+fn sqlite_prefilter_reads_only_rows_of_the_input_lengths() {
+    let dir = scratch("sqlite-pushdown");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let mut rows = LIBRARY_ROWS.to_vec();
+    rows.extend([("S1", "ACGT"), ("S2", "ACGTTGCAACGT")]);
+    sqlite_library(&dir, &rows);
+    let check = |library: &str, prefilter: &str| Command::new(env!("CARGO_BIN_EXE_aso_scramble_check"))
+        .current_dir(&dir)
+        .args(["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", library,
+               "--prefilter", prefilter, "--max-distance", "2", "--raw"])
+        .env("RUST_LOG", "info")
+        .output()
+        .expect("run aso_scramble_check");
+    let pushed = check("sqlite://lib.db?table=asos", "length");
+    assert!(stderr(&pushed).contains("read 4 rows from table asos"), "{}", stderr(&pushed));
+    assert_eq!(stdout(&pushed), stdout(&check("lib.csv", "length")));
+    assert_eq!(stdout(&pushed), "q1\tACGTTGCAAC\tL2\tACGTTGCATC\t1\nq2\tGGGCCCAAAA\tL3\tGGGCCCAAAT\t1\n");
+    let every_row = check("sqlite://lib.db?table=asos", "none");
+    assert!(stderr(&every_row).contains("read 6 rows from table asos"), "{}", stderr(&every_row));
+}
+
+#[test]
+fn sqlite_prefilter_reads_rows_as_the_csv_library_would() {
+    let dir = scratch("sqlite-pushdown-rows");
+    write(&dir, "in.csv", INPUTS);
+    let mut rows = LIBRARY_ROWS.to_vec();
+    rows.extend([("S1", "acgttgcaca"), ("S2", "ACGT TGCCAA"), ("S3", "ACGUUGCAAA"), ("S4", "GGGCCCAAUA")]);
+    sqlite_library(&dir, &rows);
+    let csv: String = rows.iter().map(|(name, seq)| format!("{},{}\n", name, seq)).collect();
+    write(&dir, "lib.csv", &format!("name,seq\n{}", csv));
+    let check = |library: &str, prefilter: &str, orientation: &str| Command::new(env!("CARGO_BIN_EXE_aso_scramble_check"))
+        .current_dir(&dir)
+        .args(["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", library,
+               "--prefilter", prefilter, "--library-orientation", orientation, "--max-distance", "2", "--raw"])
+        .env("RUST_LOG", "info")
+        .output()
+        .expect("run aso_scramble_check");
+    for prefilter in ["length", "composition"] {
+        for orientation in ["antisense", "sense"] {
+            let pushed = check("sqlite://lib.db?table=asos", prefilter, orientation);
+            assert!(pushed.status.success(), "{}", stderr(&pushed));
+            assert_eq!(stdout(&pushed), stdout(&check("lib.csv", prefilter, orientation)), "{} {}", prefilter,
+                       orientation);
+        }
+    }
+    // L1, S1 and S2 have the composition of q1, no row that of q2
+    let pushed = check("sqlite://lib.db?table=asos", "composition", "antisense");
+    assert!(stderr(&pushed).contains("read 3 rows from table asos"), "{}", stderr(&pushed));
+    assert_eq!(stdout(&pushed), "q1\tACGTTGCAAC\tS1\tACGTTGCACA\t2\nq1\tACGTTGCAAC\tS2\tACGTTGCCAA\t2\n");
+}
+
+#[test]
+fn results_csv_keeps_annotations_out_of_the_distance_column() {
+    let dir = scratch("results-csv");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let check = ["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv", "--prefilter",
+                 "length", "--max-distance", "1", "--list-by", "hamming", "--verdict", "--composition-delta",
+                 "--results-file", "results.csv"];
+    success(&dir, &check);
+    let results = fs::read_to_string(dir.join("results.csv")).expect("results file");
+    let mut rows = results.lines();
+    assert_eq!(rows.next(), Some("run_id,input,input_seq,match,match_seq,metric,distance,annotation,value"));
+    let rows: Vec<Vec<&str>> = rows.map(|row| row.split(',').skip(1).collect()).collect();
+    assert!(rows.contains(&vec!["q1", "ACGTTGCAAC", "", "", "", "", "Verdict", "REVIEW"]), "{}", results);
+    assert!(rows.contains(&vec!["q1", "ACGTTGCAAC", "L2", "ACGTTGCATC", "Hamming", "1", "", ""]), "{}", results);
+    assert!(rows.iter().any(|row| row[2] == "L2" && row[6] == "Composition delta"), "{}", results);
+    assert!(rows.iter().all(|row| row[5].is_empty() || row[5].parse::<f64>().is_ok()), "{}", results);
+    fs::write(dir.join("results.csv"), "run_id,input,input_seq,match,match_seq,metric,distance\n").expect("old file");
+    let output = run(&dir, &[&check[..], &["--append"]].concat());
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("has other columns"), "{}", stderr(&output));
+}
+
+#[test]
+fn snapshot_round_trip_gives_the_same_results() {
+    let dir = scratch("snapshot");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let check = ["--multiple-aso-seq", "--input-aso-file", "in.csv", "--prefilter", "length", "--max-distance", "2",
+                 "--raw"];
+    let from_csv = success(&dir, &[&check[..], &["--library-aso-file", "lib.csv"]].concat());
+    success(&dir, &["--library-aso-file", "lib.csv", "--export-library-snapshot", "lib.snap"]);
+    let from_snapshot = success(&dir, &[&check[..], &["--library-snapshot", "lib.snap"]].concat());
+    assert_eq!(from_csv, from_snapshot);
+    assert!(!from_snapshot.is_empty());
+
+    let snapshot = fs::read(dir.join("lib.snap")).expect("snapshot");
+    fs::write(dir.join("cut.snap"), &snapshot[..snapshot.len() / 2]).expect("truncated snapshot");
+    let output = run(&dir, &[&check[..], &["--library-snapshot", "cut.snap"]].concat());
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("truncated library snapshot"));
+}
+
+#[test]
+fn duplex_checks_both_strands() {
+    let dir = scratch("duplex");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "dx.csv", "name,guide,note,passenger\nd1,ACGTTGCAAC,first,GATGCAACGT\n");
+    let raw = success(&dir, &["--multiple-aso-seq", "--input-aso-file", "dx.csv", "--library-aso-file", "lib.csv",
+                              "--prefilter", "length", "--max-distance", "2", "--duplex",
+                              "--passenger-column", "passenger", "--raw"]);
+    let lines: Vec<&str> = raw.lines().collect();
+    assert!(lines.iter().any(|line| line.starts_with("d1\tACGTTGCAAC\tL2\tACGTTGCATC\t1")));
+    assert!(lines.iter().any(|line| line.starts_with("d1_passenger\tGATGCAACGT\t")));
+    assert!(lines.iter().all(|line| line.contains("Duplex: ")));
+}
+
+#[test]
+fn passenger_column_must_differ_from_the_sequence() {
+    let dir = scratch("duplex-column");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "dx.csv", "name,guide,passenger\nd1,ACGTTGCAAC,GTTGCAACGT\n");
+    let output = run(&dir, &["--multiple-aso-seq", "--input-aso-file", "dx.csv", "--library-aso-file", "lib.csv",
+                             "--duplex", "--passenger-column", "guide"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn unchanged_previous_run_is_reused_and_a_new_mask_is_not() {
+    let dir = scratch("incremental");
+    write(&dir, "lib.csv", "name,seq\nX,AAATTTTTTT\nY,GGGGGGGGGG\n");
+    write(&dir, "in.csv", "name,seq\nC,TTTTTTTTTT\n");
+    write(&dir, "mask.csv", "name,start,end\nC,1,3\n");
+    let check = ["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                 "--prefilter", "length", "--max-distance", "0", "--mask-file", "mask.csv", "--raw"];
+    let first = success(&dir, &[&check[..], &["--manifest", "run.json"]].concat());
+    assert_eq!(first, "C\tTTTTTTTTTT\tX\tAAATTTTTTT\t0\n");
+    assert_eq!(success(&dir, &[&check[..], &["--previous-manifest", "run.json"]].concat()), first);
+    write(&dir, "mask.csv", "name,start,end\nC,9,10\n");
+    assert_eq!(success(&dir, &[&check[..], &["--previous-manifest", "run.json"]].concat()), "");
+}
+
+#[test]
+fn run_stopped_early_is_not_reused() {
+    let dir = scratch("incremental-partial");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let check = ["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                 "--prefilter", "length", "--max-distance", "2", "--raw"];
+    let partial = run(&dir, &[&check[..], &["--max-runtime", "0s", "--manifest", "run.json"]].concat());
+    assert_eq!(partial.status.code(), Some(4));
+    assert_eq!(stdout(&partial), "");
+    let output = run(&dir, &[&check[..], &["--previous-manifest", "run.json"]].concat());
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("stopped early"));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn export_approved_orders_every_member_of_an_identical_group() {
+    let dir = scratch("approved");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", "name,seq\nA,GGGTTTAAAC\nB,GGGTTTAAAC\nq1,ACGTTGCAAC\n");
+    success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                    "--prefilter", "length", "--max-distance", "2", "--output-format", "json", "-o", "run.json"]);
+    let results = fs::read_to_string(dir.join("run.json")).expect("results");
+    assert!(results.contains("\"name\": \"A\""));
+    assert!(results.contains("\"Identical\": \"B\""));
+    let output = run(&dir, &["export-approved", "run.json", "--scale", "25nm", "--chemistry", "PS"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Name,Sequence,Scale,Chemistry\nA,GGGTTTAAAC,25nm,PS\nB,GGGTTTAAAC,25nm,PS\n");
+    assert!(stderr(&output).contains("2 of 3 candidates approved"));
+}
+
+#[test]
+fn export_approved_folds_strands_into_their_candidate() {
+    let dir = scratch("approved-strands");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", "name,seq\nI1,GTTGCAACGT\nI2,GTTGCAACGT\nI3,CCCCGGGGAA\n");
+    success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                    "--prefilter", "length", "--max-distance", "2", "--orientation", "both", "--output-format", "json",
+                    "-o", "run.json"]);
+    // the reverse complements of I1 and I2 match L1 and L2, that of I3 nothing
+    let output = run(&dir, &["export-approved", "run.json", "--scale", "25nm", "--chemistry", "PS"]);
+    assert_eq!(stdout(&output), "Name,Sequence,Scale,Chemistry\nI3,CCCCGGGGAA,25nm,PS\n");
+    assert!(stderr(&output).contains("1 of 3 candidates approved"));
+    let output = run(&dir, &["export-approved", "run.json", "--scale", "25nm", "--chemistry", "PS",
+                             "--accept", "pass", "--accept", "review"]);
+    assert_eq!(stdout(&output), "Name,Sequence,Scale,Chemistry\nI1,GTTGCAACGT,25nm,PS\nI2,GTTGCAACGT,25nm,PS\n\
+                                 I3,CCCCGGGGAA,25nm,PS\n");
+    assert!(stderr(&output).contains("3 of 3 candidates approved"));
+}
+
+#[test]
+fn compare_runs_reports_disappeared_matches() {
+    let dir = scratch("compare-runs");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    for (max_distance, results) in [("2", "old.json"), ("0.5", "new.json")] {
+        success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                        "--prefilter", "length", "--max-distance", max_distance, "--output-format", "json",
+                        "-o", results]);
+    }
+    let report = success(&dir, &["compare-runs", "old.json", "new.json"]);
+    assert!(report.lines().any(|line| line.starts_with("q1") && line.contains("L2") && line.contains("disappeared")));
+    assert!(report.ends_with("# 0 appeared, 2 disappeared, 0 changed, 0 unchanged\n"));
+}
+
+#[test]
+fn compare_runs_follows_inputs_into_identical_groups() {
+    let dir = scratch("compare-runs-groups");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "old.csv", "name,seq\nA,ACGTTGCAAC\nC,GGGCCCAAAA\n");
+    write(&dir, "new.csv", "name,seq\nB,ACGTTGCAAC\nA,ACGTTGCAAC\nC,GGGCCCAAAA\n");
+    for input in ["old", "new"] {
+        success(&dir, &["--multiple-aso-seq", "--input-aso-file", &format!("{}.csv", input), "--library-aso-file",
+                        "lib.csv", "--prefilter", "length", "--max-distance", "3", "--output-format", "json",
+                        "-o", &format!("{}.json", input)]);
+    }
+    // B, grouped with A under the name B, is new; A is unchanged
+    let report = success(&dir, &["compare-runs", "old.json", "new.json"]);
+    assert_eq!(report.lines().filter(|line| !line.starts_with("Input ASO") && !line.starts_with('#')).count(), 1);
+    assert!(report.lines().any(|line| line.starts_with("B ") && line.contains("appeared")));
+    assert!(report.ends_with("# 1 appeared, 0 disappeared, 0 changed, 2 unchanged\n"));
+}
+
+#[test]
+fn compare_runs_refuses_matches_cut_by_max_hits() {
+    let dir = scratch("compare-runs-max-hits");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    for (max_hits, results) in [("100", "all.json"), ("1", "cut.json")] {
+        success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                        "--prefilter", "length", "--max-distance", "10", "--max-hits", max_hits,
+                        "--output-format", "json", "-o", results]);
+    }
+    let output = run(&dir, &["compare-runs", "all.json", "cut.json"]);
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("--max-hits"));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn calibrate_writes_a_profile_for_the_calibrated_preset() {
+    let dir = scratch("calibrate");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    write(&dir, "pairs.csv", "seq_a,seq_b,outcome\nACGTTGCAAC,ACGTTGCATC,1\nACGTTGCAAC,ACGTTGAATC,1\n\
+                              ACGTTGCAAC,TTTTGGGGCC,0\nGGGCCCAAAT,GGGCCCAAAA,yes\nGGGCCCAAAT,ACACACACAC,no\n");
+    let fits = success(&dir, &["calibrate", "pairs.csv", "--profile", "cal.toml"]);
+    assert!(fits.lines().any(|line| line.starts_with("Hamming\t2\t5\t1.000\t1.000")));
+    let profile = fs::read_to_string(dir.join("cal.toml")).expect("profile");
+    assert!(profile.starts_with("metric = \"Hamming\"\nmax_distance = 2.0\n"));
+    let raw = success(&dir, &["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv",
+                              "--prefilter", "length", "--preset", "calibrated", "--calibration", "cal.toml", "--raw"]);
+    assert_eq!(raw.lines().count(), 2);
+}
+
+#[test]
+fn batch_runs_every_job() {
+    let dir = scratch("batch");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    write(&dir, "jobs.toml", "[[job]]\nmultiple-aso-seq = true\ninput-aso-file = \"in.csv\"\n\
+                              library-aso-file = \"lib.csv\"\nprefilter = \"length\"\nmax-distance = 2\nraw = true\n\n\
+                              [[job]]\naso-seq = \"GGGCCCAAAA\"\nlibrary-aso-file = \"lib.csv\"\n\
+                              prefilter = \"length\"\nmax-distance = 2\nraw = true\n");
+    let raw = success(&dir, &["batch", "jobs.toml"]);
+    assert_eq!(raw, "q1\tACGTTGCAAC\tL2\tACGTTGCATC\t1\nq2\tGGGCCCAAAA\tL3\tGGGCCCAAAT\t1\n\
+                     testASO_001\tGGGCCCAAAA\tL3\tGGGCCCAAAT\t1\n");
+}
+
+#[test]
+fn revcomp_keeps_the_layout_of_csv_and_fasta() {
+    let dir = scratch("revcomp");
+    write(&dir, "rc.csv", "name,seq\nr1,AACCGGTTAG\n");
+    assert_eq!(success(&dir, &["revcomp", "rc.csv"]), "name,seq\nr1,CTAACCGGTT\n");
+    write(&dir, "eu.csv", "seq;name\n# a note\nAACCGGTTAG;r1\n");
+    assert_eq!(success(&dir, &["revcomp", "eu.csv", "--delimiter", ";", "--seq-column", "seq"]),
+               "seq;name\nCTAACCGGTT;r1\n");
+    write(&dir, "rc.fa", ">r1 note\nAACCG\nGTTAG\n");
+    assert_eq!(success(&dir, &["revcomp", "rc.fa"]), ">r1\nCTAACCGGTT\n");
+    write(&dir, "rc.fq", "@r1\nACGT\n+\nIIII\n");
+    assert_eq!(run(&dir, &["revcomp", "rc.fq"]).status.code(), Some(2));
+}
+
+#[test]
+fn validate_reports_every_row() {
+    let dir = scratch("validate");
+    write(&dir, "lib.csv", LIBRARY);
+    assert!(success(&dir, &["validate", "lib.csv"]).ends_with("4 rows: 0 with errors, 0 with warnings only\n"));
+    write(&dir, "bad.csv", "name,seq\nv1,ACGT\nv1,ACXT\nv3,\n");
+    let output = run(&dir, &["validate", "bad.csv"]);
+    assert_eq!(output.status.code(), Some(6));
+    let report = stdout(&output);
+    assert!(report.contains("3\tv1\terror\tduplicate name, first on line 2"));
+    assert!(report.contains("4\tv3\terror\tempty sequence"));
+}
+
+#[test]
+fn validate_reads_columns_dialect_and_fasta() {
+    let dir = scratch("validate-layout");
+    write(&dir, "sn.csv", "seq|name\n'ACGT'|v1\n");
+    let report = success(&dir, &["validate", "sn.csv", "--name-column", "name", "--seq-column", "seq",
+                                 "--delimiter", "|", "--quote-char", "'"]);
+    assert!(report.contains("2\tv1\tok"));
+    write(&dir, "v.fa", ">v1 first\nACGT\n>v2\nACXT\n");
+    let output = run(&dir, &["validate", "v.fa"]);
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stdout(&output).contains("3\tv2\terror\tcharacters outside the dna alphabet"));
+}
+
+#[test]
+fn tui_needs_a_terminal_and_no_output_file() {
+    let dir = scratch("tui");
+    write(&dir, "lib.csv", LIBRARY);
+    write(&dir, "in.csv", INPUTS);
+    let check = ["--multiple-aso-seq", "--input-aso-file", "in.csv", "--library-aso-file", "lib.csv"];
+    let output = run(&dir, &[&check[..], &["--tui", "--tui-export", "shown.csv"]].concat());
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--tui needs an interactive terminal"));
+    assert!(stdout(&output).is_empty());
+    assert!(!dir.join("shown.csv").exists());
+    assert_eq!(run(&dir, &[&check[..], &["--tui", "-o", "out.txt"]].concat()).status.code(), Some(2));
+    assert_eq!(run(&dir, &[&check[..], &["--tui-export", "shown.csv"]].concat()).status.code(), Some(2));
+}
+
+#[test]
+fn selftest_passes() {
+    let dir = scratch("selftest");
+    assert!(success(&dir, &["selftest"]).lines().last().is_some_and(|line| line.ends_with("cases passed")));
+}
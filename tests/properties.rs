@@ -0,0 +1,172 @@
+/*
+    Property tests of the library API
+        - parsers: any bytes, and generated csv, FASTA and FASTQ records
+          with hostile contents, load or fail with an error, never panic
+        - metrics: every metric on any two strings returns without
+          panicking; identity, symmetry and the triangle inequality hold
+          where the metric promises them
+        - comparisons: generated libraries and inputs compare under the
+          prefilters and ambiguity modes without panicking
+    The fuzz/ directory holds cargo-fuzz targets for the same entry points.
+*/
+use aso_scramble_check::{Cli, Dist, Library, MetricOptions, Query};
+use proptest::prelude::*;
+
+const METRICS: [Dist; 11] = [Dist::Hamming, Dist::Levenshtein, Dist::Sift3, Dist::Damerau, Dist::Needleman,
+                             Dist::SmithWaterman, Dist::Qgram, Dist::Cosine, Dist::D2, Dist::D2s, Dist::Lcs];
+
+fn cli(args: &[&str]) -> Cli {
+    Cli::from_args(std::iter::once("aso_scramble_check").chain(args.iter().copied())).expect("valid arguments")
+}
+
+/// Bases, IUPAC codes, soft-masked bases, and a little of everything a
+/// pasted sequence can contain
+fn sequence() -> impl Strategy<Value = String> {
+    let symbol = prop_oneof![
+        8 => prop::sample::select(vec!['A', 'C', 'G', 'T']),
+        2 => prop::sample::select(vec!['a', 'c', 'g', 't', 'U', 'N', 'R', 'Y']),
+        1 => prop::sample::select(vec![' ', '\t', '-', '*', '0', 'é', 'ß', '\u{200B}', '\u{FEFF}', '"', ',', ';']),
+    ];
+    prop::collection::vec(symbol, 0..40).prop_map(|symbols| symbols.into_iter().collect())
+}
+
+fn dna() -> impl Strategy<Value = String> {
+    "[ACGT]{0,30}"
+}
+
+fn name() -> impl Strategy<Value = String> {
+    prop_oneof![
+        8 => "[A-Za-z][A-Za-z0-9_]{0,8}",
+        1 => Just(String::new()),
+        1 => "[ -~]{0,6}",
+        1 => ".{0,4}",
+    ]
+}
+
+/// Library or input file text: a header or not, rows of any width,
+/// blank lines, stray quotes, and either delimiter
+fn csv_text() -> impl Strategy<Value = String> {
+    let row = (name(), sequence(), prop::collection::vec("[ -~]{0,5}", 0..3));
+    (any::<bool>(), prop::sample::select(vec![",", ";", "\t"]), prop::collection::vec(row, 0..12))
+        .prop_map(|(header, delimiter, rows)| {
+            let mut text = String::new();
+            if header {
+                text.push_str(&format!("name{}seq{}max_distance\n", delimiter, delimiter));
+            }
+            for (name, seq, extra) in rows {
+                let fields: Vec<String> = [name, seq].into_iter().chain(extra).collect();
+                text.push_str(&fields.join(delimiter));
+                text.push('\n');
+            }
+            text
+        })
+}
+
+fn fasta_text() -> impl Strategy<Value = String> {
+    prop::collection::vec((name(), prop::collection::vec(sequence(), 0..3)), 0..8).prop_map(|records| {
+        records.into_iter()
+            .map(|(name, lines)| format!(">{}\n{}\n", name, lines.join("\n")))
+            .collect()
+    })
+}
+
+fn fastq_text() -> impl Strategy<Value = String> {
+    prop::collection::vec((name(), sequence(), any::<bool>()), 0..8).prop_map(|records| {
+        records.into_iter()
+            .map(|(name, seq, matching)| {
+                let qualities = "I".repeat(if matching { seq.len() } else { seq.len() / 2 });
+                format!("@{}\n{}\n+\n{}\n", name, seq, qualities)
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn library_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Library::from_reader(bytes.as_slice(), "fuzz", &cli(&[]));
+    }
+
+    #[test]
+    fn input_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Query::from_reader(bytes.as_slice(), "fuzz", &cli(&["-m"]));
+    }
+
+    #[test]
+    fn records_never_panic(text in prop_oneof![csv_text(), fasta_text(), fastq_text()],
+                           strict in any::<bool>(), softmask in any::<bool>()) {
+        let mut args = vec!["-m"];
+        if strict {
+            args.push("--strict");
+        }
+        if softmask {
+            args.extend(["--softmask", "ignore"]);
+        }
+        let cli = cli(&args);
+        let _ = Library::from_reader(text.as_bytes(), "fuzz", &cli);
+        let _ = Query::from_reader(text.as_bytes(), "fuzz", &cli);
+    }
+
+    #[test]
+    fn valid_rows_load(rows in prop::collection::vec(("[A-Z][0-9]{1,4}", "[ACGT]{1,30}"), 1..20)) {
+        let text: String = rows.iter().map(|(name, seq)| format!("{},{}\n", name, seq)).collect();
+        let library = Library::from_reader(format!("name,seq\n{}", text).as_bytes(), "fuzz", &cli(&[]));
+        prop_assert_eq!(library.map(|library| library.entries().count()).ok(), Some(rows.len()));
+    }
+
+    #[test]
+    fn metrics_never_panic(a in ".{0,24}", b in sequence()) {
+        for metric in METRICS {
+            if let Ok(distance) = metric.distance(&a, &b, &MetricOptions::default()) {
+                prop_assert!(!distance.is_nan(), "{:?} of {:?} and {:?} is NaN", metric, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn identical_sequences_are_at_distance_zero(a in dna()) {
+        for metric in METRICS {
+            prop_assert_eq!(metric.distance(&a, &a, &MetricOptions::default()), Ok(0.0), "{:?} of {:?}", metric, a);
+        }
+    }
+
+    #[test]
+    fn edit_distances_are_symmetric(a in dna(), b in dna()) {
+        let options = MetricOptions::default();
+        for metric in [Dist::Hamming, Dist::Levenshtein, Dist::Damerau, Dist::Qgram, Dist::Lcs, Dist::D2, Dist::D2s] {
+            prop_assert_eq!(metric.distance(&a, &b, &options), metric.distance(&b, &a, &options), "{:?}", metric);
+        }
+    }
+
+    #[test]
+    fn levenshtein_is_a_metric(a in dna(), b in dna(), c in dna()) {
+        let distance = |x: &str, y: &str| Dist::Levenshtein.distance(x, y, &MetricOptions::default()).unwrap();
+        prop_assert!(distance(&a, &c) <= distance(&a, &b) + distance(&b, &c));
+        prop_assert!(distance(&a, &b) >= a.len().abs_diff(b.len()) as f32);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn comparisons_never_panic(library in prop::collection::vec((name(), sequence()), 0..30),
+                               inputs in prop::collection::vec((name(), sequence()), 1..5),
+                               options in prop::sample::select(vec![
+                                   vec!["--list-by", "all", "--prefilter", "none"],
+                                   vec!["--prefilter", "auto", "--max-distance", "2"],
+                                   vec!["--prefilter", "length", "--ambiguity", "aware"],
+                                   vec!["--softmask", "downweight", "--prefilter", "none"],
+                               ])) {
+        let cli = cli(&options);
+        let library: Vec<(&str, &str)> = library.iter().map(|(name, seq)| (name.as_str(), seq.as_str())).collect();
+        let inputs: Vec<(&str, &str)> = inputs.iter().map(|(name, seq)| (name.as_str(), seq.as_str())).collect();
+        if let (Ok(library), Ok(query)) = (Library::from_sequences(&library, &cli), Query::from_sequences(&inputs, &cli)) {
+            for input in query.compare(&library, &cli) {
+                for hit in input.matches() {
+                    prop_assert!(!hit.distance().is_nan());
+                }
+            }
+        }
+    }
+}